@@ -0,0 +1,22 @@
+// Records the git commit and the build time for GET /version, as GIT_COMMIT and BUILD_TIME.
+use std::process::Command;
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn main() {
+    let commit = git(&["rev-parse", "--short=12", "HEAD"]).unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=GIT_COMMIT={}", commit);
+    println!("cargo:rustc-env=BUILD_TIME={}", chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+
+    // Rerun when HEAD moves to another commit or branch, rather than on every source change
+    if let Some(git_dir) = git(&["rev-parse", "--git-dir"]) {
+        println!("cargo:rerun-if-changed={}/HEAD", git_dir);
+        if let Some(head_ref) = git(&["symbolic-ref", "-q", "HEAD"]) {
+            println!("cargo:rerun-if-changed={}/{}", git_dir, head_ref);
+        }
+    }
+    println!("cargo:rerun-if-changed=build.rs");
+}
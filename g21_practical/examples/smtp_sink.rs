@@ -0,0 +1,72 @@
+// Local SMTP sink for trying out email notifications without a real mail server.
+//
+//   cargo run --example smtp_sink
+//   SMTP_HOST=127.0.0.1 SMTP_PORT=2525 cargo run
+//
+// Listens on 127.0.0.1:2525 (override with SMTP_SINK_PORT), accepts every message and prints it.
+// STARTTLS is not supported, so leave SMTP_STARTTLS unset.
+use std::env;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+fn handle(stream: TcpStream) -> std::io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    writer.write_all(b"220 smtp-sink ready\r\n")?;
+
+    let mut envelope = Vec::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let command = line.trim_end().to_ascii_uppercase();
+
+        let reply: &[u8] = if command.starts_with("EHLO") || command.starts_with("HELO") {
+            b"250-smtp-sink\r\n250 AUTH PLAIN\r\n"
+        } else if command.starts_with("AUTH") {
+            b"235 accepted\r\n"
+        } else if command.starts_with("MAIL") || command.starts_with("RCPT") {
+            envelope.push(line.trim_end().to_string());
+            b"250 ok\r\n"
+        } else if command == "DATA" {
+            writer.write_all(b"354 end with <CRLF>.<CRLF>\r\n")?;
+            let mut message = String::new();
+            loop {
+                let mut data = String::new();
+                if reader.read_line(&mut data)? == 0 || data == ".\r\n" {
+                    break;
+                }
+                message.push_str(data.strip_prefix('.').unwrap_or(&data));
+            }
+            println!("==== {} ====\n{}", envelope.join(" "), message);
+            envelope.clear();
+            b"250 queued\r\n"
+        } else if command == "QUIT" {
+            writer.write_all(b"221 bye\r\n")?;
+            return Ok(());
+        } else if command == "RSET" || command == "NOOP" {
+            b"250 ok\r\n"
+        } else {
+            b"502 command not implemented\r\n"
+        };
+        writer.write_all(reply)?;
+    }
+}
+
+fn main() -> std::io::Result<()> {
+    let port: u16 = env::var("SMTP_SINK_PORT").ok().and_then(|p| p.parse().ok()).unwrap_or(2525);
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    println!("SMTP sink listening on 127.0.0.1:{}", port);
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        thread::spawn(move || {
+            if let Err(e) = handle(stream) {
+                eprintln!("connection error: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
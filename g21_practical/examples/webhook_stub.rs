@@ -0,0 +1,71 @@
+// Local webhook receiver for trying out webhooks without an external service.
+//
+//   WEBHOOK_SECRET=<secret from POST /webhooks> cargo run --example webhook_stub
+//   WEBHOOKS_ALLOW_PRIVATE_HOSTS=true cargo run    (the stub listens on a private address)
+//
+// Listens on 127.0.0.1:9000 (override with STUB_PORT), prints every delivery and whether its
+// X-Webhook-Signature matches. Set STUB_FAIL=3 to answer the first 3 requests with 500 and watch the retries.
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::env;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct Stub {
+    secret: Option<String>,
+    fail_first: usize,
+    received: AtomicUsize,
+}
+
+async fn receive(stub: web::Data<Stub>, req: HttpRequest, body: web::Bytes) -> HttpResponse {
+    let count = stub.received.fetch_add(1, Ordering::SeqCst) + 1;
+    let header = |name: &str| {
+        req.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("-")
+            .to_string()
+    };
+
+    let signature = header("X-Webhook-Signature");
+    let verified = match &stub.secret {
+        Some(secret) => {
+            let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+            mac.update(&body);
+            let expected = format!("sha256={}", hex::encode(mac.finalize().into_bytes()));
+            if expected == signature { "valid" } else { "INVALID" }
+        }
+        None => "not checked (WEBHOOK_SECRET unset)",
+    };
+
+    println!(
+        "#{} {} delivery={} signature={}\n{}\n",
+        count,
+        header("X-Webhook-Event"),
+        header("X-Webhook-Delivery"),
+        verified,
+        String::from_utf8_lossy(&body)
+    );
+
+    if count <= stub.fail_first {
+        HttpResponse::InternalServerError().body("failing on purpose")
+    } else {
+        HttpResponse::Ok().body("ok")
+    }
+}
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    let port: u16 = env::var("STUB_PORT").ok().and_then(|p| p.parse().ok()).unwrap_or(9000);
+    let stub = web::Data::new(Stub {
+        secret: env::var("WEBHOOK_SECRET").ok(),
+        fail_first: env::var("STUB_FAIL").ok().and_then(|n| n.parse().ok()).unwrap_or(0),
+        received: AtomicUsize::new(0),
+    });
+
+    println!("Webhook stub listening on http://127.0.0.1:{}/", port);
+    HttpServer::new(move || App::new().app_data(stub.clone()).default_service(web::post().to(receive)))
+        .bind(("127.0.0.1", port))?
+        .run()
+        .await
+}
@@ -0,0 +1,30 @@
+-- Normalise free-text severities entered before severity became an enum
+UPDATE bugReport SET severity = lower(trim(severity));
+UPDATE bugReport SET severity = 'critical' WHERE severity IN ('crit', 'critcal', 'blocker', 'severe');
+UPDATE bugReport SET severity = 'high' WHERE severity IN ('hi', 'hgih', 'major');
+UPDATE bugReport SET severity = 'medium' WHERE severity IN ('med', 'meduim', 'moderate', 'normal');
+UPDATE bugReport SET severity = 'low' WHERE severity IN ('lo', 'minor', 'trivial');
+UPDATE bugReport SET severity = 'medium' WHERE severity NOT IN ('low', 'medium', 'high', 'critical');
+
+-- SQLite cannot add CHECK constraints to an existing table, so rebuild bugReport with them
+CREATE TABLE bugReport_new (
+    id BLOB PRIMARY KEY,
+    title TEXT NOT NULL UNIQUE,
+    description TEXT NOT NULL,
+    project_id BLOB NOT NULL,
+    reported_by BLOB NOT NULL,
+    fixed_by BLOB,
+    severity TEXT NOT NULL CHECK (severity IN ('low', 'medium', 'high', 'critical')),
+    priority TEXT NOT NULL DEFAULT 'medium' CHECK (priority IN ('low', 'medium', 'high', 'urgent')),
+    is_fixed BOOLEAN DEFAULT FALSE,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY(project_id) REFERENCES projectRecord(id),
+    FOREIGN KEY(reported_by) REFERENCES users(id),
+    FOREIGN KEY(fixed_by) REFERENCES users(id)
+);
+
+INSERT INTO bugReport_new (id, title, description, project_id, reported_by, fixed_by, severity, is_fixed, created_at)
+SELECT id, title, description, project_id, reported_by, fixed_by, severity, is_fixed, created_at FROM bugReport;
+
+DROP TABLE bugReport;
+ALTER TABLE bugReport_new RENAME TO bugReport;
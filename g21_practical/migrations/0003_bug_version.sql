@@ -0,0 +1,2 @@
+-- Version counter for optimistic concurrency control, incremented on every update and exposed as the ETag
+ALTER TABLE bugReport ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
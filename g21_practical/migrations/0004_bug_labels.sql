@@ -0,0 +1,10 @@
+-- Free-form labels attached to bug reports, e.g. "regression" or "ui"
+CREATE TABLE bugLabel (
+    bug_id BLOB NOT NULL,
+    label TEXT NOT NULL,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (bug_id, label),
+    FOREIGN KEY(bug_id) REFERENCES bugReport(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_bugLabel_label ON bugLabel(label);
@@ -0,0 +1,32 @@
+-- Outgoing webhook subscriptions, one or more per project
+CREATE TABLE webhook (
+    id BLOB PRIMARY KEY,
+    project_id BLOB NOT NULL,
+    url TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    events TEXT NOT NULL DEFAULT '*', -- Comma-separated event names, '*' for every event
+    created_by BLOB NOT NULL,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY(project_id) REFERENCES projectRecord(id) ON DELETE CASCADE,
+    FOREIGN KEY(created_by) REFERENCES users(id)
+);
+
+-- Delivery queue and log: pending rows are sent by the webhook worker and retried with backoff
+CREATE TABLE webhookDelivery (
+    id BLOB PRIMARY KEY,
+    webhook_id BLOB NOT NULL,
+    event TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'delivered', 'failed')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_status_code INTEGER,
+    last_error TEXT,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    delivered_at TEXT,
+    FOREIGN KEY(webhook_id) REFERENCES webhook(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_webhook_project ON webhook(project_id);
+CREATE INDEX idx_webhookDelivery_due ON webhookDelivery(status, next_attempt_at);
+CREATE INDEX idx_webhookDelivery_webhook ON webhookDelivery(webhook_id, created_at);
@@ -0,0 +1,35 @@
+-- Email addresses for users; optional, but users without one receive no email
+ALTER TABLE users ADD COLUMN email TEXT;
+CREATE UNIQUE INDEX idx_users_email ON users(email);
+
+-- Per-user email preferences. Users without a row get the defaults: every email, sent immediately
+CREATE TABLE notificationPreference (
+    user_id BLOB PRIMARY KEY,
+    email_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    notify_assigned BOOLEAN NOT NULL DEFAULT TRUE,
+    notify_status_changed BOOLEAN NOT NULL DEFAULT TRUE,
+    notify_commented BOOLEAN NOT NULL DEFAULT TRUE,
+    notify_mentioned BOOLEAN NOT NULL DEFAULT TRUE,
+    digest TEXT NOT NULL DEFAULT 'off' CHECK (digest IN ('off', 'hourly', 'daily')),
+    FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- Outgoing emails waiting to be sent by the email worker. Digest emails wait until send_after and are then
+-- combined into one message per user
+CREATE TABLE emailOutbox (
+    id BLOB PRIMARY KEY,
+    user_id BLOB NOT NULL,
+    recipient TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    body TEXT NOT NULL,
+    digest BOOLEAN NOT NULL DEFAULT FALSE,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'sent', 'failed')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    send_after TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_error TEXT,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    sent_at TEXT,
+    FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_emailOutbox_due ON emailOutbox(status, send_after);
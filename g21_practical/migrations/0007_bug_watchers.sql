@@ -0,0 +1,15 @@
+-- Users following a bug. Reporters and assignees are added automatically, anyone else through /bugs/{id}/watch
+CREATE TABLE bugWatcher (
+    bug_id BLOB NOT NULL,
+    user_id BLOB NOT NULL,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (bug_id, user_id),
+    FOREIGN KEY(bug_id) REFERENCES bugReport(id) ON DELETE CASCADE,
+    FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_bugWatcher_user ON bugWatcher(user_id);
+
+-- Existing reporters and assignees watch their bugs
+INSERT OR IGNORE INTO bugWatcher (bug_id, user_id) SELECT id, reported_by FROM bugReport;
+INSERT OR IGNORE INTO bugWatcher (bug_id, user_id) SELECT id, fixed_by FROM bugReport WHERE fixed_by IS NOT NULL;
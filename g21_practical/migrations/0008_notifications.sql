@@ -0,0 +1,17 @@
+-- In-app notifications shown in a user's inbox, e.g. "admin assigned you ...". read_at is NULL while unread
+CREATE TABLE notification (
+    id BLOB PRIMARY KEY,
+    user_id BLOB NOT NULL,
+    kind TEXT NOT NULL,
+    bug_id BLOB,
+    actor_id BLOB,
+    message TEXT NOT NULL,
+    read_at TEXT,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY(bug_id) REFERENCES bugReport(id) ON DELETE CASCADE,
+    FOREIGN KEY(actor_id) REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_notification_user ON notification(user_id, read_at);
+CREATE INDEX idx_notification_created ON notification(created_at);
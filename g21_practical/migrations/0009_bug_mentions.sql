@@ -0,0 +1,11 @@
+-- Users @mentioned in a bug's description. Kept in sync with the description whenever it changes
+CREATE TABLE bugMention (
+    bug_id BLOB NOT NULL,
+    user_id BLOB NOT NULL,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (bug_id, user_id),
+    FOREIGN KEY(bug_id) REFERENCES bugReport(id) ON DELETE CASCADE,
+    FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_bugMention_user ON bugMention(user_id);
@@ -0,0 +1,17 @@
+-- Typed links between bugs, read as "<source> <kind> <target>", e.g. "A blocks B". relates_to is symmetric
+-- and stored once
+CREATE TABLE bugLink (
+    id BLOB PRIMARY KEY,
+    source_bug_id BLOB NOT NULL,
+    target_bug_id BLOB NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('duplicate_of', 'blocks', 'relates_to', 'caused_by')),
+    created_by BLOB,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (source_bug_id, target_bug_id, kind),
+    CHECK (source_bug_id <> target_bug_id),
+    FOREIGN KEY(source_bug_id) REFERENCES bugReport(id) ON DELETE CASCADE,
+    FOREIGN KEY(target_bug_id) REFERENCES bugReport(id) ON DELETE CASCADE,
+    FOREIGN KEY(created_by) REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_bugLink_target ON bugLink(target_bug_id);
@@ -0,0 +1,35 @@
+-- Deleted bugs stay in the table, hidden from every query, until they are restored or purged from the trash.
+-- A bug in the trash must not keep its title from being used again, so titles are only unique among live bugs.
+-- SQLite cannot drop the UNIQUE constraint of a column, so rebuild bugReport without it
+CREATE TABLE bugReport_new (
+    id BLOB PRIMARY KEY,
+    title TEXT NOT NULL,
+    description TEXT NOT NULL,
+    project_id BLOB NOT NULL,
+    reported_by BLOB NOT NULL,
+    fixed_by BLOB,
+    severity TEXT NOT NULL CHECK (severity IN ('low', 'medium', 'high', 'critical')),
+    priority TEXT NOT NULL DEFAULT 'medium' CHECK (priority IN ('low', 'medium', 'high', 'urgent')),
+    is_fixed BOOLEAN DEFAULT FALSE,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    version INTEGER NOT NULL DEFAULT 1,
+    deleted_at TEXT,
+    deleted_by BLOB,
+    FOREIGN KEY(project_id) REFERENCES projectRecord(id),
+    FOREIGN KEY(reported_by) REFERENCES users(id),
+    FOREIGN KEY(fixed_by) REFERENCES users(id),
+    FOREIGN KEY(deleted_by) REFERENCES users(id) ON DELETE SET NULL
+);
+
+INSERT INTO bugReport_new (id, title, description, project_id, reported_by, fixed_by, severity, priority, is_fixed, created_at, version)
+SELECT id, title, description, project_id, reported_by, fixed_by, severity, priority, is_fixed, created_at, version FROM bugReport;
+
+DROP TABLE bugReport;
+ALTER TABLE bugReport_new RENAME TO bugReport;
+
+CREATE INDEX idx_bugReport_deleted ON bugReport(deleted_at);
+CREATE UNIQUE INDEX idx_bugReport_title ON bugReport(title) WHERE deleted_at IS NULL;
+
+-- Administrators may browse the trash and restore any bug in it. Nobody is one yet on an existing database;
+-- promote a user with `g21_practical make-admin <username>`
+ALTER TABLE users ADD COLUMN is_admin BOOLEAN NOT NULL DEFAULT FALSE;
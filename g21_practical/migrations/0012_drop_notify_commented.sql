@@ -0,0 +1,2 @@
+-- Bugs have no comments, so the preference for comment emails never had any effect
+ALTER TABLE notificationPreference DROP COLUMN notify_commented;
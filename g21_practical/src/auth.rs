@@ -1,21 +1,25 @@
 use actix_web::{Error, Result};
 use jsonwebtoken::{encode, decode, Header, Validation, EncodingKey, DecodingKey};
 use serde::{Serialize, Deserialize};
-use std::env;
+use sqlx::SqlitePool;
 use uuid::Uuid;
 use bcrypt::{hash, verify, DEFAULT_COST};
 
 // For access control middleware
 use actix_web::{
+    body::EitherBody,
     dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
     HttpMessage,
+    web,
     http::header,
-    error::ErrorUnauthorized,
 };
 use futures_util::future::LocalBoxFuture;
 use std::future::{ready, Ready};
 use std::rc::Rc;
 
+use crate::config::{AuthConfig, Config};
+use crate::error::AppError;
+
 pub struct AuthMiddleware;
 
 impl<S, B> Transform<S, ServiceRequest> for AuthMiddleware
@@ -24,7 +28,7 @@ where
     S::Future: 'static,
     B: 'static,
 {
-    type Response = ServiceResponse<B>;
+    type Response = ServiceResponse<EitherBody<B>>;
     type Error = Error;
     type InitError = ();
     type Transform = AuthMiddlewareMiddleware<S>;
@@ -47,7 +51,7 @@ where
     S::Future: 'static,
     B: 'static,
 {
-    type Response = ServiceResponse<B>;
+    type Response = ServiceResponse<EitherBody<B>>;
     type Error = Error;
     type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
 
@@ -57,45 +61,67 @@ where
         let service = self.service.clone();
 
         Box::pin(async move {
+            let config = req.app_data::<web::Data<Config>>().cloned().expect("Config is registered as app data");
+
             // Extract Authorization header
             let auth_header = req.headers().get(header::AUTHORIZATION);
             
-            if let Some(header_value) = auth_header {
-                if let Ok(auth_str) = header_value.to_str() {
-                    if auth_str.starts_with("Bearer ") {
-                        let token = &auth_str[7..];
-                        
-                        // Validate the token and extract user ID
-                        if validate_token(token) {
-                            if let Some(user_id) = extract_user_id_from_token(token) {
-                                // Store user ID in request extensions for use in handlers
-                                req.extensions_mut().insert(user_id);
-                                
-                                // Continue with the request
-                                let fut = service.call(req);
-                                return fut.await;
-                            }
-                        }
-                    }
-                }
+            if let Some(header_value) = auth_header
+                && let Ok(auth_str) = header_value.to_str()
+                && let Some(token) = auth_str.strip_prefix("Bearer ")
+                // Validate the token and extract user ID
+                && validate_token(token, &config.auth)
+                && let Some(user_id) = extract_user_id_from_token(token, &config.auth)
+            {
+                // Store user ID in request extensions for use in handlers
+                req.extensions_mut().insert(user_id);
+
+                // Continue with the request
+                let fut = service.call(req);
+                return fut.await.map(ServiceResponse::map_into_left_body);
             }
             
-            // If we reach here, authentication failed
-            Err(ErrorUnauthorized("Authentication required"))
+            // If we reach here, authentication failed. from_err keeps the AppError on the response for the metrics.
+            let error = AppError::Unauthorized("Authentication required".to_string());
+            Ok(ServiceResponse::from_err(error, req.into_parts().0).map_into_right_body())
         })
     }
 }
 
-// Helper function to extract user ID from request extensions
-pub fn get_user_id_from_request(req: &ServiceRequest) -> Option<Uuid> {
-    req.extensions().get::<Uuid>().copied()
-}
-
 // Helper function for use in handlers
 pub fn get_authenticated_user_id(req: &actix_web::HttpRequest) -> Option<Uuid> {
     req.extensions().get::<Uuid>().copied()
 }
 
+// User id from a valid bearer token, for public routes that can do more for a logged in user
+pub fn bearer_user_id(req: &actix_web::HttpRequest) -> Option<Uuid> {
+    let config = req.app_data::<web::Data<Config>>().expect("Config is registered as app data");
+    extract_user_id_from_token(bearer_token(req)?, &config.auth)
+}
+
+// The token of an `Authorization: Bearer ...` header, not yet validated
+pub fn bearer_token(req: &actix_web::HttpRequest) -> Option<&str> {
+    req.headers().get(header::AUTHORIZATION)?.to_str().ok()?.strip_prefix("Bearer ")
+}
+
+// Whether the user is an administrator
+pub async fn is_admin(pool: &SqlitePool, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar::<_, bool>("SELECT is_admin FROM users WHERE id = ?")
+        .bind(user_id.as_bytes().as_slice())
+        .fetch_optional(pool)
+        .await
+        .map(|is_admin| is_admin.unwrap_or(false))
+}
+
+// Makes the user an administrator. Returns false if there is no user with that name.
+pub async fn make_admin(pool: &SqlitePool, username: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE users SET is_admin = TRUE WHERE username = ?")
+        .bind(username)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
 // Define a struct called `Claims` that will hold the data to be encoded into the JWT.
 // This struct derives `Serialize` and `Deserialize` traits to facilitate JSON conversion.
 #[derive(Serialize, Deserialize)]
@@ -106,10 +132,10 @@ struct Claims {
 
 // A public function that creates a JWT token for a given user ID.
 // It takes a `Uuid` parameter representing the user's unique identifier and returns a String (the JWT).
-pub fn create_token(user_id: Uuid) -> String {
-    // Calculate expiration time for the token. This example sets it to one hour from the current time.
+pub fn create_token(user_id: Uuid, config: &AuthConfig) -> String {
+    // Calculate expiration time for the token, auth.token_lifetime_minutes (an hour by default) from now.
     let expiration = chrono::Utc::now()
-        .checked_add_signed(chrono::Duration::hours(1))
+        .checked_add_signed(chrono::Duration::minutes(config.token_lifetime_minutes))
         .unwrap() // Ensure the addition operation doesn't fail.
         .timestamp() as usize; // Convert the expiration time to a `usize`.
 
@@ -119,39 +145,45 @@ pub fn create_token(user_id: Uuid) -> String {
         exp: expiration,  // Set the expiration time.
     };
 
-    // Encode the claims into a JWT using a default header and a secret key.
+    // Encode the claims into a JWT using a default header and the configured secret key (auth.jwt_secret).
     // The secret key can be any byte array, but it should be kept secure and private.
     encode(
         &Header::default(), // Use default JWT header settings.
         &claims,            // Pass in the claims data.
-        &EncodingKey::from_secret(b"secretkey"), // Secret key for encoding.
+        &EncodingKey::from_secret(config.jwt_secret.as_bytes()), // Secret key for encoding.
     )
     .unwrap() // Unwrap the result, assuming encoding is successful.
 }
 
 // A public function that validates a JWT token.
 // It takes a string slice representing the token and returns a boolean indicating if the token is valid.
-pub fn validate_token(token: &str) -> bool {
+pub fn validate_token(token: &str, config: &AuthConfig) -> bool {
     // Attempt to decode the token using the same secret key used for encoding and default validation settings.
     decode::<Claims>(
         token,                                 // The JWT to be decoded.
-        &DecodingKey::from_secret(b"secretkey"), // Secret key must match the one used during encoding.
+        &DecodingKey::from_secret(config.jwt_secret.as_bytes()), // Secret key must match the one used during encoding.
         &Validation::default(),               // Use default validation parameters.
     )
     .is_ok() // Check if the decoding operation was successful.
 }
 
 // Function to extract user ID from a valid JWT token
-pub fn extract_user_id_from_token(token: &str) -> Option<Uuid> {
+pub fn extract_user_id_from_token(token: &str, config: &AuthConfig) -> Option<Uuid> {
+    decode_token(token, config).map(|(user_id, _)| user_id)
+}
+
+// User ID and expiration time (seconds since the epoch) of a valid JWT token, for connections that outlive a request
+pub fn decode_token(token: &str, config: &AuthConfig) -> Option<(Uuid, i64)> {
     // Decode the token
     let token_data = decode::<Claims>(
         token,
-        &DecodingKey::from_secret(b"secretkey"),
+        &DecodingKey::from_secret(config.jwt_secret.as_bytes()),
         &Validation::default(),
     ).ok()?;
 
     // Parse the subject (user ID) from the claims
-    Uuid::parse_str(&token_data.claims.sub).ok()
+    let user_id = Uuid::parse_str(&token_data.claims.sub).ok()?;
+    Some((user_id, token_data.claims.exp as i64))
 }
 
 // Hash password with salt
@@ -0,0 +1,609 @@
+// Server settings, layered from lowest to highest precedence:
+// 1. the defaults below,
+// 2. a TOML file: `--config FILE`, else CONFIG_FILE, else `config.toml` when it exists (see config.example.toml),
+// 3. environment variables (also read from `.env`),
+// 4. command line flags given before the subcommand, e.g. `g21_practical --port 9000 import bugs.csv`.
+// The result is validated once at startup and shared with the handlers as `web::Data<Config>`.
+use derive_more::Display;
+use lettre::message::Mailbox;
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tracing_subscriber::EnvFilter;
+
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+// The secret tokens were signed with before it became configurable; accepted, but with a warning.
+pub const DEFAULT_JWT_SECRET: &str = "secretkey";
+
+// Overrides one setting with a value given by a source (the variable or flag, named in errors).
+type Setter = fn(&mut Config, &str, &str) -> Result<(), ConfigError>;
+
+// Settings that can be overridden, by their key in the TOML file, environment variable and command line flag.
+// Secrets (the JWT secret, the SMTP password) have no flag, as command lines are visible to every user of the machine.
+const OVERRIDES: &[(&str, &str, Option<&str>, Setter)] = &[
+    ("server.host", "SERVER_HOST", Some("--host"), |c, s, v| parse(&mut c.server.host, s, v)),
+    ("server.port", "SERVER_PORT", Some("--port"), |c, s, v| parse(&mut c.server.port, s, v)),
+    ("server.shutdown_timeout_secs", "SHUTDOWN_TIMEOUT_SECS", Some("--shutdown-timeout"), |c, s, v| {
+        parse(&mut c.server.shutdown_timeout_secs, s, v)
+    }),
+    ("database.url", "DATABASE_URL", Some("--database-url"), |c, s, v| parse(&mut c.database.url, s, v)),
+    ("database.max_connections", "DATABASE_MAX_CONNECTIONS", Some("--max-connections"), |c, s, v| {
+        parse(&mut c.database.max_connections, s, v)
+    }),
+    ("database.migrations_dir", "MIGRATIONS_DIR", Some("--migrations-dir"), |c, s, v| {
+        parse(&mut c.database.migrations_dir, s, v)
+    }),
+    ("tls.cert_path", "TLS_CERT_PATH", Some("--tls-cert"), |c, s, v| parse_some(&mut c.tls.cert_path, s, v)),
+    ("tls.key_path", "TLS_KEY_PATH", Some("--tls-key"), |c, s, v| parse_some(&mut c.tls.key_path, s, v)),
+    ("tls.redirect_port", "TLS_REDIRECT_PORT", Some("--tls-redirect-port"), |c, s, v| {
+        parse_some(&mut c.tls.redirect_port, s, v)
+    }),
+    ("tls.hsts_max_age_secs", "TLS_HSTS_MAX_AGE_SECS", Some("--hsts-max-age"), |c, s, v| {
+        parse(&mut c.tls.hsts_max_age_secs, s, v)
+    }),
+    ("auth.jwt_secret", "JWT_SECRET", None, |c, s, v| parse(&mut c.auth.jwt_secret, s, v)),
+    ("auth.token_lifetime_minutes", "TOKEN_LIFETIME_MINUTES", Some("--token-lifetime-minutes"), |c, s, v| {
+        parse(&mut c.auth.token_lifetime_minutes, s, v)
+    }),
+    ("logging.format", "LOG_FORMAT", Some("--log-format"), |c, s, v| parse(&mut c.logging.format, s, v)),
+    ("logging.filter", "RUST_LOG", Some("--log-filter"), |c, s, v| parse(&mut c.logging.filter, s, v)),
+    // An empty SMTP_HOST turns email off, as an unset one does
+    ("email.smtp_host", "SMTP_HOST", Some("--smtp-host"), |c, _, v| {
+        c.email.smtp_host = Some(v.to_string()).filter(|host| !host.is_empty());
+        Ok(())
+    }),
+    ("email.smtp_port", "SMTP_PORT", Some("--smtp-port"), |c, s, v| parse(&mut c.email.smtp_port, s, v)),
+    ("email.smtp_username", "SMTP_USERNAME", Some("--smtp-username"), |c, s, v| {
+        parse_some(&mut c.email.smtp_username, s, v)
+    }),
+    ("email.smtp_password", "SMTP_PASSWORD", None, |c, s, v| parse_some(&mut c.email.smtp_password, s, v)),
+    ("email.smtp_starttls", "SMTP_STARTTLS", Some("--smtp-starttls"), |c, s, v| parse(&mut c.email.smtp_starttls, s, v)),
+    ("email.from", "SMTP_FROM", Some("--smtp-from"), |c, s, v| parse(&mut c.email.from, s, v)),
+    ("email.base_url", "APP_BASE_URL", Some("--base-url"), |c, s, v| parse(&mut c.email.base_url, s, v)),
+    ("retention.trash_days", "TRASH_RETENTION_DAYS", Some("--trash-retention-days"), |c, s, v| {
+        parse(&mut c.retention.trash_days, s, v)
+    }),
+    ("retention.notification_days", "NOTIFICATION_RETENTION_DAYS", Some("--notification-retention-days"), |c, s, v| {
+        parse(&mut c.retention.notification_days, s, v)
+    }),
+    ("webhooks.allow_private_hosts", "WEBHOOKS_ALLOW_PRIVATE_HOSTS", Some("--webhooks-allow-private-hosts"), |c, s, v| {
+        parse(&mut c.webhooks.allow_private_hosts, s, v)
+    }),
+];
+
+#[derive(Debug, Display)]
+pub enum ConfigError {
+    #[display(fmt = "Cannot read config file {}: {}", "_0.display()", _1)]
+    Read(PathBuf, std::io::Error),
+
+    #[display(fmt = "Invalid config file {}: {}", "_0.display()", _1)]
+    Parse(PathBuf, String),
+
+    // An environment variable or flag whose value has the wrong type
+    #[display(fmt = "Invalid value {:?} for {}: {}", _1, _0, _2)]
+    Value(String, String, String),
+
+    #[display(fmt = "{}", _0)]
+    Usage(String),
+
+    // `--help` was given; displays the usage
+    #[display(fmt = "{}", "usage()")]
+    Help,
+
+    // Every setting that failed validation, one per line
+    #[display(fmt = "Invalid configuration:\n  - {}", "_0.join(\"\\n  - \")")]
+    Invalid(Vec<String>),
+}
+
+impl std::error::Error for ConfigError {}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub tls: TlsConfig,
+    pub database: DatabaseConfig,
+    pub auth: AuthConfig,
+    pub logging: LoggingConfig,
+    pub email: EmailConfig,
+    pub retention: RetentionConfig,
+    pub webhooks: WebhooksConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    pub shutdown_timeout_secs: u64, // How long a shutdown waits for requests and background tasks to finish
+}
+
+// HTTPS is served when both cert_path and key_path are set; the files are read again on SIGHUP.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    pub cert_path: Option<PathBuf>, // PEM certificate chain, the server's certificate first
+    pub key_path: Option<PathBuf>,  // PEM private key (PKCS#8, RSA or EC)
+    pub redirect_port: Option<u16>, // Plain HTTP port redirecting every request to HTTPS, e.g. 80
+    pub hsts_max_age_secs: u64,     // max-age of the Strict-Transport-Security header, 0 to leave the header out
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: String, // e.g. `sqlite://bugs.db?mode=rwc` to keep data in a file
+    pub max_connections: u32,
+    pub migrations_dir: PathBuf,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    pub jwt_secret: String,
+    pub token_lifetime_minutes: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    pub format: LogFormat,
+    pub filter: String, // RUST_LOG syntax, e.g. `info,g21_practical=debug`
+}
+
+// Email notifications are sent through this SMTP relay when smtp_host is set.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmailConfig {
+    pub smtp_host: Option<String>,
+    pub smtp_port: u16,
+    pub smtp_username: Option<String>, // With smtp_password, enables AUTH
+    pub smtp_password: Option<String>,
+    pub smtp_starttls: bool, // Upgrade the connection before anything is sent
+    pub from: String,
+    pub base_url: String, // Address of the server, for links in emails, e.g. `https://bugs.example.com`
+}
+
+// Days before deleted data is removed for good by the hourly cleanup tasks.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetentionConfig {
+    pub trash_days: i64,        // Bugs in the trash
+    pub notification_days: i64, // In-app notifications, read or not
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebhooksConfig {
+    // Lets webhooks reach loopback, private and link-local addresses, e.g. the local webhook stub. Anyone who can
+    // add a webhook may then probe the server's network, so keep it off in production.
+    pub allow_private_hosts: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Pretty,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            _ => Err("expected \"pretty\" or \"json\"".to_string()),
+        }
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig { host: "127.0.0.1".to_string(), port: 8080, shutdown_timeout_secs: 30 }
+    }
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        TlsConfig { cert_path: None, key_path: None, redirect_port: None, hsts_max_age_secs: 365 * 24 * 60 * 60 }
+    }
+}
+
+impl TlsConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.cert_path.is_some() && self.key_path.is_some()
+    }
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        DatabaseConfig {
+            url: "sqlite::memory:?cache=shared".to_string(),
+            max_connections: 5,
+            migrations_dir: PathBuf::from("migrations"),
+        }
+    }
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        AuthConfig { jwt_secret: DEFAULT_JWT_SECRET.to_string(), token_lifetime_minutes: 60 }
+    }
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        LoggingConfig { format: LogFormat::Pretty, filter: "info".to_string() }
+    }
+}
+
+impl Default for EmailConfig {
+    fn default() -> Self {
+        EmailConfig {
+            smtp_host: None,
+            smtp_port: 25,
+            smtp_username: None,
+            smtp_password: None,
+            smtp_starttls: false,
+            from: "bugtracker@localhost".to_string(),
+            base_url: "http://localhost:8080".to_string(),
+        }
+    }
+}
+
+impl EmailConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.smtp_host.is_some()
+    }
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        RetentionConfig { trash_days: 30, notification_days: 90 }
+    }
+}
+
+
+// Parses `value` as the type of `field`, naming `source` (the variable or flag) in the error.
+fn parse<T: FromStr>(field: &mut T, source: &str, value: &str) -> Result<(), ConfigError>
+where
+    T::Err: std::fmt::Display,
+{
+    *field = value
+        .parse()
+        .map_err(|e: T::Err| ConfigError::Value(source.to_string(), value.to_string(), e.to_string()))?;
+    Ok(())
+}
+
+// `parse` for a setting that is unset by default.
+fn parse_some<T: FromStr>(field: &mut Option<T>, source: &str, value: &str) -> Result<(), ConfigError>
+where
+    T::Err: std::fmt::Display,
+{
+    let parsed = value
+        .parse()
+        .map_err(|e: T::Err| ConfigError::Value(source.to_string(), value.to_string(), e.to_string()))?;
+    *field = Some(parsed);
+    Ok(())
+}
+
+impl Config {
+    // Builds the configuration from the file, the environment and `args` (without the program name).
+    // Returns it with the arguments following the flags, i.e. the subcommand and its own arguments.
+    pub fn load(args: &[String]) -> Result<(Config, Vec<String>), ConfigError> {
+        Config::load_from(args, |variable| env::var(variable).ok())
+    }
+
+    // `load` with the environment variables looked up through `var`.
+    fn load_from(args: &[String], var: impl Fn(&str) -> Option<String>) -> Result<(Config, Vec<String>), ConfigError> {
+        let mut config_file = None;
+        let mut flags = Vec::new();
+
+        let mut rest = args.iter();
+        let remaining = loop {
+            let Some(arg) = rest.next() else { break Vec::new() };
+            if !arg.starts_with("--") {
+                break std::iter::once(arg).chain(rest).cloned().collect();
+            }
+            if arg == "--help" {
+                return Err(ConfigError::Help);
+            }
+            let setter = match OVERRIDES.iter().find(|(_, _, flag, _)| *flag == Some(arg.as_str())) {
+                Some((_, _, _, setter)) => Some(*setter),
+                None if arg == "--config" => None,
+                None => return Err(ConfigError::Usage(format!("unknown option {}\n{}", arg, usage()))),
+            };
+            let value = rest.next().ok_or_else(|| ConfigError::Usage(format!("{} needs a value", arg)))?;
+            match setter {
+                Some(setter) => flags.push((setter, arg.as_str(), value.as_str())),
+                None => config_file = Some(PathBuf::from(value)),
+            }
+        };
+
+        let mut config = match config_file.or_else(|| var("CONFIG_FILE").map(PathBuf::from)) {
+            Some(path) => Config::from_file(&path)?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => Config::from_file(Path::new(DEFAULT_CONFIG_FILE))?,
+            None => Config::default(),
+        };
+
+        for (_, variable, _, setter) in OVERRIDES {
+            if let Some(value) = var(variable) {
+                setter(&mut config, variable, &value)?;
+            }
+        }
+        for (setter, flag, value) in flags {
+            setter(&mut config, flag, value)?;
+        }
+
+        config.validate()?;
+        Ok((config, remaining))
+    }
+
+    fn from_file(path: &Path) -> Result<Config, ConfigError> {
+        let text = fs::read_to_string(path).map_err(|e| ConfigError::Read(path.to_path_buf(), e))?;
+        toml::from_str(&text).map_err(|e| ConfigError::Parse(path.to_path_buf(), e.message().to_string()))
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+
+        if self.server.host.trim().is_empty() {
+            problems.push("server.host must not be empty".to_string());
+        }
+        if self.server.port == 0 {
+            problems.push("server.port must be between 1 and 65535".to_string());
+        }
+        if self.server.shutdown_timeout_secs > 3600 {
+            problems.push("server.shutdown_timeout_secs must be at most 3600 (an hour)".to_string());
+        }
+        if self.tls.cert_path.is_some() != self.tls.key_path.is_some() {
+            problems.push("tls.cert_path and tls.key_path must be set together".to_string());
+        }
+        for (key, path) in [("tls.cert_path", &self.tls.cert_path), ("tls.key_path", &self.tls.key_path)] {
+            if let Some(path) = path
+                && !path.is_file()
+            {
+                problems.push(format!("{} {} is not a file", key, path.display()));
+            }
+        }
+        match self.tls.redirect_port {
+            Some(_) if !self.tls.is_enabled() => {
+                problems.push("tls.redirect_port needs tls.cert_path and tls.key_path".to_string())
+            }
+            Some(port) if port == 0 || port == self.server.port => {
+                problems.push("tls.redirect_port must be between 1 and 65535 and differ from server.port".to_string())
+            }
+            _ => {}
+        }
+        if !self.database.url.starts_with("sqlite:") {
+            problems.push(format!("database.url must be a SQLite URL starting with \"sqlite:\", got {:?}", self.database.url));
+        }
+        if self.database.max_connections == 0 {
+            problems.push("database.max_connections must be at least 1".to_string());
+        }
+        if !self.database.migrations_dir.is_dir() {
+            problems.push(format!("database.migrations_dir {} is not a directory", self.database.migrations_dir.display()));
+        }
+        if self.auth.jwt_secret.is_empty() {
+            problems.push("auth.jwt_secret must not be empty".to_string());
+        }
+        if !(1..=30 * 24 * 60).contains(&self.auth.token_lifetime_minutes) {
+            problems.push("auth.token_lifetime_minutes must be between 1 and 43200 (30 days)".to_string());
+        }
+        if let Err(e) = EnvFilter::try_new(&self.logging.filter) {
+            problems.push(format!("logging.filter {:?} is not a valid filter: {}", self.logging.filter, e));
+        }
+        if self.email.smtp_port == 0 {
+            problems.push("email.smtp_port must be between 1 and 65535".to_string());
+        }
+        if self.email.smtp_username.is_some() != self.email.smtp_password.is_some() {
+            problems.push("email.smtp_username and email.smtp_password must be set together".to_string());
+        }
+        if let Err(e) = self.email.from.parse::<Mailbox>() {
+            problems.push(format!("email.from {:?} is not an email address: {}", self.email.from, e));
+        }
+        if !(self.email.base_url.starts_with("http://") || self.email.base_url.starts_with("https://")) {
+            problems.push(format!("email.base_url must start with http:// or https://, got {:?}", self.email.base_url));
+        }
+        if self.retention.trash_days < 1 {
+            problems.push("retention.trash_days must be at least 1".to_string());
+        }
+        if self.retention.notification_days < 1 {
+            problems.push("retention.notification_days must be at least 1".to_string());
+        }
+
+        if problems.is_empty() { Ok(()) } else { Err(ConfigError::Invalid(problems)) }
+    }
+}
+
+// Help text listing the flags.
+pub fn usage() -> String {
+    let mut usage = "usage: g21_practical [--config FILE] [OPTIONS] [import ... | make-admin USERNAME]\noptions (environment variable in brackets):".to_string();
+    for (key, variable, flag, _) in OVERRIDES {
+        if let Some(flag) = flag {
+            usage.push_str(&format!("\n  {:<30} {} [{}]", flag, key, variable));
+        }
+    }
+    usage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    // Loads with `variables` as the whole environment.
+    fn load(arguments: &[&str], variables: &[(&str, &str)]) -> Result<(Config, Vec<String>), ConfigError> {
+        let variables: HashMap<String, String> = variables.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        Config::load_from(&args(arguments), |name| variables.get(name).cloned())
+    }
+
+    // A config file holding `text`, removed when dropped.
+    struct ConfigFile(PathBuf);
+
+    impl ConfigFile {
+        fn new(text: &str) -> Self {
+            let path = env::temp_dir().join(format!("g21_config_{}.toml", uuid::Uuid::new_v4()));
+            fs::write(&path, text).unwrap();
+            ConfigFile(path)
+        }
+
+        fn path(&self) -> &str {
+            self.0.to_str().unwrap()
+        }
+    }
+
+    impl Drop for ConfigFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn problems(config: &Config) -> Vec<String> {
+        match config.validate() {
+            Err(ConfigError::Invalid(problems)) => problems,
+            other => panic!("expected validation problems, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn defaults_apply_without_file_variables_or_flags() {
+        let (config, rest) = load(&[], &[]).unwrap();
+        assert_eq!(config.server.port, 8080);
+        assert_eq!(config.retention.trash_days, 30);
+        assert_eq!(config.retention.notification_days, 90);
+        assert!(!config.email.is_enabled());
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn flags_override_variables_which_override_the_file() {
+        let file = ConfigFile::new("[server]\nport = 7000\nhost = \"0.0.0.0\"\n\n[retention]\ntrash_days = 7\nnotification_days = 14\n");
+
+        let (config, _) = load(&["--config", file.path()], &[]).unwrap();
+        assert_eq!((config.server.host.as_str(), config.server.port), ("0.0.0.0", 7000));
+        assert_eq!((config.retention.trash_days, config.retention.notification_days), (7, 14));
+
+        let variables = [("SERVER_PORT", "7100"), ("TRASH_RETENTION_DAYS", "8")];
+        let (config, _) = load(&["--config", file.path()], &variables).unwrap();
+        assert_eq!((config.server.host.as_str(), config.server.port), ("0.0.0.0", 7100));
+        assert_eq!((config.retention.trash_days, config.retention.notification_days), (8, 14));
+
+        let (config, rest) = load(&["--config", file.path(), "--port", "7200", "import", "bugs.csv"], &variables).unwrap();
+        assert_eq!(config.server.port, 7200);
+        assert_eq!(config.retention.trash_days, 8);
+        assert_eq!(rest, args(&["import", "bugs.csv"]));
+    }
+
+    #[test]
+    fn config_file_flag_wins_over_config_file_variable() {
+        let from_variable = ConfigFile::new("[server]\nport = 7000\n");
+        let from_flag = ConfigFile::new("[server]\nport = 7001\n");
+        let (config, _) = load(&["--config", from_flag.path()], &[("CONFIG_FILE", from_variable.path())]).unwrap();
+        assert_eq!(config.server.port, 7001);
+        let (config, _) = load(&[], &[("CONFIG_FILE", from_variable.path())]).unwrap();
+        assert_eq!(config.server.port, 7000);
+    }
+
+    #[test]
+    fn email_settings_come_from_smtp_variables() {
+        let variables = [
+            ("SMTP_HOST", "mail.example.com"),
+            ("SMTP_PORT", "587"),
+            ("SMTP_USERNAME", "bugs"),
+            ("SMTP_PASSWORD", "hunter2"),
+            ("SMTP_STARTTLS", "true"),
+            ("SMTP_FROM", "bugs@example.com"),
+            ("APP_BASE_URL", "https://bugs.example.com"),
+        ];
+        let (config, _) = load(&[], &variables).unwrap();
+        assert_eq!(config.email.smtp_host.as_deref(), Some("mail.example.com"));
+        assert_eq!(config.email.smtp_port, 587);
+        assert_eq!(config.email.smtp_password.as_deref(), Some("hunter2"));
+        assert!(config.email.smtp_starttls);
+        assert_eq!(config.email.base_url, "https://bugs.example.com");
+
+        // An empty host leaves email off
+        let (config, _) = load(&[], &[("SMTP_HOST", "")]).unwrap();
+        assert!(!config.email.is_enabled());
+    }
+
+    #[test]
+    fn badly_typed_values_name_their_source() {
+        let error = load(&[], &[("SERVER_PORT", "eighty")]).unwrap_err();
+        assert!(matches!(&error, ConfigError::Value(source, value, _) if source == "SERVER_PORT" && value == "eighty"));
+        let error = load(&["--trash-retention-days", "a week"], &[]).unwrap_err();
+        assert!(matches!(&error, ConfigError::Value(source, _, _) if source == "--trash-retention-days"));
+    }
+
+    #[test]
+    fn unknown_flags_and_secrets_on_the_command_line_are_rejected() {
+        assert!(matches!(load(&["--verbose", "1"], &[]), Err(ConfigError::Usage(_))));
+        assert!(matches!(load(&["--port"], &[]), Err(ConfigError::Usage(_))));
+        assert!(matches!(load(&["--jwt-secret", "s"], &[]), Err(ConfigError::Usage(_))));
+        assert!(matches!(load(&["--help"], &[]), Err(ConfigError::Help)));
+    }
+
+    #[test]
+    fn unknown_file_keys_are_rejected() {
+        let file = ConfigFile::new("[server]\nprot = 8080\n");
+        assert!(matches!(load(&["--config", file.path()], &[]), Err(ConfigError::Parse(_, _))));
+    }
+
+    #[test]
+    fn validate_accepts_the_defaults() {
+        assert!(Config::default().validate().is_ok());
+    }
+
+    #[test]
+    fn validate_lists_every_problem() {
+        let mut config = Config::default();
+        config.server.port = 0;
+        config.database.url = "postgres://localhost/bugs".to_string();
+        config.auth.token_lifetime_minutes = 0;
+        config.logging.filter = "info,=".to_string();
+        assert_eq!(problems(&config).len(), 4);
+        assert!(problems(&config)[0].starts_with("server.port"));
+    }
+
+    #[test]
+    fn validate_checks_tls_pairs_and_redirect_port() {
+        let mut config = Config::default();
+        config.tls.cert_path = Some(PathBuf::from("Cargo.toml"));
+        config.tls.redirect_port = Some(80);
+        assert_eq!(
+            problems(&config),
+            vec!["tls.cert_path and tls.key_path must be set together", "tls.redirect_port needs tls.cert_path and tls.key_path"]
+        );
+
+        config.tls.key_path = Some(PathBuf::from("missing.pem"));
+        config.tls.redirect_port = Some(config.server.port);
+        assert_eq!(
+            problems(&config),
+            vec!["tls.key_path missing.pem is not a file", "tls.redirect_port must be between 1 and 65535 and differ from server.port"]
+        );
+    }
+
+    #[test]
+    fn validate_checks_email_and_retention() {
+        let mut config = Config::default();
+        config.email.smtp_username = Some("bugs".to_string());
+        config.email.from = "not an address".to_string();
+        config.email.base_url = "bugs.example.com".to_string();
+        config.retention.trash_days = 0;
+        config.retention.notification_days = -1;
+        let problems = problems(&config);
+        assert_eq!(problems.len(), 5);
+        assert_eq!(problems[0], "email.smtp_username and email.smtp_password must be set together");
+        assert!(problems[1].starts_with("email.from \"not an address\""));
+        assert_eq!(problems[3], "retention.trash_days must be at least 1");
+    }
+}
@@ -1,27 +1,35 @@
 // Import necessary items from the `sqlx` crate for SQLite database connection pooling.
 // `Pool` is used to manage a pool of database connections, while `Sqlite` and `SqlitePoolOptions` are specific to SQLite.
-use sqlx::{Pool, Sqlite};
+use sqlx::{Connection, Pool, Sqlite, SqliteConnection};
 use sqlx::sqlite::SqlitePoolOptions;
 use uuid::Uuid;
 use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
 
-use crate::auth;
+use crate::config::DatabaseConfig;
+use crate::{auth, watchers};
 
 // Define an asynchronous function `init_db` that initializes a connection pool to an SQLite database.
 // This function returns a `Pool<Sqlite>` type, which represents a pool of SQLite connections.
-pub async fn init_db() -> Pool<Sqlite> {
+pub async fn init_db(config: &DatabaseConfig) -> Pool<Sqlite> {
     // Create a new instance of `SqlitePoolOptions` to configure the connection pool settings.
     let pool = SqlitePoolOptions::new()
-        .max_connections(5) // Set the maximum number of connections in the pool to 5.
-        .connect("sqlite::memory:?cache=shared") // Connect to an in-memory SQLite database.
+        .max_connections(config.max_connections) // Set the maximum number of connections in the pool, 5 by default.
+        .connect(&config.url) // Connect to the configured database, in-memory by default.
         .await // Since database connections are asynchronous operations, await the completion.
         .expect("DB connection failed"); // Panic with an error message if the connection fails.
 
-    let sql = fs::read_to_string("migrations/schema.sql").expect("Failed to read schema.sql");
-        sqlx::query(&sql)
-        .execute(&pool)
+    run_migrations(&pool, &config.migrations_dir).await.expect("Failed to run database migrations");
+
+    // Only seed the test data into an empty database
+    let user_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users")
+        .fetch_one(&pool)
         .await
-        .expect("Failed to execute schema.sql");
+        .expect("Failed to count users");
+    if user_count > 0 {
+        return pool;
+    }
 
     // Hash passwords with salt for testing
     let salt = "bugtrack2025";
@@ -32,20 +40,22 @@ pub async fn init_db() -> Pool<Sqlite> {
 
     // Insert admin user
     let user_id = Uuid::new_v4();
-    sqlx::query("INSERT INTO users (id, username, hashed_password) VALUES (?, ?, ?)")
+    sqlx::query("INSERT INTO users (id, username, hashed_password, email, is_admin) VALUES (?, ?, ?, ?, TRUE)")
         .bind(&user_id.as_bytes()[..])
         .bind("admin")
         .bind(&admin_password_hash) // hash in real apps!
+        .bind("admin@example.com")
         .execute(&pool)
         .await
         .expect("Failed to insert admin user");
 
     // Insert a normal user
     let user_id2 = Uuid::new_v4();
-    sqlx::query("INSERT INTO users (id, username, hashed_password) VALUES (?, ?, ?)")
+    sqlx::query("INSERT INTO users (id, username, hashed_password, email) VALUES (?, ?, ?, ?)")
         .bind(&user_id2.as_bytes()[..])
         .bind("normal_user")
         .bind(&user_password_hash) // hash in real apps!
+        .bind("user@example.com")
         .execute(&pool)
         .await
         .expect("Failed to insert normal user");
@@ -74,6 +84,182 @@ pub async fn init_db() -> Pool<Sqlite> {
         .execute(&pool)
         .await
         .expect("Failed to insert bug report");
+
+    // The reporter watches the bug, as for bugs created through the API
+    watchers::watch(&pool, bug_id, user_id)
+        .await
+        .expect("Failed to insert bug watcher");
     
     pool
 }
+
+// Apply every migration in `dir` (`migrations/` by default) that has not been recorded in `schema_migrations` yet.
+// Files are named `NNNN_description.sql` and run in file-name order, each inside its own transaction.
+// Foreign keys are not enforced while a migration runs, so that a table can be rebuilt (create, copy, drop, rename)
+// without the drop cascading into the tables referencing it; they are checked before the migration is committed.
+pub async fn run_migrations(pool: &Pool<Sqlite>, dir: &Path) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (version TEXT PRIMARY KEY, applied_at TEXT DEFAULT CURRENT_TIMESTAMP)"
+    )
+    .execute(pool)
+    .await?;
+
+    // The pragma only takes effect outside a transaction and only on its own connection
+    let mut conn = pool.acquire().await?;
+    sqlx::query("PRAGMA foreign_keys = OFF").execute(&mut *conn).await?;
+    let result = apply_migrations(&mut conn, dir).await;
+    sqlx::query("PRAGMA foreign_keys = ON").execute(&mut *conn).await?;
+    result
+}
+
+async fn apply_migrations(conn: &mut SqliteConnection, dir: &Path) -> Result<(), sqlx::Error> {
+    for path in migration_files(dir)? {
+        let version = migration_version(&path);
+
+        let applied = sqlx::query("SELECT version FROM schema_migrations WHERE version = ?")
+            .bind(&version)
+            .fetch_optional(&mut *conn)
+            .await?;
+        if applied.is_some() {
+            continue;
+        }
+
+        let sql = fs::read_to_string(&path)?;
+        let mut tx = conn.begin().await?;
+        sqlx::query(&sql).execute(&mut *tx).await?;
+        let violations = sqlx::query("PRAGMA foreign_key_check").fetch_all(&mut *tx).await?;
+        if !violations.is_empty() {
+            return Err(sqlx::Error::Protocol(format!(
+                "migration {} leaves {} rows with broken foreign keys",
+                version,
+                violations.len()
+            )));
+        }
+        sqlx::query("INSERT INTO schema_migrations (version) VALUES (?)")
+            .bind(&version)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        info!(migration = %version, "Applied migration");
+    }
+
+    Ok(())
+}
+
+// The migration files in `dir`, in the order they are applied.
+fn migration_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files: Vec<_> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "sql"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+// A migration is known by its file name without `.sql`, e.g. `0011_soft_delete`.
+fn migration_version(path: &Path) -> String {
+    path.file_stem().unwrap_or_default().to_string_lossy().to_string()
+}
+
+// Migrations in `dir` that have not been applied to the database, in order.
+pub async fn pending_migrations(pool: &Pool<Sqlite>, dir: &Path) -> Result<Vec<String>, sqlx::Error> {
+    let applied: Vec<String> = sqlx::query_scalar("SELECT version FROM schema_migrations").fetch_all(pool).await?;
+    Ok(migration_files(dir)?
+        .iter()
+        .map(|path| migration_version(path))
+        .filter(|version| !applied.contains(version))
+        .collect())
+}
+
+// The last migration applied to the database, which names the version of its schema.
+pub async fn schema_version(pool: &Pool<Sqlite>) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar("SELECT MAX(version) FROM schema_migrations").fetch_one(pool).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Migrations in `migrations/` up to and including `last`, copied to a directory of their own.
+    fn migrations_until(last: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("g21_migrations_{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        for path in migration_files(Path::new("migrations")).unwrap() {
+            if migration_version(&path).as_str() <= last {
+                fs::copy(&path, dir.join(path.file_name().unwrap())).unwrap();
+            }
+        }
+        dir
+    }
+
+    async fn insert_bug(pool: &Pool<Sqlite>, bug_id: Uuid, title: &str, user_id: Uuid, project_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query("INSERT INTO bugReport (id, title, description, project_id, reported_by, severity) VALUES (?, ?, '', ?, ?, 'low')")
+            .bind(bug_id.as_bytes().as_slice())
+            .bind(title)
+            .bind(project_id.as_bytes().as_slice())
+            .bind(user_id.as_bytes().as_slice())
+            .execute(pool)
+            .await
+            .map(|_| ())
+    }
+
+    #[actix_web::test]
+    async fn soft_delete_rebuild_keeps_rows_referencing_bugs_and_frees_trashed_titles() {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        let before = migrations_until("0010");
+        run_migrations(&pool, &before).await.unwrap();
+        fs::remove_dir_all(&before).unwrap();
+
+        let (user_id, project_id, bug_id) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        sqlx::query("INSERT INTO users (id, username, hashed_password) VALUES (?, 'tester', '')")
+            .bind(user_id.as_bytes().as_slice())
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO projectRecord (id, user_id, project_name) VALUES (?, ?, 'Migrations')")
+            .bind(project_id.as_bytes().as_slice())
+            .bind(user_id.as_bytes().as_slice())
+            .execute(&pool)
+            .await
+            .unwrap();
+        insert_bug(&pool, bug_id, "Crash on save", user_id, project_id).await.unwrap();
+        sqlx::query("INSERT INTO bugWatcher (bug_id, user_id) VALUES (?, ?)")
+            .bind(bug_id.as_bytes().as_slice())
+            .bind(user_id.as_bytes().as_slice())
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        run_migrations(&pool, Path::new("migrations")).await.unwrap();
+        assert!(pending_migrations(&pool, Path::new("migrations")).await.unwrap().is_empty());
+
+        // Dropping the old table must not have cascaded into its watchers
+        let watchers: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM bugWatcher WHERE bug_id = ?")
+            .bind(bug_id.as_bytes().as_slice())
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(watchers, 1);
+        let foreign_keys: i64 = sqlx::query_scalar("PRAGMA foreign_keys").fetch_one(&pool).await.unwrap();
+        assert_eq!(foreign_keys, 1);
+
+        // A live bug still holds its title, a trashed one does not
+        let duplicate = insert_bug(&pool, Uuid::new_v4(), "Crash on save", user_id, project_id).await.unwrap_err();
+        assert!(duplicate.as_database_error().is_some_and(|e| e.is_unique_violation()));
+        sqlx::query("UPDATE bugReport SET deleted_at = CURRENT_TIMESTAMP WHERE id = ?")
+            .bind(bug_id.as_bytes().as_slice())
+            .execute(&pool)
+            .await
+            .unwrap();
+        insert_bug(&pool, Uuid::new_v4(), "Crash on save", user_id, project_id).await.unwrap();
+
+        // Restoring the trashed bug now clashes with the new one
+        let restore = sqlx::query("UPDATE bugReport SET deleted_at = NULL WHERE id = ?")
+            .bind(bug_id.as_bytes().as_slice())
+            .execute(&pool)
+            .await
+            .unwrap_err();
+        assert!(restore.as_database_error().is_some_and(|e| e.is_unique_violation()));
+    }
+}
@@ -0,0 +1,364 @@
+// Email notifications. Domain events are turned into emails for the users involved (e.g. the new assignee),
+// rendered from the Tera templates in static/email and queued in the `emailOutbox` table. A background worker
+// sends them through the SMTP relay configured in the `email` section of the configuration; users in digest mode get
+// their queued emails combined into one message per hour or day.
+//
+// Templates: the first line of each rendered template is the subject, the rest is the body.
+use actix_web::rt::task::JoinHandle;
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use sqlx::{FromRow, SqlitePool};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tera::{Context, Tera};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::config::EmailConfig;
+use crate::error::AppError;
+use crate::events::{DomainEvent, EventKind};
+use crate::models::{DigestMode, NotificationPreferences};
+use crate::{logging, shutdown, watchers};
+
+const TEMPLATES: &str = "static/email/*.txt";
+
+// Failed sends are retried after 30s, 60s, 120s, ... (capped at an hour) and given up after MAX_ATTEMPTS.
+const MAX_ATTEMPTS: i64 = 5;
+const BASE_RETRY_DELAY_SECS: i64 = 30;
+const MAX_RETRY_DELAY_SECS: i64 = 3600;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const BATCH_SIZE: i64 = 20;
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Email preferences of a user, with defaults for users who never changed them.
+const PREFERENCES_SQL: &str = "SELECT u.email, \
+    COALESCE(p.email_enabled, TRUE) AS email_enabled, \
+    COALESCE(p.notify_assigned, TRUE) AS notify_assigned, \
+    COALESCE(p.notify_status_changed, TRUE) AS notify_status_changed, \
+    COALESCE(p.notify_mentioned, TRUE) AS notify_mentioned, \
+    COALESCE(p.digest, 'off') AS digest \
+    FROM users u LEFT JOIN notificationPreference p ON p.user_id = u.id WHERE u.id = ?";
+
+// A queued email ready to be sent.
+#[derive(Debug, FromRow)]
+struct OutboxEmail {
+    id: Uuid,
+    recipient: String,
+    subject: String,
+    body: String,
+    attempts: i64,
+}
+
+// Loads a user's email address and preferences, or None if the user does not exist.
+pub async fn load_preferences(pool: &SqlitePool, user_id: Uuid) -> Result<Option<NotificationPreferences>, sqlx::Error> {
+    sqlx::query_as::<_, NotificationPreferences>(PREFERENCES_SQL)
+        .bind(user_id.as_bytes().as_slice())
+        .fetch_optional(pool)
+        .await
+}
+
+// Queues the emails an event causes. Assignments notify the new assignee, mentions the mentioned users, and
+// fixing or reopening a bug notifies its reporter, assignee and watchers. Nobody is emailed about their own change.
+pub async fn enqueue(pool: &SqlitePool, config: &EmailConfig, event: &DomainEvent) -> Result<usize, AppError> {
+    if !config.is_enabled() {
+        return Ok(0);
+    }
+
+    let user_field = |field: &str| event.data[field].as_str().and_then(|id| Uuid::parse_str(id).ok());
+    let (template, recipients) = match event.event {
+        EventKind::BugAssigned => ("assigned.txt", vec![user_field("fixed_by")]),
+        EventKind::BugMentioned => ("mentioned.txt", event.mentioned.iter().copied().map(Some).collect()),
+        EventKind::BugFixed | EventKind::BugReopened => {
+            // Watchers include the reporter and assignee, who are listed anyway in case they stopped watching
+            let mut recipients = vec![user_field("reported_by"), user_field("fixed_by")];
+            if let Some(bug_id) = user_field("id") {
+                recipients.extend(watchers::watcher_ids(pool, bug_id).await.map_err(AppError::Database)?.into_iter().map(Some));
+            }
+            ("status_changed.txt", recipients)
+        }
+        _ => return Ok(0),
+    };
+
+    let mut recipients: Vec<Uuid> = recipients.into_iter().flatten().filter(|id| Some(*id) != event.actor).collect();
+    recipients.sort();
+    recipients.dedup();
+    if recipients.is_empty() {
+        return Ok(0);
+    }
+
+    let project_name = sqlx::query_scalar::<_, String>("SELECT project_name FROM projectRecord WHERE id = ?")
+        .bind(event.project_id.as_bytes().as_slice())
+        .fetch_optional(pool)
+        .await
+        .map_err(AppError::Database)?
+        .unwrap_or_default();
+    let actor = match event.actor {
+        Some(actor) => username(pool, actor).await?,
+        None => None,
+    };
+
+    let tera = templates()?;
+    let mut queued = 0;
+    for recipient in recipients {
+        let Some(preferences) = load_preferences(pool, recipient).await.map_err(AppError::Database)? else {
+            continue;
+        };
+        let wanted = match event.event {
+            EventKind::BugAssigned => preferences.notify_assigned,
+            EventKind::BugMentioned => preferences.notify_mentioned,
+            _ => preferences.notify_status_changed,
+        };
+        let Some(address) = preferences.email.filter(|_| preferences.email_enabled && wanted) else {
+            continue;
+        };
+
+        let mut context = Context::new();
+        context.insert("recipient", &username(pool, recipient).await?.unwrap_or_default());
+        context.insert("actor", &actor.clone().unwrap_or_else(|| "Someone".to_string()));
+        context.insert("project_name", &project_name);
+        context.insert("bug", &event.data);
+        context.insert("bug_url", &format!("{}/bugs/{}", config.base_url, event.data["id"].as_str().unwrap_or_default()));
+        let (subject, mut body) = render(tera, template, &context)?;
+
+        // Digest emails only keep the event itself; the greeting and footer are added to the digest as a whole
+        let digest = preferences.digest != DigestMode::Off;
+        if !digest {
+            context.insert("body", &body);
+            body = render(tera, "message.txt", &context)?.1;
+        }
+
+        sqlx::query(&format!(
+            "INSERT INTO emailOutbox (id, user_id, recipient, subject, body, digest, send_after) VALUES (?, ?, ?, ?, ?, ?, {})",
+            send_after_sql(preferences.digest)
+        ))
+        .bind(Uuid::new_v4().as_bytes().as_slice())
+        .bind(recipient.as_bytes().as_slice())
+        .bind(&address)
+        .bind(&subject)
+        .bind(&body)
+        .bind(digest)
+        .execute(pool)
+        .await
+        .map_err(AppError::Database)?;
+        queued += 1;
+    }
+    Ok(queued)
+}
+
+// When a queued email may go out: immediately, or at the start of the next hour or day for digests.
+fn send_after_sql(digest: DigestMode) -> &'static str {
+    match digest {
+        DigestMode::Off => "CURRENT_TIMESTAMP",
+        DigestMode::Hourly => "datetime(strftime('%Y-%m-%d %H:00:00', 'now'), '+1 hour')",
+        DigestMode::Daily => "datetime('now', 'start of day', '+1 day')",
+    }
+}
+
+async fn username(pool: &SqlitePool, user_id: Uuid) -> Result<Option<String>, AppError> {
+    sqlx::query_scalar::<_, String>("SELECT username FROM users WHERE id = ?")
+        .bind(user_id.as_bytes().as_slice())
+        .fetch_optional(pool)
+        .await
+        .map_err(AppError::Database)
+}
+
+// The templates only change with a deployment, so they are parsed once.
+fn templates() -> Result<&'static Tera, AppError> {
+    static PARSED: OnceLock<Result<Tera, String>> = OnceLock::new();
+    PARSED
+        .get_or_init(|| Tera::new(TEMPLATES).map_err(|e| e.to_string()))
+        .as_ref()
+        .map_err(|e| AppError::Internal(format!("Email template parsing error: {}", e)))
+}
+
+// Renders a template into its subject (first line) and body (the rest).
+fn render(tera: &Tera, template: &str, context: &Context) -> Result<(String, String), AppError> {
+    let rendered = tera
+        .render(template, context)
+        .map_err(|e| AppError::Internal(format!("Failed to render email template {}: {}", template, e)))?;
+    let (subject, body) = rendered.split_once('\n').unwrap_or((rendered.as_str(), ""));
+    Ok((subject.trim().to_string(), body.trim_start_matches('\n').to_string()))
+}
+
+// Starts the background task sending queued emails. Does nothing when SMTP is not configured.
+// On shutdown it sends whatever is already due and stops; retries and digests due later stay queued.
+pub fn start_worker(pool: SqlitePool, config: EmailConfig) -> Option<JoinHandle<()>> {
+    let Some(host) = &config.smtp_host else {
+        info!("email.smtp_host is not set, email notifications are disabled");
+        return None;
+    };
+
+    let mailer = match transport(host, &config) {
+        Ok(mailer) => mailer,
+        Err(e) => {
+            error!(error = %e, host = %host, "Invalid SMTP settings, email notifications are disabled");
+            return None;
+        }
+    };
+
+    Some(actix_web::rt::spawn(async move {
+        loop {
+            if let Err(e) = send_due(&pool, &config, &mailer).await {
+                error!(error = ?e, "Email delivery error");
+            }
+            if !shutdown::pause(POLL_INTERVAL).await {
+                break;
+            }
+        }
+
+        let mut flushed = 0;
+        loop {
+            match send_due(&pool, &config, &mailer).await {
+                Ok(0) => break,
+                Ok(sent) => flushed += sent,
+                Err(e) => {
+                    error!(error = ?e, "Email delivery error");
+                    break;
+                }
+            }
+        }
+        info!(flushed, "Email worker stopped");
+    }))
+}
+
+// Sends every due email: immediate ones individually, digest ones combined per user.
+// Returns how many emails were attempted, counting a digest once.
+async fn send_due(pool: &SqlitePool, config: &EmailConfig, mailer: &AsyncSmtpTransport<Tokio1Executor>) -> Result<usize, AppError> {
+    let due = sqlx::query_as::<_, OutboxEmail>(
+        "SELECT id, recipient, subject, body, attempts FROM emailOutbox \
+         WHERE status = 'pending' AND digest = FALSE AND send_after <= CURRENT_TIMESTAMP ORDER BY send_after LIMIT ?"
+    )
+    .bind(BATCH_SIZE)
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::Database)?;
+
+    let mut attempted = due.len();
+    for email in due {
+        let outcome = send(config, mailer, &email.recipient, &email.subject, email.body.clone()).await;
+        record_attempt(pool, &[email.id], email.attempts, outcome).await?;
+    }
+
+    let digest_users = sqlx::query_as::<_, (Uuid, String)>(
+        "SELECT DISTINCT o.user_id, u.username FROM emailOutbox o JOIN users u ON u.id = o.user_id \
+         WHERE o.status = 'pending' AND o.digest = TRUE AND o.send_after <= CURRENT_TIMESTAMP"
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(AppError::Database)?;
+
+    for (user_id, recipient) in digest_users {
+        let emails = sqlx::query_as::<_, OutboxEmail>(
+            "SELECT id, recipient, subject, body, attempts FROM emailOutbox \
+             WHERE user_id = ? AND status = 'pending' AND digest = TRUE AND send_after <= CURRENT_TIMESTAMP ORDER BY created_at"
+        )
+        .bind(user_id.as_bytes().as_slice())
+        .fetch_all(pool)
+        .await
+        .map_err(AppError::Database)?;
+        let Some(latest) = emails.last() else {
+            continue;
+        };
+
+        let items: Vec<serde_json::Value> = emails
+            .iter()
+            .map(|email| serde_json::json!({ "subject": email.subject, "body": email.body }))
+            .collect();
+        let mut context = Context::new();
+        context.insert("recipient", &recipient);
+        context.insert("items", &items);
+        let (subject, body) = render(templates()?, "digest.txt", &context)?;
+
+        let outcome = send(config, mailer, &latest.recipient, &subject, body).await;
+        let ids: Vec<Uuid> = emails.iter().map(|email| email.id).collect();
+        let attempts = emails.iter().map(|email| email.attempts).max().unwrap_or(0);
+        record_attempt(pool, &ids, attempts, outcome).await?;
+        attempted += 1;
+    }
+    Ok(attempted)
+}
+
+// The SMTP relay at `host`. With smtp_starttls the connection must be upgraded before anything is sent, otherwise it stays plain.
+fn transport(host: &str, config: &EmailConfig) -> Result<AsyncSmtpTransport<Tokio1Executor>, lettre::transport::smtp::Error> {
+    let builder = if config.smtp_starttls {
+        AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?
+    } else {
+        AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host)
+    };
+    let mut builder = builder.port(config.smtp_port).timeout(Some(SMTP_TIMEOUT));
+    if let (Some(username), Some(password)) = (&config.smtp_username, &config.smtp_password) {
+        builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+    }
+    Ok(builder.build())
+}
+
+// Sends one plain-text email through the relay.
+async fn send(config: &EmailConfig, mailer: &AsyncSmtpTransport<Tokio1Executor>, to: &str, subject: &str, body: String) -> Result<(), String> {
+    let message = Message::builder()
+        .from(config.from.parse().map_err(|e| format!("invalid email.from address: {}", e))?)
+        .to(to.parse().map_err(|e| format!("invalid recipient address: {}", e))?)
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN)
+        .body(body)
+        .map_err(|e| format!("could not build email: {}", e))?;
+    mailer.send(message).await.map_err(|e| format!("could not send email: {}", e))?;
+    Ok(())
+}
+
+// Marks the emails as sent, or schedules a retry with backoff and gives up after MAX_ATTEMPTS.
+async fn record_attempt(pool: &SqlitePool, ids: &[Uuid], attempts: i64, outcome: Result<(), String>) -> Result<(), AppError> {
+    let attempts = attempts + 1;
+    for id in ids {
+        let query = match &outcome {
+            Ok(()) => sqlx::query(
+                "UPDATE emailOutbox SET status = 'sent', attempts = ?, last_error = NULL, sent_at = CURRENT_TIMESTAMP WHERE id = ?"
+            )
+            .bind(attempts)
+            .bind(id.as_bytes().as_slice()),
+            Err(error) => {
+                let delay = (BASE_RETRY_DELAY_SECS << (attempts - 1).min(20)).min(MAX_RETRY_DELAY_SECS);
+                sqlx::query(
+                    "UPDATE emailOutbox SET status = ?, attempts = ?, last_error = ?, send_after = datetime('now', ?) WHERE id = ?"
+                )
+                .bind(if attempts >= MAX_ATTEMPTS { "failed" } else { "pending" })
+                .bind(attempts)
+                .bind(error)
+                .bind(format!("+{} seconds", delay))
+                .bind(id.as_bytes().as_slice())
+            }
+        };
+        query.execute(pool).await.map_err(AppError::Database)?;
+    }
+
+    if let Err(error) = outcome {
+        // SMTP replies often quote the recipient
+        warn!(error = %logging::redact(&error), "Failed to send email");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_rendered_line_is_the_subject() {
+        let mut tera = Tera::default();
+        tera.add_raw_template("test.txt", "  Bug {{ title }} fixed \n\nHi {{ name }},\nit is fixed.").unwrap();
+        let mut context = Context::new();
+        context.insert("title", "Crash");
+        context.insert("name", "admin");
+        let (subject, body) = render(&tera, "test.txt", &context).unwrap();
+        assert_eq!(subject, "Bug Crash fixed");
+        assert_eq!(body, "Hi admin,\nit is fixed.");
+    }
+
+    #[test]
+    fn bundled_templates_parse_once() {
+        let first = templates().unwrap();
+        assert!(first.get_template_names().any(|name| name == "digest.txt"));
+        assert!(std::ptr::eq(first, templates().unwrap()));
+    }
+}
@@ -1,6 +1,13 @@
-use actix_web::{HttpResponse, ResponseError};
+use actix_web::{HttpRequest, HttpResponse, ResponseError};
+use actix_web::error::{JsonPayloadError, PathError, QueryPayloadError};
+use actix_web::http::StatusCode;
 use derive_more::Display;
+use serde::Serialize;
+use utoipa::ToSchema;
 use sqlx::Error as SqlxError;
+use validator::{ValidationErrors, ValidationErrorsKind};
+
+use crate::middleware;
 
 #[derive(Debug, Display)]
 pub enum AppError {
@@ -16,19 +23,355 @@ pub enum AppError {
 
     #[display(fmt = "Unauthorized: {}", _0)]
     Unauthorized(String),
+
+    // Authenticated, but not allowed to do this, e.g. a non-administrator opening the trash
+    #[display(fmt = "Forbidden: {}", _0)]
+    Forbidden(String),
+
+    // Request body, query string or path segment could not be parsed by the actix extractors
+    #[display(fmt = "Invalid JSON body: {}", _0)]
+    InvalidJson(String),
+
+    #[display(fmt = "Invalid query string: {}", _0)]
+    InvalidQuery(String),
+
+    #[display(fmt = "Invalid path: {}", _0)]
+    InvalidPath(String),
+
+    // One or more request fields failed validation; reported as 422 with field-level details
+    #[display(fmt = "Validation failed: {:?}", _0)]
+    Validation(Vec<FieldError>),
+
+    // The request conflicts with existing data, e.g. a UNIQUE constraint violation
+    #[display(fmt = "Conflict: {}", _0)]
+    Conflict(String),
+
+    // The `If-Match` header did not match the current version of the resource
+    #[display(fmt = "Precondition failed: {}", _0)]
+    PreconditionFailed(String),
+
+    // Unexpected server-side failures that are not database errors (templates, hashing, ...)
+    #[display(fmt = "Internal error: {}", _0)]
+    Internal(String),
 }
 
 impl std::error::Error for AppError {} // Implements the standard Error trait
 
+// A single field-level problem, reported inside the `details` list of an error body.
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+// The JSON body returned for every error, e.g.
+// {"status": "error", "code": "not_found", "message": "Bug not found", "details": [], "request_id": "..."}
+// `status` and `message` keep the shape the existing frontends already read.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct ErrorBody {
+    pub status: &'static str,
+    pub code: &'static str,
+    pub message: String,
+    pub details: Vec<FieldError>,
+    pub request_id: Option<String>,
+}
+
+impl AppError {
+    // Stable machine-readable code for each variant
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Database(_) => "database_error",
+            AppError::NotFound(_) => "not_found",
+            AppError::BadRequest(_) => "bad_request",
+            AppError::Unauthorized(_) => "unauthorized",
+            AppError::Forbidden(_) => "forbidden",
+            AppError::InvalidJson(_) => "invalid_json",
+            AppError::InvalidQuery(_) => "invalid_query",
+            AppError::InvalidPath(_) => "invalid_path",
+            AppError::Validation(_) => "validation_failed",
+            AppError::Conflict(_) => "conflict",
+            AppError::PreconditionFailed(_) => "precondition_failed",
+            AppError::Internal(_) => "internal_error",
+        }
+    }
+
+    // Human-readable message sent to the client. Server-side failures are not echoed back.
+    pub fn message(&self) -> String {
+        match self {
+            AppError::Database(_) => "Database error occurred".to_string(),
+            AppError::Internal(_) => "Internal server error".to_string(),
+            AppError::Validation(_) => "Request validation failed".to_string(),
+            AppError::NotFound(msg)
+            | AppError::Conflict(msg)
+            | AppError::PreconditionFailed(msg)
+            | AppError::BadRequest(msg)
+            | AppError::Unauthorized(msg)
+            | AppError::Forbidden(msg)
+            | AppError::InvalidJson(msg)
+            | AppError::InvalidQuery(msg)
+            | AppError::InvalidPath(msg) => msg.clone(),
+        }
+    }
+
+    pub fn details(&self) -> Vec<FieldError> {
+        match self {
+            AppError::Validation(errors) => errors.clone(),
+            _ => Vec::new(),
+        }
+    }
+}
+
 // Tells actix how to convert this error into an HTTP response
 impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::Database(_) | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::BadRequest(_)
+            | AppError::InvalidJson(_)
+            | AppError::InvalidQuery(_)
+            | AppError::InvalidPath(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::PreconditionFailed(_) => StatusCode::PRECONDITION_FAILED,
+        }
+    }
+
     // When you return Result<T, AppError>, this function is auto called by actix
     fn error_response(&self) -> HttpResponse {
-        match self {
-            AppError::Database(_) => HttpResponse::InternalServerError().body("Database error occurred"),
-            AppError::NotFound(msg) => HttpResponse::NotFound().body(msg.clone()),
-            AppError::BadRequest(msg) => HttpResponse::BadRequest().body(msg.clone()),
-            AppError::Unauthorized(msg) => HttpResponse::Unauthorized().body(msg.clone()),
+        HttpResponse::build(self.status_code()).json(ErrorBody {
+            status: "error",
+            code: self.code(),
+            message: self.message(),
+            details: self.details(),
+            request_id: middleware::current_request_id(),
+        })
+    }
+}
+
+// Database errors from writes: UNIQUE violations become 409 Conflict, everything else stays a Database error.
+impl From<SqlxError> for AppError {
+    fn from(err: SqlxError) -> Self {
+        if let SqlxError::Database(db_err) = &err
+            && db_err.is_unique_violation()
+        {
+            // SQLite reports "UNIQUE constraint failed: bugReport.title"
+            let column = db_err
+                .message()
+                .rsplit('.')
+                .next()
+                .unwrap_or("value")
+                .to_string();
+            return AppError::Conflict(format!("A record with this {} already exists", column));
+        }
+        AppError::Database(err)
+    }
+}
+
+// Collects every failed rule from `validator` into field-level details, sorted by field name.
+impl From<ValidationErrors> for AppError {
+    fn from(errors: ValidationErrors) -> Self {
+        let mut details = Vec::new();
+        collect_field_errors(&errors, "", &mut details);
+        details.sort_by(|a, b| a.field.cmp(&b.field));
+        AppError::Validation(details)
+    }
+}
+
+// Flattens nested validation errors into dotted field paths ("changes.fixed_by", "bug_ids[2]").
+// Struct-level rules are reported against the struct itself, or "body" at the top level.
+fn collect_field_errors(errors: &ValidationErrors, prefix: &str, details: &mut Vec<FieldError>) {
+    for (field, kind) in errors.errors() {
+        let path = match (prefix.is_empty(), *field) {
+            (true, "__all__") => "body".to_string(),
+            (false, "__all__") => prefix.to_string(),
+            (true, name) => name.to_string(),
+            (false, name) => format!("{}.{}", prefix, name),
+        };
+
+        match kind {
+            ValidationErrorsKind::Field(field_errors) => {
+                details.extend(field_errors.iter().map(|error| FieldError {
+                    field: path.clone(),
+                    message: error
+                        .message
+                        .as_ref()
+                        .map(|m| m.to_string())
+                        .unwrap_or_else(|| format!("failed the '{}' rule", error.code)),
+                }));
+            }
+            ValidationErrorsKind::Struct(nested) => collect_field_errors(nested, &path, details),
+            ValidationErrorsKind::List(items) => {
+                for (index, nested) in items {
+                    collect_field_errors(nested, &format!("{}[{}]", path, index), details);
+                }
+            }
         }
     }
-}
\ No newline at end of file
+}
+
+// Error handlers for the actix extractors, registered in main.rs through JsonConfig, QueryConfig and PathConfig,
+// so malformed input produces the same JSON body as every other error.
+// Well-formed JSON with wrong or missing values (e.g. an unknown severity) is a 422, like any other validation failure.
+pub fn json_error_handler(err: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    match &err {
+        JsonPayloadError::Deserialize(e) if e.is_data() => AppError::Validation(vec![FieldError {
+            field: "body".to_string(),
+            message: e.to_string(),
+        }])
+        .into(),
+        _ => AppError::InvalidJson(err.to_string()).into(),
+    }
+}
+
+pub fn query_error_handler(err: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    AppError::InvalidQuery(err.to_string()).into()
+}
+
+pub fn path_error_handler(err: PathError, _req: &HttpRequest) -> actix_web::Error {
+    AppError::InvalidPath(err.to_string()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::{call_service, init_service, read_body_json, TestRequest};
+    use actix_web::{web, App, Responder};
+    use serde::Deserialize;
+    use serde_json::Value;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use crate::middleware::{RequestIdMiddleware, REQUEST_ID_HEADER};
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Body {
+        severity: String,
+        count: u32,
+    }
+
+    async fn json(_: web::Json<Body>) -> impl Responder {
+        "ok"
+    }
+
+    async fn query(_: web::Query<Body>) -> impl Responder {
+        "ok"
+    }
+
+    async fn path(_: web::Path<u32>) -> impl Responder {
+        "ok"
+    }
+
+    async fn not_found() -> Result<&'static str, AppError> {
+        Err(AppError::NotFound("Bug not found".to_string()))
+    }
+
+    async fn database() -> Result<&'static str, AppError> {
+        Err(AppError::Database(SqlxError::RowNotFound))
+    }
+
+    // Sends `req` to an app wired like main.rs and returns the status and the JSON body.
+    async fn call(req: TestRequest) -> (StatusCode, Value) {
+        let app = init_service(
+            App::new()
+                .app_data(web::JsonConfig::default().error_handler(json_error_handler))
+                .app_data(web::QueryConfig::default().error_handler(query_error_handler))
+                .app_data(web::PathConfig::default().error_handler(path_error_handler))
+                .wrap(RequestIdMiddleware)
+                .route("/json", web::post().to(json))
+                .route("/query", web::get().to(query))
+                .route("/path/{id}", web::get().to(path))
+                .route("/not-found", web::get().to(not_found))
+                .route("/database", web::get().to(database)),
+        )
+        .await;
+        let res = call_service(&app, req.to_request()).await;
+        let status = res.status();
+        (status, read_body_json(res).await)
+    }
+
+    #[actix_web::test]
+    async fn error_body_carries_code_message_details_and_request_id() {
+        let req = TestRequest::get().uri("/not-found").insert_header((REQUEST_ID_HEADER, "abc-123"));
+        let (status, body) = call(req).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "status": "error",
+                "code": "not_found",
+                "message": "Bug not found",
+                "details": [],
+                "request_id": "abc-123"
+            })
+        );
+    }
+
+    #[actix_web::test]
+    async fn server_errors_do_not_leak_their_cause() {
+        let (status, body) = call(TestRequest::get().uri("/database")).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["code"], "database_error");
+        assert_eq!(body["message"], "Database error occurred");
+        // Generated when the client sends none
+        assert!(body["request_id"].as_str().is_some_and(|id| !id.is_empty()));
+    }
+
+    #[actix_web::test]
+    async fn malformed_json_is_400_and_wrong_values_are_422() {
+        let syntax = TestRequest::post().uri("/json").insert_header(("content-type", "application/json"));
+        let (status, body) = call(syntax.set_payload("{\"severity\": ")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_json");
+
+        let data = TestRequest::post().uri("/json").set_json(serde_json::json!({ "severity": "high", "count": -1 }));
+        let (status, body) = call(data).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["code"], "validation_failed");
+        assert_eq!(body["details"][0]["field"], "body");
+
+        let missing = TestRequest::post().uri("/json").set_json(serde_json::json!({ "severity": "high" }));
+        assert_eq!(call(missing).await.0, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[actix_web::test]
+    async fn bad_query_strings_and_paths_are_400() {
+        let (status, body) = call(TestRequest::get().uri("/query?severity=high&count=many")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_query");
+
+        let (status, body) = call(TestRequest::get().uri("/path/abc")).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_path");
+    }
+
+    #[actix_web::test]
+    async fn unique_violations_become_409_naming_the_column() {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        sqlx::query("CREATE TABLE project (name TEXT UNIQUE)").execute(&pool).await.unwrap();
+        sqlx::query("INSERT INTO project (name) VALUES ('A')").execute(&pool).await.unwrap();
+
+        let duplicate = sqlx::query("INSERT INTO project (name) VALUES ('A')").execute(&pool).await.unwrap_err();
+        let error = AppError::from(duplicate);
+        assert_eq!(error.status_code(), StatusCode::CONFLICT);
+        assert_eq!(error.message(), "A record with this name already exists");
+
+        let other = sqlx::query("INSERT INTO missing (name) VALUES ('A')").execute(&pool).await.unwrap_err();
+        assert!(matches!(AppError::from(other), AppError::Database(_)));
+    }
+
+    #[test]
+    fn nested_validation_errors_get_dotted_paths() {
+        let mut nested = ValidationErrors::new();
+        nested.add("fixed_by", validator::ValidationError::new("uuid").with_message("fixed_by must be a UUID".into()));
+        let mut errors = ValidationErrors::new();
+        errors.add("title", validator::ValidationError::new("length"));
+        let errors = ValidationErrors::merge(Err(errors), "changes", Err(nested)).unwrap_err();
+
+        let AppError::Validation(details) = AppError::from(errors) else { panic!("expected a validation error") };
+        let fields: Vec<_> = details.iter().map(|d| (d.field.as_str(), d.message.as_str())).collect();
+        assert_eq!(fields, vec![("changes.fixed_by", "fixed_by must be a UUID"), ("title", "failed the 'length' rule")]);
+    }
+}
@@ -0,0 +1,182 @@
+// Domain events raised by the handlers after a change has been saved, e.g. "bug.created".
+// `publish` hands each event to every consumer: the bug watcher list, the webhook delivery queue,
+// the email outbox, the notification inbox and the in-process bus that feeds the `/events` stream.
+use serde::{Serialize, Serializer};
+use sqlx::SqlitePool;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use tokio::sync::broadcast;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::config::EmailConfig;
+use crate::models::{BugReport, ProjectRecord};
+use crate::{email, notifications, watchers, webhooks};
+
+// Kinds of events, named "<resource>.<action>" on the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    BugCreated,
+    BugUpdated,
+    BugAssigned,
+    BugFixed,
+    BugReopened,
+    BugDeleted,
+    BugRestored,
+    BugMentioned,
+    ProjectCreated,
+}
+
+impl EventKind {
+    pub const ALL: [EventKind; 9] = [
+        EventKind::BugCreated,
+        EventKind::BugUpdated,
+        EventKind::BugAssigned,
+        EventKind::BugFixed,
+        EventKind::BugReopened,
+        EventKind::BugDeleted,
+        EventKind::BugRestored,
+        EventKind::BugMentioned,
+        EventKind::ProjectCreated,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::BugCreated => "bug.created",
+            EventKind::BugUpdated => "bug.updated",
+            EventKind::BugAssigned => "bug.assigned",
+            EventKind::BugFixed => "bug.fixed",
+            EventKind::BugReopened => "bug.reopened",
+            EventKind::BugDeleted => "bug.deleted",
+            EventKind::BugRestored => "bug.restored",
+            EventKind::BugMentioned => "bug.mentioned",
+            EventKind::ProjectCreated => "project.created",
+        }
+    }
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for EventKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        EventKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s.trim().to_ascii_lowercase())
+            .ok_or_else(|| format!("unknown event '{}'", s))
+    }
+}
+
+impl Serialize for EventKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+// An event together with the resource it is about. `data` is the resource as the API returns it.
+#[derive(Serialize, Debug, Clone)]
+pub struct DomainEvent {
+    pub event: EventKind,
+    pub project_id: Uuid,
+    pub actor: Option<Uuid>, // The authenticated user that caused the event
+    pub occurred_at: String,
+    pub data: serde_json::Value,
+    // Fields of the resource that changed, e.g. {"severity": {"from": "low", "to": "high"}}; only on bug.updated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changes: Option<serde_json::Value>,
+    // Users newly @mentioned in the bug's description; only on bug.mentioned
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mentioned: Vec<Uuid>,
+}
+
+impl DomainEvent {
+    pub fn bug(event: EventKind, bug: &BugReport, actor: Option<Uuid>) -> Self {
+        DomainEvent {
+            event,
+            project_id: bug.project_id,
+            actor,
+            occurred_at: chrono::Utc::now().to_rfc3339(),
+            data: serde_json::to_value(bug).unwrap_or_default(),
+            changes: None,
+            mentioned: Vec::new(),
+        }
+    }
+
+    pub fn mention(bug: &BugReport, mentioned: Vec<Uuid>, actor: Option<Uuid>) -> Self {
+        DomainEvent { mentioned, ..DomainEvent::bug(EventKind::BugMentioned, bug, actor) }
+    }
+
+    // Adds the fields in which the bug differs from how it was before the change.
+    pub fn with_changes(mut self, before: &BugReport) -> Self {
+        let before = serde_json::to_value(before).unwrap_or_default();
+        let changes = self
+            .data
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter(|(field, value)| field.as_str() != "version" && before[field.as_str()] != **value)
+            .map(|(field, value)| (field.clone(), serde_json::json!({ "from": before[field.as_str()], "to": value })))
+            .collect();
+        self.changes = Some(serde_json::Value::Object(changes));
+        self
+    }
+
+    pub fn project(project: &ProjectRecord, actor: Option<Uuid>) -> Self {
+        DomainEvent {
+            event: EventKind::ProjectCreated,
+            project_id: project.id,
+            actor,
+            occurred_at: chrono::Utc::now().to_rfc3339(),
+            data: serde_json::to_value(project).unwrap_or_default(),
+            changes: None,
+            mentioned: Vec::new(),
+        }
+    }
+}
+
+// Events buffered per live subscriber; one that falls further behind skips ahead and is told to resync.
+const BUS_CAPACITY: usize = 256;
+
+fn bus() -> &'static broadcast::Sender<DomainEvent> {
+    static BUS: OnceLock<broadcast::Sender<DomainEvent>> = OnceLock::new();
+    BUS.get_or_init(|| broadcast::channel(BUS_CAPACITY).0)
+}
+
+// Receives every event published from now on.
+pub fn subscribe() -> broadcast::Receiver<DomainEvent> {
+    bus().subscribe()
+}
+
+// Formats the event as a Server-Sent Events message for the `/events` stream.
+pub fn sse_frame(event: &DomainEvent) -> String {
+    format!("event: {}\ndata: {}\n\n", event.event, serde_json::to_string(event).unwrap_or_default())
+}
+
+// Passes the events to their consumers. Called after the change is committed, so a failure here
+// is logged rather than returned: the client's request has already succeeded.
+// Emails are only queued when `email_config` has an SMTP relay.
+pub async fn publish(pool: &SqlitePool, email_config: &EmailConfig, events: Vec<DomainEvent>) {
+    for event in events {
+        // First, so that new reporters and assignees are among the watchers the notifications go to
+        if let Err(e) = watchers::auto_watch(pool, &event).await {
+            error!(event = %event.event, error = ?e, "Failed to add watchers");
+        }
+        if let Err(e) = webhooks::enqueue(pool, &event).await {
+            error!(event = %event.event, error = ?e, "Failed to queue webhooks");
+        }
+        if let Err(e) = email::enqueue(pool, email_config, &event).await {
+            error!(event = %event.event, error = ?e, "Failed to queue emails");
+        }
+        if let Err(e) = notifications::enqueue(pool, &event).await {
+            error!(event = %event.event, error = ?e, "Failed to add notifications");
+        }
+        // Fails only when nobody is listening
+        let _ = bus().send(event);
+    }
+}
@@ -0,0 +1,252 @@
+// Formatting of bug exports (`GET /bugs/export`) as CSV, a JSON array or newline-delimited JSON.
+// The handler streams rows from the database one at a time and uses `ExportWriter` to turn each
+// into the bytes sent to the client, so large exports are never held in memory.
+use serde_json::{Map, Value};
+
+use crate::error::{AppError, FieldError};
+use crate::models::{BugExportRow, ExportFormat};
+
+// Columns that can be selected with `?columns=...`, in their default order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportColumn {
+    Id,
+    Title,
+    Description,
+    Project,
+    Severity,
+    Priority,
+    Status,
+    ReportedBy,
+    FixedBy,
+    Labels,
+    CreatedAt,
+    Version,
+}
+
+impl ExportColumn {
+    pub const ALL: [ExportColumn; 12] = [
+        ExportColumn::Id,
+        ExportColumn::Title,
+        ExportColumn::Description,
+        ExportColumn::Project,
+        ExportColumn::Severity,
+        ExportColumn::Priority,
+        ExportColumn::Status,
+        ExportColumn::ReportedBy,
+        ExportColumn::FixedBy,
+        ExportColumn::Labels,
+        ExportColumn::CreatedAt,
+        ExportColumn::Version,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportColumn::Id => "id",
+            ExportColumn::Title => "title",
+            ExportColumn::Description => "description",
+            ExportColumn::Project => "project",
+            ExportColumn::Severity => "severity",
+            ExportColumn::Priority => "priority",
+            ExportColumn::Status => "status",
+            ExportColumn::ReportedBy => "reported_by",
+            ExportColumn::FixedBy => "fixed_by",
+            ExportColumn::Labels => "labels",
+            ExportColumn::CreatedAt => "created_at",
+            ExportColumn::Version => "version",
+        }
+    }
+
+    fn value(&self, row: &BugExportRow) -> Value {
+        match self {
+            ExportColumn::Id => Value::from(row.id.to_string()),
+            ExportColumn::Title => Value::from(row.title.clone()),
+            ExportColumn::Description => Value::from(row.description.clone()),
+            ExportColumn::Project => row.project_name.clone().map_or(Value::Null, Value::from),
+            ExportColumn::Severity => Value::from(row.severity.as_str()),
+            ExportColumn::Priority => Value::from(row.priority.as_str()),
+            ExportColumn::Status => Value::from(if row.is_fixed { "fixed" } else { "open" }),
+            ExportColumn::ReportedBy => row.reported_by.clone().map_or(Value::Null, Value::from),
+            ExportColumn::FixedBy => row.fixed_by.clone().map_or(Value::Null, Value::from),
+            ExportColumn::Labels => row.labels.clone().map_or(Value::Null, Value::from),
+            ExportColumn::CreatedAt => Value::from(row.created_at.clone()),
+            ExportColumn::Version => Value::from(row.version),
+        }
+    }
+}
+
+// Parses the `columns` query parameter; every unknown name is reported as a validation error.
+pub fn parse_columns(columns: Option<&str>) -> Result<Vec<ExportColumn>, AppError> {
+    let Some(columns) = columns else {
+        return Ok(ExportColumn::ALL.to_vec());
+    };
+
+    let mut selected = Vec::new();
+    let mut errors = Vec::new();
+    for name in columns.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+        match ExportColumn::ALL.iter().find(|column| column.name() == name) {
+            Some(column) if !selected.contains(column) => selected.push(*column),
+            Some(_) => {}
+            None => errors.push(FieldError {
+                field: "columns".to_string(),
+                message: format!("unknown column '{}'", name),
+            }),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(AppError::Validation(errors));
+    }
+    if selected.is_empty() {
+        return Err(AppError::Validation(vec![FieldError {
+            field: "columns".to_string(),
+            message: "at least one column is required".to_string(),
+        }]));
+    }
+    Ok(selected)
+}
+
+// Content type and file extension of each format.
+pub fn content_type(format: ExportFormat) -> &'static str {
+    match format {
+        ExportFormat::Csv => "text/csv; charset=utf-8",
+        ExportFormat::Json => "application/json",
+        ExportFormat::Ndjson => "application/x-ndjson",
+    }
+}
+
+pub fn file_extension(format: ExportFormat) -> &'static str {
+    match format {
+        ExportFormat::Csv => "csv",
+        ExportFormat::Json => "json",
+        ExportFormat::Ndjson => "ndjson",
+    }
+}
+
+// Turns rows into output chunks: `header()` once, `row()` per bug, then `footer()`.
+pub struct ExportWriter {
+    format: ExportFormat,
+    columns: Vec<ExportColumn>,
+    rows_written: usize,
+}
+
+impl ExportWriter {
+    pub fn new(format: ExportFormat, columns: Vec<ExportColumn>) -> Self {
+        ExportWriter { format, columns, rows_written: 0 }
+    }
+
+    pub fn header(&self) -> Vec<u8> {
+        match self.format {
+            ExportFormat::Csv => csv_record(self.columns.iter().map(|c| c.name().to_string())),
+            ExportFormat::Json => b"[".to_vec(),
+            ExportFormat::Ndjson => Vec::new(),
+        }
+    }
+
+    pub fn row(&mut self, row: &BugExportRow) -> Vec<u8> {
+        let chunk = match self.format {
+            ExportFormat::Csv => csv_record(self.columns.iter().map(|c| match c.value(row) {
+                Value::Null => String::new(),
+                Value::String(s) => s,
+                other => other.to_string(),
+            })),
+            ExportFormat::Json => {
+                let mut out = if self.rows_written == 0 { Vec::new() } else { b",".to_vec() };
+                out.extend(self.json_object(row));
+                out
+            }
+            ExportFormat::Ndjson => {
+                let mut out = self.json_object(row);
+                out.push(b'\n');
+                out
+            }
+        };
+        self.rows_written += 1;
+        chunk
+    }
+
+    pub fn footer(&self) -> Vec<u8> {
+        match self.format {
+            ExportFormat::Json => b"]".to_vec(),
+            ExportFormat::Csv | ExportFormat::Ndjson => Vec::new(),
+        }
+    }
+
+    fn json_object(&self, row: &BugExportRow) -> Vec<u8> {
+        let object: Map<String, Value> = self
+            .columns
+            .iter()
+            .map(|column| (column.name().to_string(), column.value(row)))
+            .collect();
+        serde_json::to_vec(&object).unwrap_or_default()
+    }
+}
+
+// Writes one CSV record, quoting fields as needed.
+fn csv_record(fields: impl Iterator<Item = String>) -> Vec<u8> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let record: Vec<String> = fields.collect();
+    // Writing to a Vec<u8> cannot fail
+    let _ = writer.write_record(&record);
+    writer.into_inner().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(result: Result<Vec<ExportColumn>, AppError>) -> Vec<String> {
+        match result {
+            Err(AppError::Validation(errors)) => errors.into_iter().map(|e| e.message).collect(),
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn every_column_is_exported_by_default() {
+        assert_eq!(parse_columns(None).unwrap(), ExportColumn::ALL.to_vec());
+    }
+
+    #[test]
+    fn columns_keep_the_requested_order() {
+        let columns = parse_columns(Some("title, id,labels")).unwrap();
+        assert_eq!(columns, vec![ExportColumn::Title, ExportColumn::Id, ExportColumn::Labels]);
+    }
+
+    #[test]
+    fn repeated_and_empty_column_names_are_ignored() {
+        let columns = parse_columns(Some("id,,title,id,")).unwrap();
+        assert_eq!(columns, vec![ExportColumn::Id, ExportColumn::Title]);
+    }
+
+    #[test]
+    fn every_unknown_column_is_reported() {
+        let errors = messages(parse_columns(Some("id,summary,Title")));
+        assert_eq!(errors, vec!["unknown column 'summary'", "unknown column 'Title'"]);
+    }
+
+    #[test]
+    fn at_least_one_column_is_required() {
+        assert_eq!(messages(parse_columns(Some(" , "))), vec!["at least one column is required"]);
+    }
+
+    #[test]
+    fn csv_rows_follow_the_selected_columns() {
+        let row = BugExportRow {
+            id: uuid::Uuid::nil(),
+            title: "Crash, on save".to_string(),
+            description: "d".to_string(),
+            project_name: None,
+            severity: crate::models::Severity::High,
+            priority: crate::models::Priority::Low,
+            is_fixed: true,
+            reported_by: Some("admin".to_string()),
+            fixed_by: None,
+            labels: None,
+            created_at: "2024-01-01 00:00:00".to_string(),
+            version: 2,
+        };
+        let mut writer = ExportWriter::new(ExportFormat::Csv, parse_columns(Some("title,status,fixed_by,version")).unwrap());
+        assert_eq!(writer.header(), b"title,status,fixed_by,version\n");
+        assert_eq!(writer.row(&row), b"\"Crash, on save\",fixed,,2\n");
+    }
+}
@@ -3,19 +3,95 @@
 // `sqlx` is used for database connection pooling. `SqlitePool` is a specific pool type for SQLite.
 // `uuid` is used for generating unique identifiers.
 // `crate::models` and `crate::auth` denote relative imports from the current project's `models` and `auth` modules, respectively.
-use actix_web::{web, HttpResponse, Responder, HttpRequest, Error, Result};
-use sqlx::SqlitePool;
+use actix_web::{guard, web, HttpResponse, Responder, HttpRequest, Result};
+use actix_web::middleware::DefaultHeaders;
+use actix_web::http::header::{self, EntityTag, Header};
+use sqlx::{QueryBuilder, Sqlite, SqlitePool};
 use uuid::Uuid;
-use hex;
+use futures_util::StreamExt;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
 use tera::{Tera, Context};
-use serde_json;
 
-use crate::models::{User, BugReport, LoginRequest, LoginResponse, CreateBug, ProjectRecord, BugAssignment, BugAssignmentRequest, SimpleUser, BugFilter, UpdateBugReport, CreateProject};
-use crate::auth;
-use crate::error::AppError;
+use crate::models::{User, BugReport, LoginRequest, LoginResponse, CreateBug, ProjectRecord, BugAssignmentRequest, AssignBug, SimpleUser, BugFilter, BugSort, UpdateBugReport, CreateProject, BugDetails, BulkBugRequest, BulkBugResponse, BulkItemResult, BulkOutcome, ExportQuery, BugExportRow, ImportQuery, CreateWebhook, Webhook, WebhookRecord, WebhookFilter, WebhookDelivery, UpdatePreferences, EventStreamQuery, NotificationQuery, CreateBugLink, BugLinkKind, SimilarBugsRequest, SimilarBug, CreatedBug, TrashedBug, BugLink, ImportReport, NotificationPreferences, WatchedBug, NotificationInbox, Notification, normalize_label, MAX_BULK_ITEMS};
+use crate::export::{self, ExportWriter};
+use crate::import::{self, ImportOptions};
+use crate::events::{self, DomainEvent, EventKind};
+use crate::{email, links, markdown, mentions, notifications, similarity, watchers, webhooks};
+use crate::{auth, health, metrics, openapi, shutdown};
+use crate::config::{Config, EmailConfig};
+use crate::error::{AppError, ErrorBody, FieldError};
+use validator::Validate;
+use tracing::{debug, error, info, warn};
+
+// SQL expressions mapping the enum columns to a numeric weight, so ORDER BY ranks critical > high > medium > low.
+const SEVERITY_RANK_SQL: &str =
+    "CASE severity WHEN 'critical' THEN 4 WHEN 'high' THEN 3 WHEN 'medium' THEN 2 ELSE 1 END";
+const PRIORITY_RANK_SQL: &str =
+    "CASE priority WHEN 'urgent' THEN 4 WHEN 'high' THEN 3 WHEN 'medium' THEN 2 ELSE 1 END";
+
+// Columns selected for `BugExportRow`, resolving ids to user and project names.
+const EXPORT_COLUMNS: &str = "id, title, description, severity, priority, is_fixed, created_at, version, \
+    (SELECT project_name FROM projectRecord WHERE projectRecord.id = bugReport.project_id) AS project_name, \
+    (SELECT username FROM users WHERE users.id = bugReport.reported_by) AS reported_by, \
+    (SELECT username FROM users WHERE users.id = bugReport.fixed_by) AS fixed_by, \
+    (SELECT group_concat(label, ',') FROM bugLabel WHERE bugLabel.bug_id = bugReport.id) AS labels";
+
+// Columns selected for every `BugReport` query.
+const BUG_COLUMNS: &str =
+    "id, project_id, title, description, reported_by, fixed_by, severity, priority, is_fixed, created_at, version";
+
+// Prefix of version 1 of the JSON API. A later version gets its own constant, scope and `vN_routes` function
+// registered next to this one in `config`, reusing the handlers that did not change.
+pub const API_V1: &str = "/api/v1";
+
+// Headers marking the unversioned JSON routes as deprecated aliases of /api/v1 (RFC 9745 and RFC 8594).
+// Deprecated since 2026-10-18; they stop working after the sunset date.
+const LEGACY_DEPRECATION: &str = "@1792281600";
+const LEGACY_SUNSET: &str = "Sun, 18 Apr 2027 00:00:00 GMT";
+const LEGACY_LINK: &str = "</docs>; rel=\"deprecation\"";
+
+// First path segments of the routes in `legacy_routes`. Other unversioned paths are not aliases and get the plain 404.
+const LEGACY_PREFIXES: [&str; 6] = ["/login", "/projects", "/bugs", "/webhooks", "/me", "/events"];
 
 // Function to configure the service, setting up the routes available in this web application.
 pub fn config(cfg: &mut web::ServiceConfig) {
+    // Version 1 of the JSON API
+    cfg.service(web::scope(API_V1).configure(v1_routes));
+
+    // HTML page for assigning bugs, which is not part of the JSON API
+    cfg.route("/bugs/assign", web::get().to(render_bug_form));
+
+    // OpenAPI description of the current API version and a page for browsing it
+    cfg.service(web::resource("/openapi.json").route(web::get().to(openapi::openapi_json)));
+    cfg.service(web::resource("/docs").route(web::get().to(openapi::api_docs)));
+
+    // Prometheus metrics, scraped without a token
+    cfg.service(web::resource("/metrics").route(web::get().to(metrics::metrics)));
+
+    // Liveness, readiness and version for orchestration scripts, without a token
+    cfg.service(web::resource("/healthz").route(web::get().to(health::healthz)));
+    cfg.service(web::resource("/readyz").route(web::get().to(health::readyz)));
+    cfg.service(web::resource("/version").route(web::get().to(health::version)));
+
+    // The JSON routes as they were before versioning, kept as deprecated aliases until the sunset date.
+    // Registered last and guarded by their prefixes, so that unknown paths such as /favicon.ico get a 404 without
+    // the deprecation headers.
+    cfg.service(
+        web::scope("")
+            .guard(guard::fn_guard(|ctx| is_legacy_path(ctx.head().uri.path())))
+            .wrap(
+                DefaultHeaders::new()
+                    .add(("Deprecation", LEGACY_DEPRECATION))
+                    .add(("Sunset", LEGACY_SUNSET))
+                    .add((header::LINK, LEGACY_LINK))
+            )
+            .configure(legacy_routes)
+    );
+}
+
+// Routes of version 1 of the JSON API, relative to API_V1.
+fn v1_routes(cfg: &mut web::ServiceConfig) {
     // The login route is a standalone public endpoint
     cfg.service(web::resource("/login").route(web::post().to(login_function)));
 
@@ -25,10 +101,9 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             // Public GET /projects
             .route("", web::get().to(get_projects))
             // Authenticated POST /projects (add_project)
-            // This nested service ensures only the POST method for /projects is authenticated
             .service(
-                web::resource("") // Relative path to /projects, so it matches /projects
-                    .wrap(auth::AuthMiddleware) // Apply middleware only to this resource
+                web::resource("")
+                    .wrap(auth::AuthMiddleware)
                     .route(web::post().to(add_project))
             )
     );
@@ -38,93 +113,162 @@ pub fn config(cfg: &mut web::ServiceConfig) {
         web::scope("/bugs")
             // Public GET routes for /bugs
             .route("", web::get().to(get_bugs))
-            .route("/assign", web::get().to(render_bug_form))
+            .route("/export", web::get().to(export_bugs))
+            // Authenticated GET /bugs/trash, registered before /{id} so "trash" is not taken for a bug id
+            .service(
+                web::resource("/trash")
+                    .wrap(auth::AuthMiddleware)
+                    .route(web::get().to(get_trash))
+            )
             .route("/{id}", web::get().to(get_bug_by_id))
 
-            // Nested scope for authenticated /bugs operations (POST, PATCH, DELETE)
-            // This inner scope inherits the "/bugs" prefix from its parent.
+            // Nested scope for authenticated /bugs operations
             .service(
-                web::scope("") // This effectively means "/bugs/*"
-                    .wrap(auth::AuthMiddleware) // Apply AuthMiddleware to all routes within this nested scope
-                    // Authenticated POST /bugs/assign
-                    .route("/assign", web::post().to(assign_bug))
-                    // Authenticated POST /bugs/new
-                    .route("/new", web::post().to(create_bug))
+                web::scope("")
+                    .wrap(auth::AuthMiddleware)
+                    // Authenticated POST /bugs to report a bug
+                    .route("", web::post().to(create_bug))
+                    // Authenticated POST /bugs/similar, listing likely duplicates before a bug is reported
+                    .route("/similar", web::post().to(similar_bugs))
+                    // Authenticated POST /bugs/bulk
+                    .route("/bulk", web::post().to(bulk_update_bugs))
+                    // Authenticated POST /bugs/import, accepting export files of up to 10 MB
+                    .service(
+                        web::resource("/import")
+                            .app_data(web::PayloadConfig::new(10 * 1024 * 1024))
+                            .route(web::post().to(import_bugs))
+                    )
+                    // Authenticated PUT /bugs/{id}/assignee
+                    .route("/{id}/assignee", web::put().to(set_bug_assignee))
+                    // Authenticated POST and DELETE /bugs/{id}/watch to follow or unfollow a bug
+                    .route("/{id}/watch", web::post().to(watch_bug))
+                    .route("/{id}/watch", web::delete().to(unwatch_bug))
+                    // Authenticated POST /bugs/{id}/links and DELETE /bugs/{id}/links/{link_id}
+                    .route("/{id}/links", web::post().to(add_bug_link))
+                    .route("/{id}/links/{link_id}", web::delete().to(remove_bug_link))
+                    // Authenticated POST /bugs/{id}/restore to take a bug out of the trash
+                    .route("/{id}/restore", web::post().to(restore_bug))
                     // Authenticated PATCH /bugs/{id}
                     .route("/{id}", web::patch().to(update_bug_details))
                     // Authenticated DELETE /bugs/{id}
                     .route("/{id}", web::delete().to(delete_bug))
             )
     );
+
+    // Webhook subscriptions and their delivery log; every route requires authentication
+    cfg.service(
+        web::scope("/webhooks")
+            .wrap(auth::AuthMiddleware)
+            .route("", web::post().to(create_webhook))
+            .route("", web::get().to(get_webhooks))
+            .route("/deliveries/{id}/redeliver", web::post().to(redeliver_webhook))
+            .route("/{id}", web::delete().to(delete_webhook))
+            .route("/{id}/deliveries", web::get().to(get_webhook_deliveries))
+    );
+
+    // Settings of the authenticated user
+    cfg.service(
+        web::scope("/me")
+            .wrap(auth::AuthMiddleware)
+            .route("/preferences", web::get().to(get_preferences))
+            .route("/preferences", web::patch().to(update_preferences))
+            .route("/watching", web::get().to(get_watching))
+            .route("/notifications", web::get().to(get_notifications))
+            .route("/notifications/read-all", web::post().to(mark_all_notifications_read))
+            .route("/notifications/{id}/read", web::post().to(mark_notification_read))
+    );
+
+    // Live stream of bug and project events; authenticates in the handler so the token may be in the query string
+    cfg.service(web::resource("/events").route(web::get().to(stream_events)));
+}
+
+// The unversioned routes that preceded API_V1: the routes of version 1, plus the two old shapes they replaced.
+// Registered first, so that they are matched before the /bugs scope of version 1.
+fn legacy_routes(cfg: &mut web::ServiceConfig) {
+    // POST /bugs/new, now POST /bugs
+    cfg.service(
+        web::resource("/bugs/new")
+            .wrap(auth::AuthMiddleware)
+            .route(web::post().to(create_bug))
+    );
+    // POST /bugs/assign with the bug id in the body, now PUT /bugs/{id}/assignee
+    cfg.service(
+        web::resource("/bugs/assign")
+            .wrap(auth::AuthMiddleware)
+            .route(web::post().to(assign_bug))
+    );
+
+    v1_routes(cfg);
+}
+
+// Whether the unversioned path falls under one of the LEGACY_PREFIXES.
+fn is_legacy_path(path: &str) -> bool {
+    LEGACY_PREFIXES
+        .iter()
+        .any(|prefix| path.strip_prefix(prefix).is_some_and(|rest| rest.is_empty() || rest.starts_with('/')))
 }
 
 // Asynchronous function for user login, expected to receive a JSON payload corresponding to a `User` object.
 // Returns a responder, encapsulating an HTTP response.
+#[utoipa::path(
+    post, path = "/login", tag = "auth",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Token, or a failure message", body = LoginResponse),
+        (status = 400, description = "Malformed request", body = ErrorBody),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody)
+    )
+)]
 async fn login_function(
     pool: web::Data<SqlitePool>, 
+    config: web::Data<Config>,
     body: web::Json<LoginRequest>
-) -> impl Responder {
+) -> Result<impl Responder, AppError> {
     let salt = "bugtrack2025";
     let user = sqlx::query_as::<_, User>(
              "SELECT id, username, hashed_password FROM users WHERE username = ?",
         )
         .bind(&body.username)
         .fetch_optional(pool.get_ref())
-        .await;
-
-    match user {
-        Ok(Some(user)) => {
-            // Verify password
-            match auth::verify_with_salt(&body.password, salt, &user.hashed_password) {
-                Ok(true) => {
-                    // Password correct, create token
-                    let token = auth::create_token(user.id);
-
-                    HttpResponse::Ok().json(LoginResponse {
-                        status: "success".to_string(),
-                        message: "Login successful".to_string(),
-                        token: Some(token),
-                    })
-                }
-                Ok(false) => {
-                    // Password incorrect
-                    HttpResponse::Unauthorized().json(LoginResponse {
-                        status: "failure".to_string(),
-                        message: "Invalid credentials".to_string(),
-                        token: None,
-                    })
-                }
-                Err(_) => {
-                    // Error verifying password
-                    HttpResponse::InternalServerError().json(LoginResponse {
-                        status: "failure".to_string(),
-                        message: "Internal server error".to_string(),
-                        token: None,
-                    })
-                }
-            }
-        }
-        Ok(None) => {
-            // User not found
-            HttpResponse::Unauthorized().json(LoginResponse {
-                status: "failure".to_string(),
-                message: "Invalid credentials".to_string(),
-                token: None,
-            })
-        }
-        Err(_) => {
-            // Database error
-            HttpResponse::InternalServerError().json(LoginResponse {
-                status: "failure".to_string(),
-                message: "Database error".to_string(),
-                token: None,
-            })
-        }
+        .await
+        .map_err(AppError::Database)?
+        // User not found
+        .ok_or_else(|| {
+            metrics::record_login(false);
+            warn!("Login failed: unknown username");
+            AppError::Unauthorized("Invalid credentials".to_string())
+        })?;
+
+    // Verify password
+    let password_ok = auth::verify_with_salt(&body.password, salt, &user.hashed_password)
+        .map_err(|e| AppError::Internal(format!("Password verification failed: {}", e)))?;
+
+    metrics::record_login(password_ok);
+    if !password_ok {
+        // Password incorrect
+        warn!(user_id = %user.id, "Login failed: wrong password");
+        return Err(AppError::Unauthorized("Invalid credentials".to_string()));
     }
+
+    // Password correct, create token
+    let token = auth::create_token(user.id, &config.auth);
+    info!(user_id = %user.id, "Login succeeded");
+
+    Ok(HttpResponse::Ok().json(LoginResponse {
+        status: "success".to_string(),
+        message: "Login successful".to_string(),
+        token: Some(token),
+    }))
 }
 
 // Asynchronous function for handling stock purchase requests.
 // Simply responds to the request with a confirmation message.
+#[utoipa::path(
+    get, path = "/projects", tag = "projects",
+    responses(
+        (status = 200, description = "Every project", body = Vec<ProjectRecord>)
+    )
+)]
 async fn get_projects(_pool: web::Data<SqlitePool>) -> Result<impl Responder, AppError> {
     let project = sqlx::query_as::<_, ProjectRecord>(
         "SELECT id, project_name, project_description, created_at, user_id  FROM projectRecord"
@@ -132,8 +276,8 @@ async fn get_projects(_pool: web::Data<SqlitePool>) -> Result<impl Responder, Ap
     .fetch_all(_pool.get_ref())
     .await
     .map_err(|e| {
-        eprintln!("Project query error: {:?}", e);
-        AppError::Database(e.into())
+        error!(error = ?e, "Project query error");
+        AppError::Database(e)
     })?;
 
     Ok(HttpResponse::Ok().json(project))
@@ -142,7 +286,22 @@ async fn get_projects(_pool: web::Data<SqlitePool>) -> Result<impl Responder, Ap
 
 // Asynchronous function for handling stock purchase requests.
 // Simply responds to the request with a confirmation message.
-async fn add_project(_pool: web::Data<SqlitePool>, _body: web::Json<CreateProject>) -> Result<impl Responder, AppError> {
+#[utoipa::path(
+    post, path = "/projects", tag = "projects",
+    request_body = CreateProject,
+    responses(
+        (status = 200, description = "Project created", body = String, content_type = "text/plain"),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 404, description = "Not found", body = ErrorBody),
+        (status = 409, description = "Conflicts with existing data", body = ErrorBody),
+        (status = 422, description = "Validation failed", body = ErrorBody)
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn add_project(_pool: web::Data<SqlitePool>, config: web::Data<Config>, _body: web::Json<CreateProject>, _req: HttpRequest) -> Result<impl Responder, AppError> {
+    // Reject empty or oversized fields before touching the database
+    _body.validate()?;
+
     // Query to get the user by username
     let user = sqlx::query_as::<_, User>(
         "SELECT id, username, hashed_password FROM users WHERE username = ?"
@@ -151,127 +310,365 @@ async fn add_project(_pool: web::Data<SqlitePool>, _body: web::Json<CreateProjec
     .fetch_optional(_pool.get_ref())
     .await
     .map_err(|e| { 
-        eprintln!("User query error: {:?}", e); 
-        AppError::Database(e.into())
+        error!(error = ?e, "User query error"); 
+        AppError::Database(e)
     })? 
     .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
 
     // Generate a new UUID for the project
     let project_id = Uuid::new_v4();
-    let user_id = user.id.clone(); // Get the user's id
+    let user_id = user.id; // Get the user's id
 
     // Insert the new project into the database
-    let project = sqlx::query(
-        "INSERT INTO projectRecord (id, user_id, project_name, project_description) VALUES (?, ?, ?, ?)"
+    let project = sqlx::query_as::<_, ProjectRecord>(
+        "INSERT INTO projectRecord (id, user_id, project_name, project_description) VALUES (?, ?, ?, ?) \
+         RETURNING id, project_name, project_description, created_at, user_id"
     )
-    .bind(&project_id)
-    .bind(&user_id) // Binding user_id from the User struct
+    .bind(project_id)
+    .bind(user_id) // Binding user_id from the User struct
     .bind(&_body.project_title)
     .bind(&_body.project_description)
-    .execute(_pool.get_ref())
+    .fetch_one(_pool.get_ref())
     .await
     .map_err(|e| {
-        eprintln!("Create project error: {:?}", e);
-        AppError::Database(e.into())
+        error!(error = ?e, "Create project error");
+        AppError::from(e)
     })?;
 
+    events::publish(_pool.get_ref(), &config.email, vec![DomainEvent::project(&project, auth::get_authenticated_user_id(&_req))]).await;
+
     Ok(HttpResponse::Ok().body("Project added successfully"))
 }
 
 // Asynchronous function for fetching bug reports based on optional filters.
-async fn get_bugs(_pool: web::Data<SqlitePool>, _filter: web::Query<BugFilter>) -> Result<impl Responder, AppError> {
-    let mut query = "SELECT id, project_id, title, description, reported_by, fixed_by, severity, is_fixed, created_at FROM bugReport WHERE 1=1"
-        .to_string();
+#[utoipa::path(
+    get, path = "/bugs", tag = "bugs",
+    params(BugFilter),
+    responses(
+        (status = 200, description = "Bugs matching the filter", body = Vec<BugReport>),
+        (status = 400, description = "Malformed request", body = ErrorBody),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 404, description = "Not found", body = ErrorBody)
+    ),
+    security((), ("bearer_auth" = []))
+)]
+async fn get_bugs(_pool: web::Data<SqlitePool>, _filter: web::Query<BugFilter>, _req: HttpRequest) -> Result<impl Responder, AppError> {
+    let mut query = bug_filter_query(_pool.get_ref(), BUG_COLUMNS, &_filter, auth::bearer_user_id(&_req)).await?;
+    push_bug_sort(&mut query, _filter.sort);
+
+    // Execute the final query
+    let bugs = query.build_query_as::<BugReport>().fetch_all(_pool.get_ref()).await
+        .map_err(|e| {
+            error!(error = ?e, "Database error");
+            AppError::Database(e)
+        })?;
+
+    let bugs: Vec<BugReport> = bugs.into_iter().map(BugReport::with_description_html).collect();
+    Ok(HttpResponse::Ok().json(bugs))
+}
+
+// Asynchronous function exporting the bugs matching the `GET /bugs` filters as CSV, JSON or NDJSON.
+// Rows are streamed from the database to the client through a channel rather than collected first,
+// with reporter, assignee and project ids resolved to names.
+#[utoipa::path(
+    get, path = "/bugs/export", tag = "bugs",
+    params(BugFilter, ExportQuery),
+    responses(
+        (status = 200, description = "CSV, JSON or NDJSON file of the matching bugs", content(
+            (String = "text/csv"), (String = "application/json"), (String = "application/x-ndjson")
+        )),
+        (status = 400, description = "Malformed request", body = ErrorBody),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 404, description = "Not found", body = ErrorBody)
+    ),
+    security((), ("bearer_auth" = []))
+)]
+async fn export_bugs(
+    pool: web::Data<SqlitePool>,
+    filter: web::Query<BugFilter>,
+    options: web::Query<ExportQuery>,
+    req: HttpRequest,
+) -> Result<impl Responder, AppError> {
+    let columns = export::parse_columns(options.columns.as_deref())?;
+    let format = options.format;
+
+    // Resolve the filter before responding so an unknown project is still a proper 404
+    let mut query = bug_filter_query(pool.get_ref(), EXPORT_COLUMNS, &filter, auth::bearer_user_id(&req)).await?;
+    push_bug_sort(&mut query, filter.sort);
+
+    let (tx, rx) = mpsc::channel::<Result<web::Bytes, AppError>>(64);
+    let pool = pool.get_ref().clone();
+    let mut writer = ExportWriter::new(format, columns);
+
+    actix_web::rt::spawn(async move {
+        if tx.send(Ok(writer.header().into())).await.is_err() {
+            return;
+        }
+
+        let mut rows = query.build_query_as::<BugExportRow>().fetch(&pool);
+        while let Some(row) = rows.next().await {
+            let chunk = match row {
+                Ok(row) => Ok(writer.row(&row).into()),
+                Err(e) => {
+                    // The status line has already been sent, so abort the body instead
+                    error!(error = ?e, "Database error during export");
+                    let _ = tx.send(Err(AppError::Database(e))).await;
+                    return;
+                }
+            };
+            // Stop early if the client disconnected
+            if tx.send(chunk).await.is_err() {
+                return;
+            }
+        }
+
+        let _ = tx.send(Ok(writer.footer().into())).await;
+    });
 
-    if let Some(is_fixed) = _filter.is_fixed {
-        query.push_str(&format!(" AND is_fixed = {}", if is_fixed { 1 } else { 0 }));
+    let body = futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type(export::content_type(format))
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"bugs.{}\"", export::file_extension(format)),
+        ))
+        .streaming(body))
+}
+
+// Builds `SELECT <columns> FROM bugReport WHERE ...` with the conditions of a `BugFilter`.
+// Shared by every endpoint that accepts a filter so they all select the same bugs, none of them in the trash.
+// `current_user` is the logged in user, if any, which `mentioned=me` refers to.
+async fn bug_filter_query(pool: &SqlitePool, columns: &str, filter: &BugFilter, current_user: Option<Uuid>) -> Result<QueryBuilder<'static, Sqlite>, AppError> {
+    let mut query = QueryBuilder::new(format!("SELECT {} FROM bugReport WHERE deleted_at IS NULL", columns));
+
+    if let Some(is_fixed) = filter.is_fixed {
+        query.push(" AND is_fixed = ").push_bind(is_fixed);
     }
 
-    if let Some(severity) = &_filter.severity {
-        query.push_str(&format!(" AND severity = '{}'", severity.replace('\'', "''"))); 
+    if let Some(severity) = filter.severity {
+        query.push(" AND severity = ").push_bind(severity);
     }
 
-    if let Some(project_name) = &_filter.project_name {
+    if let Some(priority) = filter.priority {
+        query.push(" AND priority = ").push_bind(priority);
+    }
+
+    if let Some(project_name) = &filter.project_name {
         // Query for project record by name
         let project = sqlx::query_as::<_, ProjectRecord>(
             "SELECT id, project_name, project_description, created_at, user_id FROM projectRecord WHERE project_name = ?"
         )
         .bind(project_name)
-        .fetch_optional(_pool.get_ref())
+        .fetch_optional(pool)
         .await
         .map_err(|e| {
-            eprintln!("Error fetching project: {:?}", e);
-            AppError::Database(e.into())
+            error!(error = ?e, "Error fetching project");
+            AppError::Database(e)
         })? 
         .ok_or_else(|| AppError::NotFound("Project not found".to_string()))?;
 
         // Now add the project_id condition to the query
-        let project_id_bytes = project.id.as_bytes().to_vec();
-        query.push_str(&format!(" AND project_id = x'{}'", hex::encode(project_id_bytes)));
+        query.push(" AND project_id = ").push_bind(project.id.as_bytes().to_vec());
     }
 
-    // Execute the final query
-    let bugs = sqlx::query_as::<_, BugReport>(&query).fetch_all(_pool.get_ref()).await
-        .map_err(|e| {
-            eprintln!("Database error: {:?}", e);
-            AppError::Database(e.into())
-        })?;
+    if let Some(label) = &filter.label {
+        query
+            .push(" AND id IN (SELECT bug_id FROM bugLabel WHERE label = ")
+            .push_bind(normalize_label(label))
+            .push(")");
+    }
 
-    Ok(HttpResponse::Ok().json(bugs))
+    if let Some(mentioned) = &filter.mentioned {
+        let user_id = if mentioned == "me" {
+            current_user.ok_or_else(|| AppError::Unauthorized("Authentication required for mentioned=me".to_string()))?
+        } else {
+            sqlx::query_scalar::<_, Uuid>("SELECT id FROM users WHERE username = ?")
+                .bind(mentioned)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| {
+                    error!(error = ?e, "Error fetching user");
+                    AppError::Database(e)
+                })?
+                .ok_or_else(|| AppError::NotFound("User not found".to_string()))?
+        };
+        query
+            .push(" AND id IN (SELECT bug_id FROM bugMention WHERE user_id = ")
+            .push_bind(user_id.as_bytes().to_vec())
+            .push(")");
+    }
+
+    Ok(query)
+}
+
+// Apply the requested ordering, ranking enum columns by weight rather than alphabetically
+fn push_bug_sort(query: &mut QueryBuilder<'static, Sqlite>, sort: Option<BugSort>) {
+    match sort {
+        Some(BugSort::Severity) => query.push(format!(" ORDER BY {} DESC, created_at DESC", SEVERITY_RANK_SQL)),
+        Some(BugSort::Priority) => query.push(format!(" ORDER BY {} DESC, created_at DESC", PRIORITY_RANK_SQL)),
+        Some(BugSort::CreatedAt) => query.push(" ORDER BY created_at DESC"),
+        None => query,
+    };
 }
 
 // Asynchronous function for getting a bug by its ID in it path.
-async fn get_bug_by_id(_pool: web::Data<SqlitePool>, _bug_id: web::Path<String>) -> Result<impl Responder, AppError> {
+// The response carries an `ETag` of the bug's version; a matching `If-None-Match` returns 304 Not Modified.
+#[utoipa::path(
+    get, path = "/bugs/{id}", tag = "bugs",
+    params(("id" = Uuid, Path, description = "Bug id"), ("If-None-Match" = Option<String>, Header, description = "ETag of a cached copy")),
+    responses(
+        (status = 200, description = "The bug with its labels, watchers, mentions and links", body = BugDetails), (status = 304, description = "Not modified since the given ETag"),
+        (status = 400, description = "Malformed request", body = ErrorBody),
+        (status = 404, description = "Not found", body = ErrorBody)
+    )
+)]
+async fn get_bug_by_id(_pool: web::Data<SqlitePool>, _bug_id: web::Path<String>, _req: HttpRequest) -> Result<impl Responder, AppError> {
     // Manually parse the UUID string.
     // If parsing fails, return an AppError::BadRequest.
     let bug_id = Uuid::parse_str(&_bug_id.into_inner())
         .map_err(|e| {
-            eprintln!("UUID parsing failed: {:?}", e);
+            debug!(error = ?e, "UUID parsing failed");
             AppError::BadRequest(format!("Invalid Bug ID format: {}", e))
         })?;
 
     // Convert Uuid to Vec<u8> for matching BLOB field in SQLite
     let bug_id_bytes = bug_id.as_bytes().to_vec();
 
-    let bug = sqlx::query_as::<_, BugReport>(
-        "SELECT id, project_id, title, description, reported_by, fixed_by, severity, is_fixed, created_at \
-         FROM bugReport WHERE id = ?"
-    )
+    let bug = sqlx::query_as::<_, BugReport>(&format!("SELECT {} FROM bugReport WHERE id = ? AND deleted_at IS NULL", BUG_COLUMNS))
     .bind(bug_id_bytes)
     .fetch_optional(_pool.get_ref())
     .await
     .map_err(|e| {
-        eprintln!("Database error fetching bug by ID: {:?}", e);
-        AppError::Database(e.into())
+        error!(error = ?e, "Database error fetching bug by ID");
+        AppError::Database(e)
     })?
     .ok_or_else(|| AppError::NotFound("Bug not found".to_string()))?;
 
-    Ok(HttpResponse::Ok().json(bug))
+    let etag = bug_etag(bug.version);
+
+    // The client already has this version
+    if let Ok(header::IfNoneMatch::Items(tags)) = header::IfNoneMatch::parse(&_req)
+        && tags.iter().any(|tag| tag.weak_eq(&etag))
+    {
+        return Ok(HttpResponse::NotModified().insert_header(header::ETag(etag)).finish());
+    }
+
+    let labels = sqlx::query_scalar::<_, String>("SELECT label FROM bugLabel WHERE bug_id = ? ORDER BY label")
+        .bind(bug.id.as_bytes().as_slice())
+        .fetch_all(_pool.get_ref())
+        .await
+        .map_err(|e| {
+            error!(error = ?e, "Database error fetching bug labels");
+            AppError::Database(e)
+        })?;
+
+    let watchers = watchers::watchers(_pool.get_ref(), bug.id)
+        .await
+        .map_err(|e| {
+            error!(error = ?e, "Database error fetching bug watchers");
+            AppError::Database(e)
+        })?;
+
+    let mentions = mentions::mentioned_users(_pool.get_ref(), bug.id)
+        .await
+        .map_err(|e| {
+            error!(error = ?e, "Database error fetching bug mentions");
+            AppError::Database(e)
+        })?;
+
+    let links = links::for_bug(_pool.get_ref(), bug.id)
+        .await
+        .map_err(|e| {
+            error!(error = ?e, "Database error fetching bug links");
+            AppError::Database(e)
+        })?;
+
+    Ok(HttpResponse::Ok().insert_header(header::ETag(etag)).json(BugDetails { bug: bug.with_description_html(), labels, watchers, mentions, links }))
+}
+
+// ETag for a bug, derived from its version column.
+fn bug_etag(version: i64) -> EntityTag {
+    EntityTag::new_strong(format!("v{}", version))
+}
+
+// Rejects the request with 412 if it has an `If-Match` header that does not match the current version.
+// Requests without `If-Match` are allowed through unchanged.
+fn check_if_match(req: &HttpRequest, version: i64) -> Result<(), AppError> {
+    match header::IfMatch::parse(req) {
+        // A missing header also parses as an empty list, which must not be treated as a mismatch
+        Ok(header::IfMatch::Items(tags)) if !tags.is_empty() && !tags.iter().any(|tag| tag.strong_eq(&bug_etag(version))) => {
+            Err(AppError::PreconditionFailed(format!(
+                "Bug has been modified, current version is {}",
+                bug_etag(version)
+            )))
+        }
+        // Unparseable tags are dropped from the list, so a header that is present but empty was malformed
+        Ok(header::IfMatch::Items(tags)) if tags.is_empty() && req.headers().contains_key(header::IF_MATCH) => {
+            Err(AppError::BadRequest("Invalid If-Match header".to_string()))
+        }
+        Ok(_) => Ok(()),
+        Err(_) => Err(AppError::BadRequest("Invalid If-Match header".to_string())),
+    }
+}
+
+// Fetches the current version of a bug, or NotFound if it does not exist or is in the trash.
+async fn fetch_bug_version(pool: &SqlitePool, bug_id_bytes: &[u8]) -> Result<i64, AppError> {
+    sqlx::query_scalar::<_, i64>("SELECT version FROM bugReport WHERE id = ? AND deleted_at IS NULL")
+        .bind(bug_id_bytes)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!(error = ?e, "Database error fetching bug version");
+            AppError::Database(e)
+        })?
+        .ok_or_else(|| AppError::NotFound("Bug not found".to_string()))
+}
+
+// Explains why a write conditioned on the version matched no row: the bug was deleted or trashed meanwhile (404),
+// or another request changed it first (412).
+async fn version_conflict(pool: &SqlitePool, bug_id_bytes: &[u8]) -> AppError {
+    match fetch_bug_version(pool, bug_id_bytes).await {
+        Ok(_) => AppError::PreconditionFailed("Bug was modified by another request".to_string()),
+        Err(e) => e,
+    }
 }
 
 
 // Asynchronous function for creating a new bug report.
 // Simply responds to the request with a confirmation message.
-async fn create_bug(_pool: web::Data<SqlitePool>, _body: web::Json<CreateBug>,_req: HttpRequest) -> Result<impl Responder, AppError> {
+#[utoipa::path(
+    post, path = "/bugs", tag = "bugs",
+    request_body = CreateBug,
+    responses(
+        (status = 200, description = "Bug created, with open bugs it may duplicate", body = CreatedBug),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 404, description = "Not found", body = ErrorBody),
+        (status = 409, description = "Conflicts with existing data", body = ErrorBody),
+        (status = 422, description = "Validation failed", body = ErrorBody)
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn create_bug(_pool: web::Data<SqlitePool>, config: web::Data<Config>, _body: web::Json<CreateBug>,_req: HttpRequest) -> Result<impl Responder, AppError> {
+    // Reject empty or oversized fields before touching the database
+    _body.validate()?;
+
     // Extract user ID from the request extensions
-    let authenticated_user_id = match auth::get_authenticated_user_id(&_req) {
-        Some(user_id) => user_id,
-        None => return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
-            "status": "error",
-            "message": "Authentication required"
-        })))
-    };
+    let authenticated_user_id = auth::get_authenticated_user_id(&_req)
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
 
     // Get the authenticated user from database
     let user = sqlx::query_as::<_, User>(
         "SELECT id, username, hashed_password FROM users WHERE id = ?"
     )
-    .bind(&authenticated_user_id)
+    .bind(authenticated_user_id)
     .fetch_optional(_pool.get_ref())
     .await
     .map_err(|e| {
-        eprintln!("User query error: {:?}", e);
-        AppError::Database(e.into())
+        error!(error = ?e, "User query error");
+        AppError::Database(e)
     })? 
     .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
   
@@ -283,58 +680,118 @@ async fn create_bug(_pool: web::Data<SqlitePool>, _body: web::Json<CreateBug>,_r
     .fetch_optional(_pool.get_ref())
     .await
     .map_err(|e| { 
-        eprintln!("Project query error: {:?}", e); 
-        AppError::Database(e.into()) 
+        error!(error = ?e, "Project query error"); 
+        AppError::Database(e) 
     })? 
     .ok_or_else(|| AppError::NotFound("Project not found".to_string()))?;
 
+    // Look for duplicates before inserting, so the new bug is not among them
+    let possible_duplicates = similarity::find_similar(_pool.get_ref(), project.id, &_body.title, &_body.description, similarity::DEFAULT_LIMIT)
+        .await
+        .map_err(|e| {
+            error!(error = ?e, "Similar bugs query error");
+            AppError::Database(e)
+        })?;
+
     let bug_id = uuid::Uuid::new_v4();
+    let mut tx = _pool.begin().await.map_err(AppError::Database)?;
     
     // Insert bug report using authenticated user's ID
-    sqlx::query("INSERT INTO bugReport (id, project_id, title, description, reported_by, severity, is_fixed) VALUES (?, ?, ?, ?, ?, ?, ?)")
-        .bind(&bug_id.as_bytes().as_slice())// Convert UUID to bytes for SQLite
-        .bind(&project.id.as_bytes().as_slice())
+    sqlx::query("INSERT INTO bugReport (id, project_id, title, description, reported_by, severity, priority, is_fixed) VALUES (?, ?, ?, ?, ?, ?, ?, ?)")
+        .bind(bug_id.as_bytes().as_slice())// Convert UUID to bytes for SQLite
+        .bind(project.id.as_bytes().as_slice())
         .bind(&_body.title)
         .bind(&_body.description)
-        .bind(&user.id.as_bytes().as_slice())
-        .bind(&_body.severity)
+        .bind(user.id.as_bytes().as_slice())
+        .bind(_body.severity)
+        .bind(_body.priority)
         .bind(false)
-        .execute(_pool.get_ref())
+        .execute(&mut *tx)
         .await
         .map_err(|e| { 
-            eprintln!("BugReport insert error: {:?}", e);
-            AppError::Database(e.into())
+            error!(error = ?e, "BugReport insert error");
+            AppError::from(e)
         })?;
 
+    // Store the users @mentioned in the description
+    let mentioned = mentions::sync(&mut tx, bug_id, &_body.description)
+        .await
+        .map_err(|e| {
+            error!(error = ?e, "Bug mention error");
+            AppError::Database(e)
+        })?;
+
+    tx.commit().await.map_err(AppError::Database)?;
+
     let response = BugReport {
         id: bug_id,
         project_id: project.id,
         title: _body.title.clone(),
         description: _body.description.clone(),
         reported_by: user.id,
-        severity: _body.severity.clone(),
+        severity: _body.severity,
+        priority: _body.priority,
+        version: 1,
         fixed_by: None, // Initially set to nil, as the bug is not fixed yet
         created_at: chrono::Utc::now().to_rfc3339(), // Current timestamp in RFC 3339 format
         is_fixed: false,
+        description_html: None,
     };
 
-    Ok(HttpResponse::Ok().json(response))
+    let mut bug_events = vec![DomainEvent::bug(EventKind::BugCreated, &response, Some(user.id))];
+    if !mentioned.is_empty() {
+        bug_events.push(DomainEvent::mention(&response, mentioned, Some(user.id)));
+    }
+    events::publish(_pool.get_ref(), &config.email, bug_events).await;
+
+    Ok(HttpResponse::Ok().json(CreatedBug { bug: response.with_description_html(), possible_duplicates }))
+}
+
+// Asynchronous function listing open bugs of the project that look like the bug being written, most similar first.
+// Takes {"title", "description", "project_name"} so the form can warn the reporter before they submit.
+#[utoipa::path(
+    post, path = "/bugs/similar", tag = "bugs",
+    request_body = SimilarBugsRequest,
+    responses(
+        (status = 200, description = "Open bugs resembling the given one, most similar first", body = Vec<SimilarBug>),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 404, description = "Not found", body = ErrorBody),
+        (status = 422, description = "Validation failed", body = ErrorBody)
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn similar_bugs(pool: web::Data<SqlitePool>, body: web::Json<SimilarBugsRequest>) -> Result<impl Responder, AppError> {
+    body.validate()?;
+
+    let project_id = find_project_id(pool.get_ref(), &body.project_name).await?;
+    let similar = similarity::find_similar(
+        pool.get_ref(),
+        project_id,
+        &body.title,
+        &body.description,
+        body.limit.unwrap_or(similarity::DEFAULT_LIMIT),
+    )
+    .await
+    .map_err(|e| {
+        error!(error = ?e, "Similar bugs query error");
+        AppError::Database(e)
+    })?;
+
+    Ok(HttpResponse::Ok().json(similar))
 }
 
 
 // Asynchronous function to render the bug assignment form.
 async fn render_bug_form(pool: web::Data<SqlitePool>) -> Result<impl Responder, AppError> {
-    println!("render_bug_form called");
-    
     // Fetch open bugs
     let open_bugs = sqlx::query_as::<_, BugReport>(
-        "SELECT * FROM bugReport WHERE is_fixed = false"
+        "SELECT * FROM bugReport WHERE is_fixed = false AND deleted_at IS NULL"
     )
     .fetch_all(pool.get_ref())
     .await
     .map_err(|e| { 
-        eprintln!("Error fetching bugs from database: {:?}", e); 
-        AppError::Database(e.into()) 
+        error!(error = ?e, "Error fetching bugs from database"); 
+        AppError::Database(e) 
     })?;
 
     // Fetch all users
@@ -344,19 +801,11 @@ async fn render_bug_form(pool: web::Data<SqlitePool>) -> Result<impl Responder,
     .fetch_all(pool.get_ref())
     .await
     .map_err(|e| { 
-        eprintln!("Error fetching users from database: {:?}", e); 
-        AppError::Database(e.into()) 
+        error!(error = ?e, "Error fetching users from database"); 
+        AppError::Database(e) 
     })?;
 
-    println!("Found {} bugs and {} users", open_bugs.len(), users.len());
-
-    //For testing purposes
-    for bug in &open_bugs {
-                println!("Bug ID: {}, Title: {}", bug.id, bug.title);
-            }
-    for user in &users {
-                println!("UserID: {}, Username: {}", user.id, user.username);
-            }
+    debug!(bugs = open_bugs.len(), users = users.len(), "Rendering the bug assignment form");
 
     // Create template-friendly data structures with string UUIDs
     let template_bugs: Vec<serde_json::Value> = open_bugs.iter().map(|bug| {
@@ -365,6 +814,7 @@ async fn render_bug_form(pool: web::Data<SqlitePool>) -> Result<impl Responder,
             "title": bug.title,
             "description": bug.description,
             "severity": bug.severity,
+            "priority": bug.priority,
             "project_id": bug.project_id.to_string(),
             "reported_by": bug.reported_by.to_string(),
             "fixed_by": bug.fixed_by.map(|id| id.to_string()),
@@ -381,144 +831,163 @@ async fn render_bug_form(pool: web::Data<SqlitePool>) -> Result<impl Responder,
     }).collect();
             
     // Create Tera instance
-    let tera = match Tera::new("static/*.html") {
-        Ok(t) => {
-            println!("Tera instance created successfully");
-            t
-        },
-        Err(e) => {
-            eprintln!("Tera parsing error: {}", e);
-            return Ok(HttpResponse::InternalServerError().body("Template parsing error")); // Not an AppError variant
-        }
-    };
+    let mut tera = Tera::new("static/*.html").map_err(|e| {
+        error!(error = %e, "Tera parsing error");
+        AppError::Internal(format!("Template parsing error: {}", e))
+    })?;
+    markdown::register_filter(&mut tera);
 
     let mut context = Context::new(); 
     context.insert("bugs", &template_bugs); 
     context.insert("users", &template_users); 
 
     // Render the template
-    match tera.render("bugform.html", &context) {
-        Ok(rendered) => {
-            println!("Template rendered successfully"); 
-            Ok(HttpResponse::Ok().content_type("text/html").body(rendered)) 
-        },
-        Err(e) => {
-            eprintln!("Template rendering error: {}", e); 
-            Ok(HttpResponse::InternalServerError().body("Failed to render template")) // Not an AppError variant
-        }
-    }
+    let rendered = tera.render("bugform.html", &context).map_err(|e| {
+        error!(error = %e, "Template rendering error");
+        AppError::Internal(format!("Failed to render template: {}", e))
+    })?;
+    Ok(HttpResponse::Ok().content_type("text/html").body(rendered))
 }
 
 // Function to assign a bug to a user (requires authentication)
 async fn assign_bug(
     pool: web::Data<SqlitePool>, 
+    config: web::Data<Config>,
     body: web::Json<BugAssignmentRequest>,
     req: HttpRequest
 ) -> Result<impl Responder, AppError> {
-    println!("assign_bug called with bug_id: '{}', user_id: '{}'", body.bug_id, body.user_id);
+    // Both ids must be well-formed UUIDs
+    body.validate()?;
     
     // Check if user is authenticated
-    let _authenticated_user_id = match auth::get_authenticated_user_id(&req) {
-        Some(user_id) => user_id,
-        None => return Ok(HttpResponse::Unauthorized().json(serde_json::json!({
-            "status": "error",
-            "message": "Authentication required"
-        })))
-    };
+    let authenticated_user_id = auth::get_authenticated_user_id(&req)
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
 
     // Parse string UUIDs from frontend
     let bug_id = Uuid::parse_str(&body.bug_id)
-        .map_err(|e| {
-            eprintln!("Invalid bug_id format: '{}', error: {:?}", body.bug_id, e);
-            AppError::BadRequest(format!("Invalid bug ID format: {}", e))
-        })?;
+        .map_err(|e| AppError::BadRequest(format!("Invalid bug ID format: {}", e)))?;
 
     let user_id = Uuid::parse_str(&body.user_id)
-        .map_err(|e| {
-            eprintln!("Invalid user_id format: '{}', error: {:?}", body.user_id, e);
-            AppError::BadRequest(format!("Invalid user ID format: {}", e))
-        })?;
+        .map_err(|e| AppError::BadRequest(format!("Invalid user ID format: {}", e)))?;
+
+    assign_to_user(pool.get_ref(), &config.email, bug_id, user_id, authenticated_user_id).await?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "status": "success",
+        "message": "Bug assigned successfully"
+    })))
+}
 
+// Asynchronous function assigning the bug in the path to the user in {"user_id": "..."}. Returns the updated bug.
+#[utoipa::path(
+    put, path = "/bugs/{id}/assignee", tag = "bugs",
+    request_body = AssignBug,
+    params(("id" = Uuid, Path, description = "Bug id")),
+    responses(
+        (status = 200, description = "Assigned bug", body = BugReport),
+        (status = 400, description = "Malformed request", body = ErrorBody),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 404, description = "Not found", body = ErrorBody),
+        (status = 422, description = "Validation failed", body = ErrorBody)
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn set_bug_assignee(pool: web::Data<SqlitePool>, config: web::Data<Config>, bug_id: web::Path<String>, body: web::Json<AssignBug>, req: HttpRequest) -> Result<impl Responder, AppError> {
+    body.validate()?;
+
+    let actor = auth::get_authenticated_user_id(&req)
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+    let bug_id = Uuid::parse_str(&bug_id.into_inner())
+        .map_err(|e| AppError::BadRequest(format!("Invalid Bug ID format: {}", e)))?;
+    let user_id = Uuid::parse_str(&body.user_id)
+        .map_err(|e| AppError::BadRequest(format!("Invalid user ID format: {}", e)))?;
+
+    let bug = assign_to_user(pool.get_ref(), &config.email, bug_id, user_id, actor).await?;
+    Ok(HttpResponse::Ok().insert_header(header::ETag(bug_etag(bug.version))).json(bug.with_description_html()))
+}
+
+// Makes the user the assignee (fixed_by) of the bug and publishes bug.assigned.
+// NotFound if the bug does not exist (or is in the trash) or the user does not exist.
+async fn assign_to_user(pool: &SqlitePool, email_config: &EmailConfig, bug_id: Uuid, user_id: Uuid, actor: Uuid) -> Result<BugReport, AppError> {
     // Convert UUIDs to bytes for SQLite
     let bug_id_bytes = bug_id.as_bytes().to_vec();
     let user_id_bytes = user_id.as_bytes().to_vec();
 
-    println!("Bug ID bytes: {:?}", hex::encode(&bug_id_bytes));
-    println!("User ID bytes: {:?}", hex::encode(&user_id_bytes));
-
     // First, check if the bug exists
-    let bug_exists = sqlx::query("SELECT id FROM bugReport WHERE id = ?")
+    let bug_exists = sqlx::query("SELECT id FROM bugReport WHERE id = ? AND deleted_at IS NULL")
         .bind(&bug_id_bytes)
-        .fetch_optional(pool.get_ref())
+        .fetch_optional(pool)
         .await
         .map_err(|e| {
-            eprintln!("Error checking bug existence: {:?}", e);
-            AppError::Database(e.into())
+            error!(error = ?e, "Error checking bug existence");
+            AppError::Database(e)
         })?;
 
     if bug_exists.is_none() {
-        println!("Bug not found in database");
-        return Ok(HttpResponse::NotFound().json(serde_json::json!({
-            "status": "error",
-            "message": "Bug not found"
-        })));
+        return Err(AppError::NotFound("Bug not found".to_string()));
     }
 
     // Check if the user exists
     let user_exists = sqlx::query("SELECT id FROM users WHERE id = ?")
         .bind(&user_id_bytes)
-        .fetch_optional(pool.get_ref())
+        .fetch_optional(pool)
         .await
         .map_err(|e| {
-            eprintln!("Error checking user existence: {:?}", e);
-            AppError::Database(e.into())
+            error!(error = ?e, "Error checking user existence");
+            AppError::Database(e)
         })?;
 
     if user_exists.is_none() {
-        println!("User not found in database");
-        return Ok(HttpResponse::NotFound().json(serde_json::json!({
-            "status": "error",
-            "message": "User not found"
-        })));
+        return Err(AppError::NotFound("User not found".to_string()));
     }
 
     // Now update the bug assignment
-    let result = sqlx::query(
-        "UPDATE bugReport SET fixed_by = ? WHERE id = ?"
-    )
+    let assigned_bug = sqlx::query_as::<_, BugReport>(&format!(
+        "UPDATE bugReport SET fixed_by = ?, version = version + 1 WHERE id = ? AND deleted_at IS NULL RETURNING {}",
+        BUG_COLUMNS
+    ))
     .bind(&user_id_bytes)
     .bind(&bug_id_bytes)
-    .execute(pool.get_ref())
+    .fetch_optional(pool)
     .await
     .map_err(|e| {
-        eprintln!("Bug assignment error: {:?}", e);
-        AppError::Database(e.into())
-    })?;
-
-    println!("Rows affected: {}", result.rows_affected());
+        error!(error = ?e, "Bug assignment error");
+        AppError::from(e)
+    })?
+    .ok_or_else(|| AppError::NotFound("Bug assignment failed - no rows updated".to_string()))?;
 
-    if result.rows_affected() == 0 {
-        return Ok(HttpResponse::NotFound().json(serde_json::json!({
-            "status": "error",
-            "message": "Bug assignment failed - no rows updated"
-        })));
-    }
+    info!(%bug_id, assignee = %user_id, "Bug assigned");
+    events::publish(pool, email_config, vec![DomainEvent::bug(EventKind::BugAssigned, &assigned_bug, Some(actor))]).await;
 
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "status": "success",
-        "message": "Bug assigned successfully"
-    })))
+    Ok(assigned_bug)
 }
 
 
 // Asynchronous function to update a bug details.
-pub async fn update_bug_details(_pool: web::Data<SqlitePool>,_bug_id: web::Path<String>,_body: web::Json<UpdateBugReport>) -> Result<impl Responder, AppError> {
+// Honours `If-Match`: the update only applies if the bug is still at the version the client last saw.
+#[utoipa::path(
+    patch, path = "/bugs/{id}", tag = "bugs",
+    request_body = UpdateBugReport,
+    params(("id" = Uuid, Path, description = "Bug id"), ("If-Match" = Option<String>, Header, description = "ETag the update is based on")),
+    responses(
+        (status = 200, description = "Updated bug", body = BugReport),
+        (status = 400, description = "Malformed request", body = ErrorBody),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 404, description = "Not found", body = ErrorBody),
+        (status = 412, description = "If-Match does not match the current version", body = ErrorBody),
+        (status = 422, description = "Validation failed", body = ErrorBody)
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_bug_details(_pool: web::Data<SqlitePool>, config: web::Data<Config>,_bug_id: web::Path<String>,_body: web::Json<UpdateBugReport>,_req: HttpRequest) -> Result<impl Responder, AppError> {
+    // Reject empty or oversized fields before touching the database
+    _body.validate()?;
 
     // Manually parse the UUID string.
     // If parsing fails, return an AppError::BadRequest.
     let bug_id = Uuid::parse_str(&_bug_id.into_inner())
         .map_err(|e| {
-            eprintln!("UUID parsing failed for update_bug_details: {:?}", e);
+            debug!(error = ?e, "UUID parsing failed for update_bug_details");
             AppError::BadRequest(format!("Invalid Bug ID format: {}", e))
         })?;
     
@@ -532,11 +1001,16 @@ pub async fn update_bug_details(_pool: web::Data<SqlitePool>,_bug_id: web::Path<
         string_params.push(if is_fixed { "1" } else { "0" });
     }
 
-    if let Some(severity) = &_body.severity {
+    if let Some(severity) = _body.severity {
         set_clauses.push("severity = ?");
         string_params.push(severity.as_str());
     }
 
+    if let Some(priority) = _body.priority {
+        set_clauses.push("priority = ?");
+        string_params.push(priority.as_str());
+    }
+
     if let Some(description) = &_body.description {
         set_clauses.push("description = ?");
         string_params.push(description.as_str());
@@ -551,8 +1025,8 @@ pub async fn update_bug_details(_pool: web::Data<SqlitePool>,_bug_id: web::Path<
         .fetch_optional(_pool.get_ref())
         .await
         .map_err(|e| { 
-            eprintln!("Database error: {:?}", e); 
-            AppError::Database(e.into()) 
+            error!(error = ?e, "Database error"); 
+            AppError::Database(e) 
         })? //
         .ok_or_else(|| AppError::NotFound("User not found".to_string()))?; 
 
@@ -564,16 +1038,32 @@ pub async fn update_bug_details(_pool: web::Data<SqlitePool>,_bug_id: web::Path<
         return Err(AppError::BadRequest("No fields provided for update".to_string())); 
     }
 
-    // Build final query
+    // Add bug_id to blob_params LAST
+    let bug_id_bytes = bug_id.as_bytes().to_vec(); 
+
+    // Check the client's If-Match against the current version
+    let current_version = fetch_bug_version(_pool.get_ref(), &bug_id_bytes).await?;
+    check_if_match(&_req, current_version)?;
+
+    // Needed to tell what this update changes, e.g. whether it fixes or reopens the bug
+    let previous_bug = sqlx::query_as::<_, BugReport>(&format!("SELECT {} FROM bugReport WHERE id = ? AND deleted_at IS NULL", BUG_COLUMNS))
+        .bind(&bug_id_bytes)
+        .fetch_optional(_pool.get_ref())
+        .await
+        .map_err(|e| {
+            error!(error = ?e, "Database error");
+            AppError::Database(e)
+        })?
+        .ok_or_else(|| AppError::NotFound("Bug not found".to_string()))?;
+
+    // Build final query; the version condition makes a concurrent update lose instead of overwrite
+    set_clauses.push("version = version + 1");
     let set_clause = set_clauses.join(", "); 
     let query = format!(
-        "UPDATE bugReport SET {} WHERE id = ? RETURNING *",
-        set_clause
+        "UPDATE bugReport SET {} WHERE id = ? AND version = ? AND deleted_at IS NULL RETURNING {}",
+        set_clause, BUG_COLUMNS
     );
 
-    // Add bug_id to blob_params LAST
-    let bug_id_bytes = bug_id.as_bytes().to_vec(); 
-
     // Build and bind query
     let mut query_builder = sqlx::query_as::<_, BugReport>(&query); 
 
@@ -587,44 +1077,1176 @@ pub async fn update_bug_details(_pool: web::Data<SqlitePool>,_bug_id: web::Path<
         query_builder = query_builder.bind::<Vec<u8>>(param.clone()); 
     }
 
-    // Finally bind bug_id
-    query_builder = query_builder.bind(bug_id_bytes); 
+    // Finally bind bug_id and the version the update is based on
+    query_builder = query_builder.bind(bug_id_bytes.clone()).bind(current_version); 
 
     // Execute query
-    let updated_bug = query_builder.fetch_one(_pool.get_ref()).await //
+    let mut tx = _pool.begin().await.map_err(AppError::Database)?;
+    let updated_bug = query_builder.fetch_optional(&mut *tx).await //
         .map_err(|e| { 
-            eprintln!("Database error: {:?}", e); 
-            AppError::Database(e.into()) 
-        })?; 
+            error!(error = ?e, "Database error"); 
+            AppError::from(e) 
+        })?;
+    let Some(updated_bug) = updated_bug else {
+        // Roll back before looking at the row again through the pool
+        drop(tx);
+        return Err(version_conflict(_pool.get_ref(), &bug_id_bytes).await);
+    };
+
+    // A new description may add or remove @mentions
+    let mentioned = match &_body.description {
+        Some(description) => mentions::sync(&mut tx, bug_id, description)
+            .await
+            .map_err(|e| {
+                error!(error = ?e, "Bug mention error");
+                AppError::Database(e)
+            })?,
+        None => Vec::new(),
+    };
+    tx.commit().await.map_err(AppError::Database)?;
 
-    Ok(HttpResponse::Ok().json(updated_bug))
+    let actor = auth::get_authenticated_user_id(&_req);
+    let mut bug_events = vec![DomainEvent::bug(EventKind::BugUpdated, &updated_bug, actor).with_changes(&previous_bug)];
+    if _body.fixed_by.is_some() {
+        bug_events.push(DomainEvent::bug(EventKind::BugAssigned, &updated_bug, actor));
+    }
+    if updated_bug.is_fixed != previous_bug.is_fixed {
+        let kind = if updated_bug.is_fixed { EventKind::BugFixed } else { EventKind::BugReopened };
+        bug_events.push(DomainEvent::bug(kind, &updated_bug, actor));
+    }
+    if !mentioned.is_empty() {
+        bug_events.push(DomainEvent::mention(&updated_bug, mentioned, actor));
+    }
+    events::publish(_pool.get_ref(), &config.email, bug_events).await;
+
+    Ok(HttpResponse::Ok().insert_header(header::ETag(bug_etag(updated_bug.version))).json(updated_bug.with_description_html()))
 }
 
 
-async fn delete_bug(_pool: web::Data<SqlitePool>, _bug_id: web::Path<String>) -> Result<impl Responder, AppError> {
+// Asynchronous function to move a bug to the trash, honouring `If-Match` like update_bug_details.
+// The bug disappears from every listing but can be restored until the purge job removes it.
+#[utoipa::path(
+    delete, path = "/bugs/{id}", tag = "trash",
+    params(("id" = Uuid, Path, description = "Bug id"), ("If-Match" = Option<String>, Header, description = "ETag the deletion is based on")),
+    responses(
+        (status = 200, description = "Bug moved to the trash", body = String, content_type = "text/plain"),
+        (status = 400, description = "Malformed request", body = ErrorBody),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 404, description = "Not found", body = ErrorBody),
+        (status = 412, description = "If-Match does not match the current version", body = ErrorBody)
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn delete_bug(_pool: web::Data<SqlitePool>, config: web::Data<Config>, _bug_id: web::Path<String>, _req: HttpRequest) -> Result<impl Responder, AppError> {
     // Manually parse the UUID string.
     // If parsing fails, return an AppError::BadRequest.
     let bug_id = Uuid::parse_str(&_bug_id.into_inner())
         .map_err(|e| {
-            eprintln!("UUID parsing failed for delete_bug: {:?}", e);
+            debug!(error = ?e, "UUID parsing failed for delete_bug");
             AppError::BadRequest(format!("Invalid Bug ID format: {}", e))
         })?;
 
     // Convert Uuid to Vec<u8> for matching BLOB field in SQLite
     let bug_id_bytes = bug_id.as_bytes().to_vec();
 
-    let result = sqlx::query("DELETE FROM bugReport WHERE id = ?")
-        .bind(bug_id_bytes)
-        .execute(_pool.get_ref())
+    let current_version = fetch_bug_version(_pool.get_ref(), &bug_id_bytes).await?;
+    check_if_match(&_req, current_version)?;
+
+    let actor = auth::get_authenticated_user_id(&_req);
+    let deleted_bug = sqlx::query_as::<_, BugReport>(&format!(
+        "UPDATE bugReport SET deleted_at = CURRENT_TIMESTAMP, deleted_by = ?, version = version + 1 WHERE id = ? AND version = ? AND deleted_at IS NULL RETURNING {}",
+        BUG_COLUMNS
+    ))
+        .bind(actor.map(|id| id.as_bytes().to_vec()))
+        .bind(&bug_id_bytes)
+        .bind(current_version)
+        .fetch_optional(_pool.get_ref())
         .await
         .map_err(|e| { 
-            eprintln!("Database error: {:?}", e); 
-            AppError::Database(e.into()) 
+            error!(error = ?e, "Database error"); 
+            AppError::Database(e) 
         })?; 
 
-    if result.rows_affected() == 0 { 
-        Err(AppError::NotFound("Bug not found".to_string())) 
+    match deleted_bug {
+        None => Err(version_conflict(_pool.get_ref(), &bug_id_bytes).await),
+        Some(bug) => {
+            events::publish(_pool.get_ref(), &config.email, vec![DomainEvent::bug(EventKind::BugDeleted, &bug, actor)]).await;
+            Ok(HttpResponse::Ok().body("Bug moved to the trash"))
+        }
+    }
+}
+
+// Asynchronous function listing the bugs in the trash, most recently deleted first (administrators only).
+#[utoipa::path(
+    get, path = "/bugs/trash", tag = "trash",
+    responses(
+        (status = 200, description = "Bugs in the trash, most recently deleted first", body = Vec<TrashedBug>),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody)
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_trash(pool: web::Data<SqlitePool>, config: web::Data<Config>, req: HttpRequest) -> Result<impl Responder, AppError> {
+    let user_id = auth::get_authenticated_user_id(&req)
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+    if !auth::is_admin(pool.get_ref(), user_id).await.map_err(AppError::Database)? {
+        return Err(AppError::Forbidden("Only administrators can view the trash".to_string()));
+    }
+
+    let trashed = sqlx::query_as::<_, TrashedBug>(&format!(
+        "SELECT {}, deleted_at, (SELECT username FROM users WHERE users.id = bugReport.deleted_by) AS deleted_by, \
+         datetime(deleted_at, ?) AS purge_at FROM bugReport WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC",
+        BUG_COLUMNS
+    ))
+    .bind(format!("+{} days", config.retention.trash_days))
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| {
+        error!(error = ?e, "Trash query error");
+        AppError::Database(e)
+    })?;
+
+    Ok(HttpResponse::Ok().json(trashed))
+}
+
+// Asynchronous function taking a bug out of the trash. Allowed for administrators and for the user who deleted it.
+#[utoipa::path(
+    post, path = "/bugs/{id}/restore", tag = "trash",
+    params(("id" = Uuid, Path, description = "Bug id")),
+    responses(
+        (status = 200, description = "Restored bug", body = BugReport),
+        (status = 400, description = "Malformed request", body = ErrorBody),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Not found", body = ErrorBody),
+        (status = 409, description = "A live bug has the same title", body = ErrorBody)
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn restore_bug(pool: web::Data<SqlitePool>, config: web::Data<Config>, bug_id: web::Path<String>, req: HttpRequest) -> Result<impl Responder, AppError> {
+    let user_id = auth::get_authenticated_user_id(&req)
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+    let bug_id = Uuid::parse_str(&bug_id.into_inner())
+        .map_err(|e| AppError::BadRequest(format!("Invalid Bug ID format: {}", e)))?;
+
+    let deleted_by = sqlx::query_scalar::<_, Option<Uuid>>("SELECT deleted_by FROM bugReport WHERE id = ? AND deleted_at IS NOT NULL")
+        .bind(bug_id.as_bytes().as_slice())
+        .fetch_optional(pool.get_ref())
+        .await
+        .map_err(AppError::Database)?
+        .ok_or_else(|| AppError::NotFound("Bug not found in the trash".to_string()))?;
+    if deleted_by != Some(user_id) && !auth::is_admin(pool.get_ref(), user_id).await.map_err(AppError::Database)? {
+        return Err(AppError::Forbidden("Only administrators and the user who deleted the bug can restore it".to_string()));
+    }
+
+    let restored = sqlx::query_as::<_, BugReport>(&format!(
+        "UPDATE bugReport SET deleted_at = NULL, deleted_by = NULL, version = version + 1 WHERE id = ? AND deleted_at IS NOT NULL RETURNING {}",
+        BUG_COLUMNS
+    ))
+    .bind(bug_id.as_bytes().as_slice())
+    .fetch_optional(pool.get_ref())
+    .await
+    .map_err(|e| match AppError::from(e) {
+        // Titles are only unique among live bugs, and a new one may have taken this one's
+        AppError::Conflict(_) => AppError::Conflict("A bug with the same title was created since this one was deleted".to_string()),
+        e => {
+            error!(error = ?e, "Restore bug error");
+            e
+        }
+    })?
+    // Restored by a concurrent request
+    .ok_or_else(|| AppError::NotFound("Bug not found in the trash".to_string()))?;
+
+    events::publish(pool.get_ref(), &config.email, vec![DomainEvent::bug(EventKind::BugRestored, &restored, Some(user_id))]).await;
+
+    Ok(HttpResponse::Ok().insert_header(header::ETag(bug_etag(restored.version))).json(restored.with_description_html()))
+}
+
+// Asynchronous function making the authenticated user watch a bug. Returns the bug's watchers.
+#[utoipa::path(
+    post, path = "/bugs/{id}/watch", tag = "bugs",
+    params(("id" = Uuid, Path, description = "Bug id")),
+    responses(
+        (status = 200, description = "Watchers of the bug", body = Vec<SimpleUser>),
+        (status = 400, description = "Malformed request", body = ErrorBody),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 404, description = "Not found", body = ErrorBody)
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn watch_bug(pool: web::Data<SqlitePool>, bug_id: web::Path<String>, req: HttpRequest) -> Result<impl Responder, AppError> {
+    let user_id = auth::get_authenticated_user_id(&req)
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+    let bug_id = Uuid::parse_str(&bug_id.into_inner())
+        .map_err(|e| AppError::BadRequest(format!("Invalid Bug ID format: {}", e)))?;
+
+    // NotFound for an unknown bug
+    fetch_bug_version(pool.get_ref(), bug_id.as_bytes()).await?;
+
+    watchers::watch(pool.get_ref(), bug_id, user_id).await.map_err(|e| {
+        error!(error = ?e, "Watch bug error");
+        AppError::from(e)
+    })?;
+
+    let watchers = watchers::watchers(pool.get_ref(), bug_id).await.map_err(AppError::Database)?;
+    Ok(HttpResponse::Ok().json(watchers))
+}
+
+// Asynchronous function making the authenticated user stop watching a bug. Returns the bug's remaining watchers.
+#[utoipa::path(
+    delete, path = "/bugs/{id}/watch", tag = "bugs",
+    params(("id" = Uuid, Path, description = "Bug id")),
+    responses(
+        (status = 200, description = "Remaining watchers of the bug", body = Vec<SimpleUser>),
+        (status = 400, description = "Malformed request", body = ErrorBody),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 404, description = "Not found", body = ErrorBody)
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn unwatch_bug(pool: web::Data<SqlitePool>, bug_id: web::Path<String>, req: HttpRequest) -> Result<impl Responder, AppError> {
+    let user_id = auth::get_authenticated_user_id(&req)
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+    let bug_id = Uuid::parse_str(&bug_id.into_inner())
+        .map_err(|e| AppError::BadRequest(format!("Invalid Bug ID format: {}", e)))?;
+
+    fetch_bug_version(pool.get_ref(), bug_id.as_bytes()).await?;
+
+    watchers::unwatch(pool.get_ref(), bug_id, user_id).await.map_err(|e| {
+        error!(error = ?e, "Unwatch bug error");
+        AppError::Database(e)
+    })?;
+
+    let watchers = watchers::watchers(pool.get_ref(), bug_id).await.map_err(AppError::Database)?;
+    Ok(HttpResponse::Ok().json(watchers))
+}
+
+// Asynchronous function linking a bug to another one, e.g. {"target_id": "...", "kind": "blocks"}.
+// Marking a bug as duplicate_of another closes it (sets is_fixed) in the same transaction.
+#[utoipa::path(
+    post, path = "/bugs/{id}/links", tag = "bugs",
+    request_body = CreateBugLink,
+    params(("id" = Uuid, Path, description = "Bug id")),
+    responses(
+        (status = 200, description = "Link created", body = BugLink),
+        (status = 400, description = "Malformed request", body = ErrorBody),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 404, description = "Not found", body = ErrorBody),
+        (status = 409, description = "Conflicts with existing data", body = ErrorBody),
+        (status = 422, description = "Validation failed", body = ErrorBody)
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn add_bug_link(pool: web::Data<SqlitePool>, config: web::Data<Config>, bug_id: web::Path<String>, body: web::Json<CreateBugLink>, req: HttpRequest) -> Result<impl Responder, AppError> {
+    body.validate()?;
+
+    let actor = auth::get_authenticated_user_id(&req);
+    let bug_id = Uuid::parse_str(&bug_id.into_inner())
+        .map_err(|e| AppError::BadRequest(format!("Invalid Bug ID format: {}", e)))?;
+    let target_id = Uuid::parse_str(&body.target_id)
+        .map_err(|e| AppError::BadRequest(format!("Invalid target_id format: {}", e)))?;
+
+    // Both bugs must exist
+    fetch_bug_version(pool.get_ref(), bug_id.as_bytes()).await?;
+    match fetch_bug_version(pool.get_ref(), target_id.as_bytes()).await {
+        Err(AppError::NotFound(_)) => return Err(AppError::NotFound("Linked bug not found".to_string())),
+        result => result?,
+    };
+
+    let mut tx = pool.begin().await.map_err(AppError::Database)?;
+    let link = links::add(&mut tx, bug_id, target_id, body.kind, actor).await?;
+
+    let mut bug_events = Vec::new();
+    if link.kind == BugLinkKind::DuplicateOf {
+        let previous = sqlx::query_as::<_, BugReport>(&format!("SELECT {} FROM bugReport WHERE id = ? AND deleted_at IS NULL", BUG_COLUMNS))
+            .bind(link.source_bug_id.as_bytes().as_slice())
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(AppError::Database)?;
+
+        if !previous.is_fixed {
+            let closed = sqlx::query_as::<_, BugReport>(&format!(
+                "UPDATE bugReport SET is_fixed = TRUE, version = version + 1 WHERE id = ? RETURNING {}",
+                BUG_COLUMNS
+            ))
+            .bind(link.source_bug_id.as_bytes().as_slice())
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| {
+                error!(error = ?e, "Closing duplicate bug error");
+                AppError::Database(e)
+            })?;
+            bug_events.push(DomainEvent::bug(EventKind::BugUpdated, &closed, actor).with_changes(&previous));
+            bug_events.push(DomainEvent::bug(EventKind::BugFixed, &closed, actor));
+        }
+    }
+
+    tx.commit().await.map_err(AppError::Database)?;
+    events::publish(pool.get_ref(), &config.email, bug_events).await;
+
+    Ok(HttpResponse::Ok().json(link))
+}
+
+// Asynchronous function removing a link to or from a bug. Removing a duplicate_of link does not reopen the bug.
+#[utoipa::path(
+    delete, path = "/bugs/{id}/links/{link_id}", tag = "bugs",
+    params(("id" = Uuid, Path, description = "Bug id"), ("link_id" = Uuid, Path, description = "Link id")),
+    responses(
+        (status = 200, description = "Link removed", body = String, content_type = "text/plain"),
+        (status = 400, description = "Malformed request", body = ErrorBody),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 404, description = "Not found", body = ErrorBody)
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn remove_bug_link(pool: web::Data<SqlitePool>, path: web::Path<(String, String)>) -> Result<impl Responder, AppError> {
+    let (bug_id, link_id) = path.into_inner();
+    let bug_id = Uuid::parse_str(&bug_id).map_err(|e| AppError::BadRequest(format!("Invalid Bug ID format: {}", e)))?;
+    let link_id = Uuid::parse_str(&link_id).map_err(|e| AppError::BadRequest(format!("Invalid Link ID format: {}", e)))?;
+
+    let removed = links::remove(pool.get_ref(), bug_id, link_id)
+        .await
+        .map_err(|e| {
+            error!(error = ?e, "Remove bug link error");
+            AppError::Database(e)
+        })?;
+    if !removed {
+        return Err(AppError::NotFound("Link not found".to_string()));
+    }
+
+    Ok(HttpResponse::Ok().body("Link removed successfully"))
+}
+
+// Asynchronous function applying one set of changes to many bugs (requires authentication).
+// All changes run in a single transaction: per-bug problems (unknown or malformed ids) are reported
+// in the results without aborting, while a database error rolls back the whole batch.
+// With `dry_run` the changes are executed and then rolled back, so the report reflects what would happen.
+#[utoipa::path(
+    post, path = "/bugs/bulk", tag = "bugs",
+    request_body = BulkBugRequest,
+    responses(
+        (status = 200, description = "Outcome per bug", body = BulkBugResponse),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 404, description = "Not found", body = ErrorBody),
+        (status = 422, description = "Validation failed", body = ErrorBody)
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn bulk_update_bugs(pool: web::Data<SqlitePool>, config: web::Data<Config>, body: web::Json<BulkBugRequest>, req: HttpRequest) -> Result<impl Responder, AppError> {
+    body.validate()?;
+    let changes = &body.changes;
+
+    // Resolve the assignee once rather than per bug
+    let fixed_by = match &changes.fixed_by {
+        Some(username) => Some(
+            sqlx::query_scalar::<_, Uuid>("SELECT id FROM users WHERE username = ?")
+                .bind(username)
+                .fetch_optional(pool.get_ref())
+                .await
+                .map_err(AppError::Database)?
+                .ok_or_else(|| AppError::NotFound("User not found".to_string()))?,
+        ),
+        None => None,
+    };
+
+    let actor = auth::get_authenticated_user_id(&req);
+
+    // Work out which bugs the request targets
+    let targets: Vec<String> = match (&body.bug_ids, &body.filter) {
+        (Some(ids), _) => ids.clone(),
+        (None, Some(filter)) => {
+            let mut query = bug_filter_query(pool.get_ref(), "id", filter, actor).await?;
+            push_bug_sort(&mut query, filter.sort);
+            // One more than allowed is enough to tell that the filter matches too many
+            query.push(" LIMIT ").push_bind(MAX_BULK_ITEMS as i64 + 1);
+            let ids = query
+                .build_query_scalar::<Uuid>()
+                .fetch_all(pool.get_ref())
+                .await
+                .map_err(AppError::Database)?;
+            if ids.len() as u64 > MAX_BULK_ITEMS {
+                return Err(AppError::Validation(vec![FieldError {
+                    field: "filter".to_string(),
+                    message: format!("filter matches more than {} bugs, narrow it down", MAX_BULK_ITEMS),
+                }]));
+            }
+            ids.iter().map(Uuid::to_string).collect()
+        }
+        (None, None) => Vec::new(), // Rejected by validation
+    };
+
+    let add_labels: Vec<String> = changes.add_labels.iter().map(|l| normalize_label(l)).collect();
+    let remove_labels: Vec<String> = changes.remove_labels.iter().map(|l| normalize_label(l)).collect();
+
+    let mut tx = pool.begin().await.map_err(AppError::Database)?;
+    let mut results = Vec::with_capacity(targets.len());
+    let mut bug_events = Vec::new();
+
+    for target in &targets {
+        let bug_id = match Uuid::parse_str(target) {
+            Ok(id) => id,
+            Err(e) => {
+                results.push(BulkItemResult {
+                    bug_id: target.clone(),
+                    outcome: BulkOutcome::InvalidId,
+                    error: Some(format!("Invalid Bug ID format: {}", e)),
+                });
+                continue;
+            }
+        };
+        let bug_id_bytes = bug_id.as_bytes().to_vec();
+
+        let outcome = if changes.delete {
+            let deleted = sqlx::query_as::<_, BugReport>(&format!(
+                "UPDATE bugReport SET deleted_at = CURRENT_TIMESTAMP, deleted_by = ?, version = version + 1 WHERE id = ? AND deleted_at IS NULL RETURNING {}",
+                BUG_COLUMNS
+            ))
+                .bind(actor.map(|id| id.as_bytes().to_vec()))
+                .bind(&bug_id_bytes)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(AppError::from)?;
+            match deleted {
+                None => BulkOutcome::NotFound,
+                Some(bug) => {
+                    bug_events.push(DomainEvent::bug(EventKind::BugDeleted, &bug, actor));
+                    BulkOutcome::Deleted
+                }
+            }
+        } else {
+            let previous = sqlx::query_as::<_, BugReport>(&format!("SELECT {} FROM bugReport WHERE id = ? AND deleted_at IS NULL", BUG_COLUMNS))
+                .bind(&bug_id_bytes)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(AppError::Database)?;
+
+            // Fields that were not provided keep their current value
+            let updated = sqlx::query_as::<_, BugReport>(&format!(
+                "UPDATE bugReport SET is_fixed = COALESCE(?, is_fixed), severity = COALESCE(?, severity), \
+                 priority = COALESCE(?, priority), fixed_by = COALESCE(?, fixed_by), version = version + 1 \
+                 WHERE id = ? AND deleted_at IS NULL RETURNING {}",
+                BUG_COLUMNS
+            ))
+            .bind(changes.is_fixed)
+            .bind(changes.severity)
+            .bind(changes.priority)
+            .bind(fixed_by.map(|id| id.as_bytes().to_vec()))
+            .bind(&bug_id_bytes)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+
+            if let (Some(bug), Some(previous)) = (updated, &previous) {
+                bug_events.push(DomainEvent::bug(EventKind::BugUpdated, &bug, actor).with_changes(previous));
+                if fixed_by.is_some() {
+                    bug_events.push(DomainEvent::bug(EventKind::BugAssigned, &bug, actor));
+                }
+                if previous.is_fixed != bug.is_fixed {
+                    let kind = if bug.is_fixed { EventKind::BugFixed } else { EventKind::BugReopened };
+                    bug_events.push(DomainEvent::bug(kind, &bug, actor));
+                }
+            }
+
+            if previous.is_none() {
+                BulkOutcome::NotFound
+            } else {
+                for label in &add_labels {
+                    sqlx::query("INSERT OR IGNORE INTO bugLabel (bug_id, label) VALUES (?, ?)")
+                        .bind(&bug_id_bytes)
+                        .bind(label)
+                        .execute(&mut *tx)
+                        .await
+                        .map_err(AppError::from)?;
+                }
+                for label in &remove_labels {
+                    sqlx::query("DELETE FROM bugLabel WHERE bug_id = ? AND label = ?")
+                        .bind(&bug_id_bytes)
+                        .bind(label)
+                        .execute(&mut *tx)
+                        .await
+                        .map_err(AppError::from)?;
+                }
+                BulkOutcome::Updated
+            }
+        };
+
+        results.push(BulkItemResult {
+            bug_id: bug_id.to_string(),
+            outcome,
+            error: (outcome == BulkOutcome::NotFound).then(|| "Bug not found".to_string()),
+        });
+    }
+
+    if body.dry_run {
+        tx.rollback().await.map_err(AppError::Database)?;
     } else {
-        Ok(HttpResponse::Ok().body("Bug deleted successfully")) 
+        tx.commit().await.map_err(AppError::Database)?;
+        events::publish(pool.get_ref(), &config.email, bug_events).await;
+    }
+
+    let succeeded = results.iter().filter(|r| r.error.is_none()).count();
+    Ok(HttpResponse::Ok().json(BulkBugResponse {
+        dry_run: body.dry_run,
+        matched: targets.len(),
+        succeeded,
+        failed: results.len() - succeeded,
+        results,
+    }))
+}
+
+// Asynchronous function importing bugs from an uploaded CSV, JSON, GitHub or Jira export file.
+// Every row is validated and inserted on its own; rows that fail are listed in the report with their errors
+// instead of aborting the import. With `dry_run=true` nothing is saved.
+#[utoipa::path(
+    post, path = "/bugs/import", tag = "bugs",
+    request_body(description = "CSV or JSON export file", content((String = "text/csv"), (String = "application/json"))),
+    params(ImportQuery),
+    responses(
+        (status = 200, description = "Outcome per row", body = ImportReport),
+        (status = 400, description = "Malformed request", body = ErrorBody),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 404, description = "Not found", body = ErrorBody)
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn import_bugs(
+    pool: web::Data<SqlitePool>,
+    query: web::Query<ImportQuery>,
+    body: web::Bytes,
+    req: HttpRequest,
+) -> Result<impl Responder, AppError> {
+    // Rows without a reporter are reported by the user running the import
+    let importer = auth::get_authenticated_user_id(&req)
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+
+    if body.is_empty() {
+        return Err(AppError::BadRequest("Import file is empty".to_string()));
+    }
+
+    let query = query.into_inner();
+    let options = ImportOptions {
+        format: query.format,
+        dry_run: query.dry_run,
+        default_project: query.project,
+        mapping: import::parse_mapping(query.mapping.as_deref())?,
+    };
+
+    let records = import::parse_source(&body, &options)?;
+    let report = import::import_records(pool.get_ref(), records, &options, importer).await?;
+
+    Ok(HttpResponse::Ok().json(report))
+}
+
+// Columns of the `webhook` table in the order of the `WebhookRecord` model. The secret is never read back.
+const WEBHOOK_COLUMNS: &str = "id, project_id, url, events, created_by, created_at";
+
+// Asynchronous function subscribing a URL to events of a project. Allowed for the owner of the project and
+// administrators. The response includes the signing secret; it is not shown again afterwards.
+#[utoipa::path(
+    post, path = "/webhooks", tag = "webhooks",
+    request_body = CreateWebhook,
+    responses(
+        (status = 200, description = "Webhook created, including its secret", body = Webhook),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Not found", body = ErrorBody),
+        (status = 422, description = "Validation failed", body = ErrorBody)
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn create_webhook(pool: web::Data<SqlitePool>, config: web::Data<Config>, body: web::Json<CreateWebhook>, req: HttpRequest) -> Result<impl Responder, AppError> {
+    body.validate()?;
+    if let Err(message) = webhooks::check_url(&body.url, &config.webhooks).await {
+        return Err(AppError::Validation(vec![FieldError { field: "url".to_string(), message: format!("url is not allowed: {}", message) }]));
+    }
+
+    let user_id = auth::get_authenticated_user_id(&req)
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+    let project_id = find_project_id(pool.get_ref(), &body.project_name).await?;
+
+    // A webhook receives every bug of its project, so only those who may see all of them can add one
+    let owner = sqlx::query_scalar::<_, Uuid>("SELECT user_id FROM projectRecord WHERE id = ?")
+        .bind(project_id.as_bytes().as_slice())
+        .fetch_one(pool.get_ref())
+        .await
+        .map_err(AppError::Database)?;
+    if owner != user_id && !auth::is_admin(pool.get_ref(), user_id).await.map_err(AppError::Database)? {
+        return Err(AppError::Forbidden("Only administrators and the project owner can add webhooks to it".to_string()));
+    }
+
+    // Store the subscription as a normalised list, "*" when every event is wanted
+    let mut events: Vec<String> = body.events.iter().map(|event| event.trim().to_ascii_lowercase()).collect();
+    events.sort();
+    events.dedup();
+    if events.is_empty() || events.iter().any(|event| event == "*") {
+        events = vec!["*".to_string()];
+    }
+
+    let secret = body.secret.clone().unwrap_or_else(webhooks::generate_secret);
+
+    let record = sqlx::query_as::<_, WebhookRecord>(&format!(
+        "INSERT INTO webhook (id, project_id, url, secret, events, created_by) VALUES (?, ?, ?, ?, ?, ?) RETURNING {}",
+        WEBHOOK_COLUMNS
+    ))
+    .bind(Uuid::new_v4().as_bytes().as_slice())
+    .bind(project_id.as_bytes().as_slice())
+    .bind(&body.url)
+    .bind(&secret)
+    .bind(events.join(","))
+    .bind(user_id.as_bytes().as_slice())
+    .fetch_one(pool.get_ref())
+    .await
+    .map_err(|e| {
+        error!(error = ?e, "Create webhook error");
+        AppError::from(e)
+    })?;
+
+    let webhook = Webhook { secret: Some(secret), ..Webhook::from(record) };
+    Ok(HttpResponse::Ok().json(webhook))
+}
+
+// Asynchronous function listing webhooks, optionally only those of one project (requires authentication).
+#[utoipa::path(
+    get, path = "/webhooks", tag = "webhooks",
+    params(WebhookFilter),
+    responses(
+        (status = 200, description = "Webhooks the user may manage, without their secrets", body = Vec<Webhook>),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 404, description = "Not found", body = ErrorBody)
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_webhooks(pool: web::Data<SqlitePool>, filter: web::Query<WebhookFilter>, req: HttpRequest) -> Result<impl Responder, AppError> {
+    let user_id = auth::get_authenticated_user_id(&req)
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+
+    let mut query = QueryBuilder::<Sqlite>::new(format!("SELECT {} FROM webhook WHERE 1 = 1", WEBHOOK_COLUMNS));
+    if let Some(project_name) = &filter.project_name {
+        let project_id = find_project_id(pool.get_ref(), project_name).await?;
+        query.push(" AND project_id = ").push_bind(project_id.as_bytes().to_vec());
+    }
+    // Administrators see every webhook, other users those they created or that belong to their projects
+    if !auth::is_admin(pool.get_ref(), user_id).await.map_err(AppError::Database)? {
+        query
+            .push(" AND (created_by = ")
+            .push_bind(user_id.as_bytes().to_vec())
+            .push(" OR project_id IN (SELECT id FROM projectRecord WHERE user_id = ")
+            .push_bind(user_id.as_bytes().to_vec())
+            .push("))");
+    }
+    query.push(" ORDER BY created_at");
+
+    let webhooks: Vec<Webhook> = query
+        .build_query_as::<WebhookRecord>()
+        .fetch_all(pool.get_ref())
+        .await
+        .map_err(|e| {
+            error!(error = ?e, "Webhook query error");
+            AppError::Database(e)
+        })?
+        .into_iter()
+        .map(Webhook::from)
+        .collect();
+
+    Ok(HttpResponse::Ok().json(webhooks))
+}
+
+// Asynchronous function removing a webhook together with its delivery log (requires authentication).
+#[utoipa::path(
+    delete, path = "/webhooks/{id}", tag = "webhooks",
+    params(("id" = Uuid, Path, description = "Webhook id")),
+    responses(
+        (status = 200, description = "Webhook deleted", body = String, content_type = "text/plain"),
+        (status = 400, description = "Malformed request", body = ErrorBody),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Not found", body = ErrorBody)
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn delete_webhook(pool: web::Data<SqlitePool>, webhook_id: web::Path<String>, req: HttpRequest) -> Result<impl Responder, AppError> {
+    let webhook_id = parse_webhook_id(&webhook_id)?;
+    authorize_webhook(pool.get_ref(), webhook_id, &req).await?;
+
+    let result = sqlx::query("DELETE FROM webhook WHERE id = ?")
+        .bind(webhook_id.as_bytes().as_slice())
+        .execute(pool.get_ref())
+        .await
+        .map_err(|e| {
+            error!(error = ?e, "Delete webhook error");
+            AppError::Database(e)
+        })?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Webhook not found".to_string()));
+    }
+    Ok(HttpResponse::Ok().body("Webhook deleted successfully"))
+}
+
+// Asynchronous function returning the most recent deliveries of a webhook, newest first (requires authentication).
+#[utoipa::path(
+    get, path = "/webhooks/{id}/deliveries", tag = "webhooks",
+    params(("id" = Uuid, Path, description = "Webhook id")),
+    responses(
+        (status = 200, description = "Deliveries of the webhook, newest first", body = Vec<WebhookDelivery>),
+        (status = 400, description = "Malformed request", body = ErrorBody),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Not found", body = ErrorBody)
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_webhook_deliveries(pool: web::Data<SqlitePool>, webhook_id: web::Path<String>, req: HttpRequest) -> Result<impl Responder, AppError> {
+    let webhook_id = parse_webhook_id(&webhook_id)?;
+    authorize_webhook(pool.get_ref(), webhook_id, &req).await?;
+
+    let deliveries = sqlx::query_as::<_, WebhookDelivery>(&format!(
+        "SELECT {} FROM webhookDelivery WHERE webhook_id = ? ORDER BY created_at DESC, rowid DESC LIMIT 100",
+        webhooks::DELIVERY_COLUMNS
+    ))
+    .bind(webhook_id.as_bytes().as_slice())
+    .fetch_all(pool.get_ref())
+    .await
+    .map_err(|e| {
+        error!(error = ?e, "Webhook delivery query error");
+        AppError::Database(e)
+    })?;
+
+    Ok(HttpResponse::Ok().json(deliveries))
+}
+
+// Asynchronous function queueing a delivery again, e.g. after the receiver was fixed (requires authentication).
+#[utoipa::path(
+    post, path = "/webhooks/deliveries/{id}/redeliver", tag = "webhooks",
+    params(("id" = Uuid, Path, description = "Delivery id")),
+    responses(
+        (status = 200, description = "The new delivery", body = WebhookDelivery),
+        (status = 400, description = "Malformed request", body = ErrorBody),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 403, description = "Not allowed for this user", body = ErrorBody),
+        (status = 404, description = "Not found", body = ErrorBody)
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn redeliver_webhook(pool: web::Data<SqlitePool>, delivery_id: web::Path<String>, req: HttpRequest) -> Result<impl Responder, AppError> {
+    let delivery_id = Uuid::parse_str(&delivery_id)
+        .map_err(|e| AppError::BadRequest(format!("Invalid Delivery ID format: {}", e)))?;
+
+    let webhook_id = sqlx::query_scalar::<_, Uuid>("SELECT webhook_id FROM webhookDelivery WHERE id = ?")
+        .bind(delivery_id.as_bytes().as_slice())
+        .fetch_optional(pool.get_ref())
+        .await
+        .map_err(AppError::Database)?
+        .ok_or_else(|| AppError::NotFound("Delivery not found".to_string()))?;
+    authorize_webhook(pool.get_ref(), webhook_id, &req).await?;
+
+    let delivery = webhooks::redeliver(pool.get_ref(), delivery_id)
+        .await
+        .map_err(|e| {
+            error!(error = ?e, "Redeliver webhook error");
+            AppError::Database(e)
+        })?
+        .ok_or_else(|| AppError::NotFound("Delivery not found".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(delivery))
+}
+
+fn parse_webhook_id(webhook_id: &str) -> Result<Uuid, AppError> {
+    Uuid::parse_str(webhook_id).map_err(|e| AppError::BadRequest(format!("Invalid Webhook ID format: {}", e)))
+}
+
+// A webhook can be managed by the user who created it, the owner of its project and administrators.
+// NotFound if the webhook does not exist, Forbidden for anybody else.
+async fn authorize_webhook(pool: &SqlitePool, webhook_id: Uuid, req: &HttpRequest) -> Result<(), AppError> {
+    let user_id = auth::get_authenticated_user_id(req)
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+
+    let (created_by, project_owner) = sqlx::query_as::<_, (Uuid, Option<Uuid>)>(
+        "SELECT w.created_by, p.user_id FROM webhook w LEFT JOIN projectRecord p ON p.id = w.project_id WHERE w.id = ?"
+    )
+    .bind(webhook_id.as_bytes().as_slice())
+    .fetch_optional(pool)
+    .await
+    .map_err(AppError::Database)?
+    .ok_or_else(|| AppError::NotFound("Webhook not found".to_string()))?;
+
+    if created_by != user_id
+        && project_owner != Some(user_id)
+        && !auth::is_admin(pool, user_id).await.map_err(AppError::Database)?
+    {
+        return Err(AppError::Forbidden(
+            "Only administrators, the project owner and the user who created the webhook can manage it".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+// Looks up a project id by name, or NotFound.
+async fn find_project_id(pool: &SqlitePool, project_name: &str) -> Result<Uuid, AppError> {
+    sqlx::query_scalar::<_, Uuid>("SELECT id FROM projectRecord WHERE project_name = ?")
+        .bind(project_name)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| {
+            error!(error = ?e, "Project query error");
+            AppError::Database(e)
+        })?
+        .ok_or_else(|| AppError::NotFound("Project not found".to_string()))
+}
+
+// Asynchronous function returning the authenticated user's email address and notification preferences.
+#[utoipa::path(
+    get, path = "/me/preferences", tag = "me",
+    responses(
+        (status = 200, description = "Email address and notification preferences", body = NotificationPreferences),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 404, description = "Not found", body = ErrorBody)
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_preferences(pool: web::Data<SqlitePool>, req: HttpRequest) -> Result<impl Responder, AppError> {
+    let user_id = auth::get_authenticated_user_id(&req)
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+
+    let preferences = email::load_preferences(pool.get_ref(), user_id)
+        .await
+        .map_err(|e| {
+            error!(error = ?e, "Preferences query error");
+            AppError::Database(e)
+        })?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(preferences))
+}
+
+// Asynchronous function listing the bugs the authenticated user watches, most recently watched first.
+#[utoipa::path(
+    get, path = "/me/watching", tag = "me",
+    responses(
+        (status = 200, description = "Bugs the user watches, most recently watched first", body = Vec<WatchedBug>),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody)
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_watching(pool: web::Data<SqlitePool>, req: HttpRequest) -> Result<impl Responder, AppError> {
+    let user_id = auth::get_authenticated_user_id(&req)
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+
+    let mut bugs = watchers::watched_bugs(pool.get_ref(), user_id)
+        .await
+        .map_err(|e| {
+            error!(error = ?e, "Watched bugs query error");
+            AppError::Database(e)
+        })?;
+    for watched in &mut bugs {
+        watched.bug.description_html = Some(markdown::to_html(&watched.bug.description));
+    }
+
+    Ok(HttpResponse::Ok().json(bugs))
+}
+
+// Asynchronous function returning the authenticated user's notifications, newest first, with unread counts.
+#[utoipa::path(
+    get, path = "/me/notifications", tag = "me",
+    params(NotificationQuery),
+    responses(
+        (status = 200, description = "Notification inbox, newest first", body = NotificationInbox),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 422, description = "Validation failed", body = ErrorBody)
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn get_notifications(pool: web::Data<SqlitePool>, query: web::Query<NotificationQuery>, req: HttpRequest) -> Result<impl Responder, AppError> {
+    query.validate()?;
+
+    let user_id = auth::get_authenticated_user_id(&req)
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+
+    let inbox = notifications::inbox(pool.get_ref(), user_id, query.unread_only, query.limit)
+        .await
+        .map_err(|e| {
+            error!(error = ?e, "Notifications query error");
+            AppError::Database(e)
+        })?;
+
+    Ok(HttpResponse::Ok().json(inbox))
+}
+
+// Asynchronous function marking one of the authenticated user's notifications as read.
+#[utoipa::path(
+    post, path = "/me/notifications/{id}/read", tag = "me",
+    params(("id" = Uuid, Path, description = "Notification id")),
+    responses(
+        (status = 200, description = "The notification", body = Notification),
+        (status = 400, description = "Malformed request", body = ErrorBody),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 404, description = "Not found", body = ErrorBody)
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn mark_notification_read(pool: web::Data<SqlitePool>, notification_id: web::Path<String>, req: HttpRequest) -> Result<impl Responder, AppError> {
+    let user_id = auth::get_authenticated_user_id(&req)
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+    let notification_id = Uuid::parse_str(&notification_id.into_inner())
+        .map_err(|e| AppError::BadRequest(format!("Invalid Notification ID format: {}", e)))?;
+
+    // Other users' notifications are reported as not found
+    let notification = notifications::mark_read(pool.get_ref(), user_id, notification_id)
+        .await
+        .map_err(|e| {
+            error!(error = ?e, "Mark notification read error");
+            AppError::Database(e)
+        })?
+        .ok_or_else(|| AppError::NotFound("Notification not found".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(notification))
+}
+
+// Asynchronous function marking all of the authenticated user's notifications as read.
+#[utoipa::path(
+    post, path = "/me/notifications/read-all", tag = "me",
+    responses(
+        (status = 200, description = "Number of notifications marked read", body = Object),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody)
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn mark_all_notifications_read(pool: web::Data<SqlitePool>, req: HttpRequest) -> Result<impl Responder, AppError> {
+    let user_id = auth::get_authenticated_user_id(&req)
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+
+    let marked = notifications::mark_all_read(pool.get_ref(), user_id)
+        .await
+        .map_err(|e| {
+            error!(error = ?e, "Mark notifications read error");
+            AppError::Database(e)
+        })?;
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "marked": marked })))
+}
+
+// Asynchronous function updating the authenticated user's email address and notification preferences.
+// Fields that are left out keep their current value.
+#[utoipa::path(
+    patch, path = "/me/preferences", tag = "me",
+    request_body = UpdatePreferences,
+    responses(
+        (status = 200, description = "Updated preferences", body = NotificationPreferences),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 404, description = "Not found", body = ErrorBody),
+        (status = 422, description = "Validation failed", body = ErrorBody)
+    ),
+    security(("bearer_auth" = []))
+)]
+async fn update_preferences(pool: web::Data<SqlitePool>, body: web::Json<UpdatePreferences>, req: HttpRequest) -> Result<impl Responder, AppError> {
+    body.validate()?;
+
+    let user_id = auth::get_authenticated_user_id(&req)
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+    let user_id_bytes = user_id.as_bytes().to_vec();
+
+    let mut tx = pool.begin().await.map_err(AppError::Database)?;
+
+    if let Some(email) = &body.email {
+        // A duplicate address is reported as 409 Conflict
+        sqlx::query("UPDATE users SET email = ? WHERE id = ?")
+            .bind(email)
+            .bind(&user_id_bytes)
+            .execute(&mut *tx)
+            .await
+            .map_err(AppError::from)?;
+    }
+
+    sqlx::query("INSERT INTO notificationPreference (user_id) VALUES (?) ON CONFLICT (user_id) DO NOTHING")
+        .bind(&user_id_bytes)
+        .execute(&mut *tx)
+        .await
+        .map_err(AppError::from)?;
+
+    sqlx::query(
+        "UPDATE notificationPreference SET email_enabled = COALESCE(?, email_enabled), notify_assigned = COALESCE(?, notify_assigned), \
+         notify_status_changed = COALESCE(?, notify_status_changed), \
+         notify_mentioned = COALESCE(?, notify_mentioned), digest = COALESCE(?, digest) WHERE user_id = ?"
+    )
+    .bind(body.email_enabled)
+    .bind(body.notify_assigned)
+    .bind(body.notify_status_changed)
+    .bind(body.notify_mentioned)
+    .bind(body.digest)
+    .bind(&user_id_bytes)
+    .execute(&mut *tx)
+    .await
+    .map_err(AppError::from)?;
+
+    tx.commit().await.map_err(AppError::Database)?;
+
+    let preferences = email::load_preferences(pool.get_ref(), user_id)
+        .await
+        .map_err(AppError::Database)?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    Ok(HttpResponse::Ok().json(preferences))
+}
+
+// How often an idle event stream sends a comment line, so proxies and browsers keep the connection open.
+const EVENT_STREAM_KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+// Asynchronous function streaming domain events to the client as Server-Sent Events, each one as
+// `event: bug.updated` followed by `data: <the JSON a webhook receives>`. Clients that fall too far
+// behind get an `event: resync` and should reload what they display. When the token expires the stream sends
+// `event: reauth` and ends; the client has to log in again and reconnect with the new token.
+#[utoipa::path(
+    get, path = "/events", tag = "events",
+    params(EventStreamQuery),
+    responses(
+        (status = 200, description = "Server-Sent Events stream of domain events", body = String, content_type = "text/event-stream"),
+        (status = 401, description = "Missing or invalid token", body = ErrorBody),
+        (status = 404, description = "Not found", body = ErrorBody)
+    ),
+    security(("bearer_auth" = []), ("access_token" = []))
+)]
+async fn stream_events(pool: web::Data<SqlitePool>, config: web::Data<Config>, query: web::Query<EventStreamQuery>, req: HttpRequest) -> Result<impl Responder, AppError> {
+    // The Authorization header takes precedence over the access_token parameter
+    let (_, expires_at) = auth::bearer_token(&req)
+        .and_then(|token| auth::decode_token(token, &config.auth))
+        .or_else(|| query.access_token.as_deref().and_then(|token| auth::decode_token(token, &config.auth)))
+        .ok_or_else(|| AppError::Unauthorized("Authentication required".to_string()))?;
+    // Token validation allows some clock skew past `exp`; a stream that would end at once is refused instead
+    let expires_in = u64::try_from(expires_at - chrono::Utc::now().timestamp())
+        .ok()
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs)
+        .ok_or_else(|| AppError::Unauthorized("Token has expired".to_string()))?;
+
+    // Every project is visible to every user (GET /projects and GET /bugs are public),
+    // so a stream carries all of them unless narrowed to one
+    let project_id = match &query.project_name {
+        Some(name) => Some(find_project_id(pool.get_ref(), name).await?),
+        None => None,
+    };
+
+    let mut keep_alive = tokio::time::interval(EVENT_STREAM_KEEP_ALIVE);
+    keep_alive.reset(); // The first tick would otherwise fire immediately
+
+    // Tell EventSource to reconnect after 3 seconds if the connection drops
+    let opening = futures_util::stream::once(async { Ok::<_, AppError>(web::Bytes::from_static(b"retry: 3000\n\n")) });
+    let expiry = Box::pin(tokio::time::sleep(expires_in));
+    let state = (events::subscribe(), keep_alive, expiry, false);
+    let frames = futures_util::stream::unfold(state, move |(mut receiver, mut keep_alive, mut expiry, mut expired)| async move {
+        if expired {
+            return None;
+        }
+        let frame = loop {
+            tokio::select! {
+                received = receiver.recv() => match received {
+                    Ok(event) if project_id.is_none_or(|id| id == event.project_id) => break events::sse_frame(&event),
+                    Ok(_) => continue,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        break format!("event: resync\ndata: {{\"missed\":{}}}\n\n", missed)
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                },
+                _ = keep_alive.tick() => break ": keep-alive\n\n".to_string(),
+                // The connection was only authorised for the lifetime of the token
+                _ = &mut expiry => {
+                    expired = true;
+                    break "event: reauth\ndata: {}\n\n".to_string();
+                }
+                // End the stream so that it does not hold up the shutdown; EventSource reconnects to the next server
+                _ = shutdown::requested() => return None,
+            }
+        };
+        Some((Ok(web::Bytes::from(frame)), (receiver, keep_alive, expiry, expired)))
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        // Stop reverse proxies such as nginx from buffering the stream
+        .insert_header(("X-Accel-Buffering", "no"))
+        .streaming(opening.chain(frames)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn bug_etag_is_strong_and_named_after_the_version() {
+        let etag = bug_etag(3);
+        assert!(!etag.weak);
+        assert_eq!(etag.to_string(), "\"v3\"");
+    }
+
+    #[test]
+    fn missing_if_match_is_allowed() {
+        let req = TestRequest::default().to_http_request();
+        assert!(check_if_match(&req, 3).is_ok());
+    }
+
+    #[test]
+    fn matching_if_match_is_allowed() {
+        let req = TestRequest::default().insert_header((header::IF_MATCH, "\"v3\"")).to_http_request();
+        assert!(check_if_match(&req, 3).is_ok());
+
+        let req = TestRequest::default().insert_header((header::IF_MATCH, "\"v2\", \"v3\"")).to_http_request();
+        assert!(check_if_match(&req, 3).is_ok());
+    }
+
+    #[test]
+    fn wildcard_if_match_is_allowed() {
+        let req = TestRequest::default().insert_header((header::IF_MATCH, "*")).to_http_request();
+        assert!(check_if_match(&req, 3).is_ok());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn stale_if_match_is_rejected() {
+        let req = TestRequest::default().insert_header((header::IF_MATCH, "\"v2\"")).to_http_request();
+        assert!(matches!(check_if_match(&req, 3), Err(AppError::PreconditionFailed(_))));
+    }
+
+    #[test]
+    fn weak_if_match_is_rejected() {
+        // If-Match uses the strong comparison, so a weak tag never matches
+        let req = TestRequest::default().insert_header((header::IF_MATCH, "W/\"v3\"")).to_http_request();
+        assert!(matches!(check_if_match(&req, 3), Err(AppError::PreconditionFailed(_))));
+    }
+
+    #[test]
+    fn malformed_if_match_is_a_bad_request() {
+        let req = TestRequest::default().insert_header((header::IF_MATCH, "v3")).to_http_request();
+        assert!(matches!(check_if_match(&req, 3), Err(AppError::BadRequest(_))));
+    }
+
+    #[test]
+    fn every_v1_route_has_a_legacy_prefix() {
+        use utoipa::OpenApi;
+        for path in openapi::ApiDoc::openapi().paths.paths.keys() {
+            assert!(is_legacy_path(path), "{} is missing from LEGACY_PREFIXES", path);
+        }
+        assert!(is_legacy_path("/bugs/new"));
+        assert!(!is_legacy_path("/bugsy"));
+        assert!(!is_legacy_path("/favicon.ico"));
+    }
+
+    #[actix_web::test]
+    async fn only_legacy_paths_carry_the_deprecation_headers() {
+        use actix_web::test::{call_service, init_service};
+        let app = init_service(actix_web::App::new().configure(config)).await;
+
+        // Wrong method on a legacy alias
+        let res = call_service(&app, TestRequest::delete().uri("/login").to_request()).await;
+        assert_eq!(res.status(), 405);
+        assert_eq!(res.headers().get("Deprecation").unwrap(), LEGACY_DEPRECATION);
+        assert_eq!(res.headers().get("Sunset").unwrap(), LEGACY_SUNSET);
+        assert_eq!(res.headers().get(header::LINK).unwrap(), LEGACY_LINK);
+
+        for uri in ["/favicon.ico", "/loginx", "/api/v1/nothing"] {
+            let res = call_service(&app, TestRequest::get().uri(uri).to_request()).await;
+            assert_eq!(res.status(), 404, "{}", uri);
+            assert!(!res.headers().contains_key("Deprecation"), "{}", uri);
+            assert!(!res.headers().contains_key(header::LINK), "{}", uri);
+        }
+    }
+}
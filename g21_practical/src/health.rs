@@ -0,0 +1,156 @@
+// Endpoints for orchestration scripts and load balancers, all public:
+// - GET /healthz: liveness; answers 200 as long as the server is handling requests.
+// - GET /readyz: readiness; 200 when the database answers a query and every migration in database.migrations_dir is applied, 503 otherwise.
+// - GET /version: crate version, git commit and build time (recorded by build.rs) and the schema version.
+use actix_web::{web, HttpResponse};
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::time::Duration;
+use tracing::warn;
+
+use crate::config::Config;
+use crate::db;
+use crate::error::AppError;
+
+// A database that takes longer than this to answer `SELECT 1` counts as unreachable.
+const DATABASE_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Serialize)]
+struct Readiness {
+    status: &'static str,   // "ready" or "not_ready"
+    database: String,       // "ok" or what went wrong
+    migrations: String,     // "ok", "pending", "unknown" when the database is down, or what went wrong
+    pending_migrations: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct VersionInfo {
+    version: &'static str,
+    git_commit: &'static str,
+    build_time: &'static str,
+    schema_version: Option<String>, // Last applied migration, e.g. "0011_soft_delete"
+}
+
+// Asynchronous function answering liveness probes. It does not touch the database, so a slow database does not get
+// a healthy server restarted.
+pub async fn healthz() -> HttpResponse {
+    HttpResponse::Ok().json(serde_json::json!({ "status": "ok" }))
+}
+
+// Asynchronous function answering readiness probes.
+pub async fn readyz(pool: web::Data<SqlitePool>, config: web::Data<Config>) -> HttpResponse {
+    let database = match tokio::time::timeout(DATABASE_TIMEOUT, sqlx::query("SELECT 1").execute(pool.get_ref())).await {
+        Ok(Ok(_)) => "ok".to_string(),
+        Ok(Err(e)) => format!("query failed: {}", e),
+        Err(_) => format!("no answer within {} seconds", DATABASE_TIMEOUT.as_secs()),
+    };
+
+    let (migrations, pending_migrations) = if database != "ok" {
+        ("unknown".to_string(), Vec::new())
+    } else {
+        match db::pending_migrations(pool.get_ref(), &config.database.migrations_dir).await {
+            Ok(pending) if pending.is_empty() => ("ok".to_string(), pending),
+            Ok(pending) => ("pending".to_string(), pending),
+            Err(e) => (format!("check failed: {}", e), Vec::new()),
+        }
+    };
+
+    if database == "ok" && migrations == "ok" {
+        return HttpResponse::Ok().json(Readiness { status: "ready", database, migrations, pending_migrations });
+    }
+    warn!(%database, %migrations, pending = ?pending_migrations, "Not ready");
+    HttpResponse::ServiceUnavailable().json(Readiness { status: "not_ready", database, migrations, pending_migrations })
+}
+
+// Asynchronous function returning the version of the running build and of the database schema.
+pub async fn version(pool: web::Data<SqlitePool>) -> Result<HttpResponse, AppError> {
+    let schema_version = db::schema_version(pool.get_ref()).await.map_err(AppError::Database)?;
+    Ok(HttpResponse::Ok().json(VersionInfo {
+        version: env!("CARGO_PKG_VERSION"),
+        git_commit: env!("GIT_COMMIT"),
+        build_time: env!("BUILD_TIME"),
+        schema_version,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::{call_service, init_service, read_body_json, TestRequest};
+    use actix_web::App;
+    use serde_json::{json, Value};
+    use sqlx::sqlite::SqlitePoolOptions;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    // Names of the migrations in `migrations/`, in order.
+    fn migration_versions() -> Vec<String> {
+        let mut versions: Vec<String> = fs::read_dir("migrations")
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "sql"))
+            .map(|path| path.file_stem().unwrap().to_string_lossy().to_string())
+            .collect();
+        versions.sort();
+        versions
+    }
+
+    async fn get(pool: &SqlitePool, uri: &str) -> (u16, Value) {
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(web::Data::new(Config::default()))
+                .route("/readyz", web::get().to(readyz))
+                .route("/version", web::get().to(version)),
+        )
+        .await;
+        let res = call_service(&app, TestRequest::get().uri(uri).to_request()).await;
+        (res.status().as_u16(), read_body_json(res).await)
+    }
+
+    #[actix_web::test]
+    async fn readiness_and_schema_version_follow_the_applied_migrations() {
+        let versions = migration_versions();
+        let (last, previous) = (&versions[versions.len() - 1], &versions[versions.len() - 2]);
+
+        // A database with every migration but the last applied
+        let dir = std::env::temp_dir().join(format!("g21_health_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        for version in &versions[..versions.len() - 1] {
+            let file = format!("{}.sql", version);
+            fs::copy(Path::new("migrations").join(&file), dir.join(&file)).unwrap();
+        }
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        db::run_migrations(&pool, &dir).await.unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let (status, body) = get(&pool, "/readyz").await;
+        assert_eq!(status, 503);
+        assert_eq!(
+            body,
+            json!({ "status": "not_ready", "database": "ok", "migrations": "pending", "pending_migrations": [last] })
+        );
+        let (status, body) = get(&pool, "/version").await;
+        assert_eq!(status, 200);
+        assert_eq!(body["schema_version"], json!(previous));
+        assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+
+        db::run_migrations(&pool, &PathBuf::from("migrations")).await.unwrap();
+        let (status, body) = get(&pool, "/readyz").await;
+        assert_eq!(status, 200);
+        assert_eq!(body, json!({ "status": "ready", "database": "ok", "migrations": "ok", "pending_migrations": [] }));
+        assert_eq!(get(&pool, "/version").await.1["schema_version"], json!(last));
+    }
+
+    #[actix_web::test]
+    async fn an_unreachable_database_is_not_ready() {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        pool.close().await;
+
+        let (status, body) = get(&pool, "/readyz").await;
+        assert_eq!(status, 503);
+        assert_eq!(body["status"], "not_ready");
+        assert_eq!(body["migrations"], "unknown");
+        assert!(body["database"].as_str().unwrap().starts_with("query failed"));
+    }
+}
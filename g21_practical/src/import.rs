@@ -0,0 +1,654 @@
+// Bulk import of bug reports from CSV, generic JSON, GitHub issues and Jira export files.
+// Every source is first turned into `ImportRecord`s keyed by our own field names
+// (title, description, severity, priority, project, reported_by, fixed_by, is_fixed, labels),
+// then each record is validated and inserted on its own so one bad row never aborts the import.
+// Used by `POST /bugs/import` and by the `import` command line mode in main.rs.
+use serde_json::Value;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::fs;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::{
+    normalize_label, ImportFormat, ImportOutcome, ImportReport, ImportRowResult, Priority, Severity, MAX_DESCRIPTION_LEN,
+    MAX_TITLE_LEN,
+};
+use crate::{mentions, watchers};
+
+// Fields an import row can set, i.e. the valid targets of a column mapping.
+pub const IMPORT_FIELDS: [&str; 9] = [
+    "title",
+    "description",
+    "severity",
+    "priority",
+    "project",
+    "reported_by",
+    "fixed_by",
+    "is_fixed",
+    "labels",
+];
+
+// Options shared by the HTTP endpoint and the command line.
+#[derive(Debug)]
+pub struct ImportOptions {
+    pub format: ImportFormat,
+    pub dry_run: bool,
+    pub default_project: Option<String>,
+    pub mapping: HashMap<String, String>, // Our field name -> column/key in the source file
+}
+
+// One source row with values keyed by our field names.
+#[derive(Debug, Default)]
+pub struct ImportRecord {
+    pub row: usize,
+    pub fields: HashMap<String, String>,
+    pub errors: Vec<String>, // Problems found while reading the row itself
+}
+
+impl ImportRecord {
+    fn get(&self, field: &str) -> Option<&str> {
+        self.fields.get(field).map(|v| v.trim()).filter(|v| !v.is_empty())
+    }
+}
+
+// Parses a mapping such as "title:Summary,description:Details" into our field -> source column.
+pub fn parse_mapping(mapping: Option<&str>) -> Result<HashMap<String, String>, AppError> {
+    let mut result = HashMap::new();
+    let Some(mapping) = mapping else {
+        return Ok(result);
+    };
+
+    for pair in mapping.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (field, column) = pair
+            .split_once(':')
+            .ok_or_else(|| AppError::BadRequest(format!("Invalid mapping '{}', expected field:column", pair)))?;
+        let field = field.trim();
+        if !IMPORT_FIELDS.contains(&field) {
+            return Err(AppError::BadRequest(format!(
+                "Unknown import field '{}', expected one of: {}",
+                field,
+                IMPORT_FIELDS.join(", ")
+            )));
+        }
+        result.insert(field.to_string(), column.trim().to_string());
+    }
+    Ok(result)
+}
+
+// Turns the raw file into records. Errors here mean the whole file is unreadable.
+pub fn parse_source(data: &[u8], options: &ImportOptions) -> Result<Vec<ImportRecord>, AppError> {
+    match options.format {
+        ImportFormat::Csv => parse_csv(data, &options.mapping),
+        ImportFormat::Json => {
+            let value = parse_json(data)?;
+            let items = match value {
+                Value::Array(items) => items,
+                Value::Object(mut object) => match object.remove("bugs") {
+                    Some(Value::Array(items)) => items,
+                    _ => return Err(AppError::BadRequest("Expected a JSON array or an object with a \"bugs\" array".to_string())),
+                },
+                _ => return Err(AppError::BadRequest("Expected a JSON array of bugs".to_string())),
+            };
+            Ok(items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| json_record(index + 1, item, &options.mapping))
+                .collect())
+        }
+        ImportFormat::Github => {
+            let items = match parse_json(data)? {
+                Value::Array(items) => items,
+                _ => return Err(AppError::BadRequest("Expected a JSON array of GitHub issues".to_string())),
+            };
+            Ok(items.iter().enumerate().map(|(index, item)| github_record(index + 1, item)).collect())
+        }
+        ImportFormat::Jira => {
+            let items = match parse_json(data)? {
+                Value::Object(mut object) => match object.remove("issues") {
+                    Some(Value::Array(items)) => items,
+                    _ => return Err(AppError::BadRequest("Expected a Jira export with an \"issues\" array".to_string())),
+                },
+                Value::Array(items) => items,
+                _ => return Err(AppError::BadRequest("Expected a Jira export with an \"issues\" array".to_string())),
+            };
+            Ok(items.iter().enumerate().map(|(index, item)| jira_record(index + 1, item)).collect())
+        }
+    }
+}
+
+fn parse_json(data: &[u8]) -> Result<Value, AppError> {
+    serde_json::from_slice(data).map_err(|e| AppError::InvalidJson(format!("Import file is not valid JSON: {}", e)))
+}
+
+// Source column for one of our fields: the mapped column, or the field name itself.
+fn source_key<'a>(mapping: &'a HashMap<String, String>, field: &'a str) -> &'a str {
+    mapping.get(field).map(String::as_str).unwrap_or(field)
+}
+
+fn parse_csv(data: &[u8], mapping: &HashMap<String, String>) -> Result<Vec<ImportRecord>, AppError> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(data);
+    let headers = reader
+        .headers()
+        .map_err(|e| AppError::BadRequest(format!("Could not read CSV header: {}", e)))?
+        .clone();
+
+    let mut records = Vec::new();
+    for (index, row) in reader.records().enumerate() {
+        let mut record = ImportRecord { row: index + 1, ..Default::default() };
+        match row {
+            Ok(row) => {
+                for field in IMPORT_FIELDS {
+                    let key = source_key(mapping, field);
+                    if let Some(position) = headers.iter().position(|h| h.trim() == key)
+                        && let Some(value) = row.get(position)
+                    {
+                        record.fields.insert(field.to_string(), value.to_string());
+                    }
+                }
+            }
+            Err(e) => record.errors.push(format!("Malformed CSV row: {}", e)),
+        }
+        records.push(record);
+    }
+    Ok(records)
+}
+
+fn json_record(row: usize, item: &Value, mapping: &HashMap<String, String>) -> ImportRecord {
+    let mut record = ImportRecord { row, ..Default::default() };
+    let Some(object) = item.as_object() else {
+        record.errors.push("Expected a JSON object".to_string());
+        return record;
+    };
+
+    for field in IMPORT_FIELDS {
+        if let Some(value) = object.get(source_key(mapping, field)).and_then(json_to_string) {
+            record.fields.insert(field.to_string(), value);
+        }
+    }
+    record
+}
+
+// Flattens a JSON value into the string form used by records; arrays become comma-separated lists.
+fn json_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        Value::Array(items) => Some(items.iter().filter_map(json_to_string).collect::<Vec<_>>().join(",")),
+        other => Some(other.to_string()),
+    }
+}
+
+// GitHub issues API format: severity and priority come from labels such as "severity:high" or "critical".
+fn github_record(row: usize, item: &Value) -> ImportRecord {
+    let mut record = ImportRecord { row, ..Default::default() };
+    if item.get("pull_request").is_some() {
+        record.errors.push("Pull requests are not imported".to_string());
+        return record;
+    }
+
+    let mut set = |field: &str, value: Option<&str>| {
+        if let Some(value) = value {
+            record.fields.insert(field.to_string(), value.to_string());
+        }
+    };
+    set("title", item["title"].as_str());
+    set("description", item["body"].as_str());
+    set("reported_by", item["user"]["login"].as_str());
+    set("fixed_by", item["assignee"]["login"].as_str());
+    set("is_fixed", item["state"].as_str().map(|state| if state == "closed" { "true" } else { "false" }));
+
+    let labels: Vec<String> = item["labels"]
+        .as_array()
+        .map(|labels| {
+            labels
+                .iter()
+                .filter_map(|label| label["name"].as_str().or_else(|| label.as_str()))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    apply_labels(&mut record, labels);
+    record
+}
+
+// Jira export format (`{"issues": [{"key": ..., "fields": {...}}]}`); Jira's priority maps onto ours.
+fn jira_record(row: usize, item: &Value) -> ImportRecord {
+    let mut record = ImportRecord { row, ..Default::default() };
+    let fields = &item["fields"];
+    if !fields.is_object() {
+        record.errors.push("Jira issue has no \"fields\" object".to_string());
+        return record;
+    }
+
+    let user = |value: &Value| value["name"].as_str().or_else(|| value["displayName"].as_str()).map(str::to_string);
+    let mut set = |field: &str, value: Option<String>| {
+        if let Some(value) = value {
+            record.fields.insert(field.to_string(), value);
+        }
+    };
+    set("title", fields["summary"].as_str().map(str::to_string));
+    set("description", jira_text(&fields["description"]));
+    set("project", fields["project"]["name"].as_str().map(str::to_string));
+    set("reported_by", user(&fields["reporter"]));
+    set("fixed_by", user(&fields["assignee"]));
+    set(
+        "priority",
+        fields["priority"]["name"].as_str().map(|name| {
+            match name.to_ascii_lowercase().as_str() {
+                "highest" | "blocker" | "critical" => "urgent",
+                "high" | "major" => "high",
+                "low" | "lowest" | "minor" | "trivial" => "low",
+                _ => "medium",
+            }
+            .to_string()
+        }),
+    );
+    let done = fields["status"]["statusCategory"]["key"].as_str() == Some("done")
+        || matches!(fields["status"]["name"].as_str(), Some("Done" | "Closed" | "Resolved"));
+    set("is_fixed", Some(done.to_string()));
+
+    let labels: Vec<String> = fields["labels"]
+        .as_array()
+        .map(|labels| labels.iter().filter_map(|l| l.as_str()).map(str::to_string).collect())
+        .unwrap_or_default();
+    apply_labels(&mut record, labels);
+    record
+}
+
+// Jira Cloud descriptions are Atlassian Document Format trees; collect their text nodes.
+fn jira_text(value: &Value) -> Option<String> {
+    fn collect(value: &Value, out: &mut Vec<String>) {
+        match value {
+            Value::Object(object) => {
+                if let Some(Value::String(text)) = object.get("text") {
+                    out.push(text.clone());
+                }
+                if let Some(content) = object.get("content") {
+                    collect(content, out);
+                }
+            }
+            Value::Array(items) => items.iter().for_each(|item| collect(item, out)),
+            _ => {}
+        }
+    }
+
+    match value {
+        Value::String(text) => Some(text.clone()),
+        Value::Null => None,
+        other => {
+            let mut parts = Vec::new();
+            collect(other, &mut parts);
+            Some(parts.join("\n"))
+        }
+    }
+}
+
+// Stores labels on the record, taking severity and priority from labels like "severity:high", "priority/low" or "critical".
+fn apply_labels(record: &mut ImportRecord, labels: Vec<String>) {
+    let mut plain = Vec::new();
+    for label in labels {
+        let lower = label.to_ascii_lowercase();
+        let (prefix, value) = lower
+            .split_once([':', '/'])
+            .map(|(p, v)| (p.trim(), v.trim()))
+            .unwrap_or(("", lower.as_str()));
+
+        if (prefix.is_empty() || prefix == "severity") && value.parse::<Severity>().is_ok() && !record.fields.contains_key("severity") {
+            record.fields.insert("severity".to_string(), value.to_string());
+        } else if prefix == "priority" && value.parse::<Priority>().is_ok() {
+            record.fields.insert("priority".to_string(), value.to_string());
+        } else {
+            plain.push(label);
+        }
+    }
+    if !plain.is_empty() {
+        record.fields.insert("labels".to_string(), plain.join(","));
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" | "y" | "fixed" | "closed" | "done" | "resolved" => Some(true),
+        "false" | "0" | "no" | "n" | "open" | "" => Some(false),
+        _ => None,
+    }
+}
+
+// Validates and inserts every record. Rows with errors are reported and skipped; the rest are inserted
+// in one transaction that is rolled back for a dry run. `importer` reports rows without a reporter.
+pub async fn import_records(
+    pool: &SqlitePool,
+    records: Vec<ImportRecord>,
+    options: &ImportOptions,
+    importer: Uuid,
+) -> Result<ImportReport, AppError> {
+    let mut projects: HashMap<String, Option<Uuid>> = HashMap::new();
+    let mut users: HashMap<String, Option<Uuid>> = HashMap::new();
+    let mut tx = pool.begin().await.map_err(AppError::Database)?;
+    let mut rows = Vec::with_capacity(records.len());
+
+    for record in records {
+        let title = record.get("title").map(str::to_string);
+
+        // Rows that could not even be read are reported as they are
+        if !record.errors.is_empty() {
+            rows.push(ImportRowResult { row: record.row, title, outcome: ImportOutcome::Error, bug_id: None, errors: record.errors });
+            continue;
+        }
+        let mut errors = Vec::new();
+
+        match &title {
+            None => errors.push("title is required".to_string()),
+            Some(t) if t.chars().count() as u64 > MAX_TITLE_LEN => errors.push("title must be at most 200 characters".to_string()),
+            _ => {}
+        }
+
+        let description = record.get("description").unwrap_or("(no description)").to_string();
+        if description.chars().count() as u64 > MAX_DESCRIPTION_LEN {
+            errors.push("description must be at most 10000 characters".to_string());
+        }
+
+        let severity = match record.get("severity").map(str::parse::<Severity>) {
+            None => Severity::Medium,
+            Some(Ok(severity)) => severity,
+            Some(Err(e)) => {
+                errors.push(e);
+                Severity::Medium
+            }
+        };
+        let priority = match record.get("priority").map(str::parse::<Priority>) {
+            None => Priority::Medium,
+            Some(Ok(priority)) => priority,
+            Some(Err(e)) => {
+                errors.push(e);
+                Priority::Medium
+            }
+        };
+        let is_fixed = match record.get("is_fixed") {
+            None => false,
+            Some(value) => parse_bool(value).unwrap_or_else(|| {
+                errors.push(format!("invalid is_fixed value '{}'", value));
+                false
+            }),
+        };
+
+        // Resolve names to ids, caching lookups across rows
+        let project_id = match record.get("project").or(options.default_project.as_deref()) {
+            None => {
+                errors.push("project is required".to_string());
+                None
+            }
+            Some(name) => {
+                let id = lookup(&mut projects, name, "SELECT id FROM projectRecord WHERE project_name = ?", pool).await?;
+                if id.is_none() {
+                    errors.push(format!("unknown project '{}'", name));
+                }
+                id
+            }
+        };
+        let reported_by = match record.get("reported_by") {
+            None => Some(importer),
+            Some(name) => {
+                let id = lookup(&mut users, name, "SELECT id FROM users WHERE username = ?", pool).await?;
+                if id.is_none() {
+                    errors.push(format!("unknown reporter '{}'", name));
+                }
+                id
+            }
+        };
+        let fixed_by = match record.get("fixed_by") {
+            None => None,
+            Some(name) => {
+                let id = lookup(&mut users, name, "SELECT id FROM users WHERE username = ?", pool).await?;
+                if id.is_none() {
+                    errors.push(format!("unknown assignee '{}'", name));
+                }
+                id
+            }
+        };
+
+        let mut bug_id = None;
+        if errors.is_empty()
+            && let (Some(title), Some(project_id), Some(reported_by)) = (&title, project_id, reported_by)
+        {
+            let id = Uuid::new_v4();
+            // A failing INSERT only undoes itself in SQLite, so the transaction carries on with the next row
+            let inserted = sqlx::query(
+                "INSERT INTO bugReport (id, project_id, title, description, reported_by, fixed_by, severity, priority, is_fixed) \
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            )
+            .bind(id.as_bytes().as_slice())
+            .bind(project_id.as_bytes().as_slice())
+            .bind(title)
+            .bind(&description)
+            .bind(reported_by.as_bytes().as_slice())
+            .bind(fixed_by.map(|id| id.as_bytes().to_vec()))
+            .bind(severity)
+            .bind(priority)
+            .bind(is_fixed)
+            .execute(&mut *tx)
+            .await;
+
+            match inserted.map_err(AppError::from) {
+                Ok(_) => {
+                    let labels = record.get("labels").unwrap_or_default();
+                    for label in labels.split(',').map(normalize_label).filter(|l| !l.is_empty()) {
+                        sqlx::query("INSERT OR IGNORE INTO bugLabel (bug_id, label) VALUES (?, ?)")
+                            .bind(id.as_bytes().as_slice())
+                            .bind(label)
+                            .execute(&mut *tx)
+                            .await
+                            .map_err(AppError::Database)?;
+                    }
+                    // Imports raise no events, so add the watchers that would otherwise be added from them
+                    for user_id in [Some(reported_by), fixed_by].into_iter().flatten() {
+                        watchers::watch(&mut *tx, id, user_id).await.map_err(AppError::Database)?;
+                    }
+                    mentions::sync(&mut tx, id, &description).await.map_err(AppError::Database)?;
+                    bug_id = Some(id);
+                }
+                Err(AppError::Conflict(_)) => errors.push("a bug with this title already exists".to_string()),
+                Err(e) => return Err(e),
+            }
+        }
+
+        rows.push(ImportRowResult {
+            row: record.row,
+            title,
+            outcome: if bug_id.is_some() { ImportOutcome::Imported } else { ImportOutcome::Error },
+            bug_id,
+            errors,
+        });
+    }
+
+    if options.dry_run {
+        tx.rollback().await.map_err(AppError::Database)?;
+    } else {
+        tx.commit().await.map_err(AppError::Database)?;
+    }
+
+    let imported = rows.iter().filter(|row| row.bug_id.is_some()).count();
+    Ok(ImportReport {
+        dry_run: options.dry_run,
+        total: rows.len(),
+        imported,
+        failed: rows.len() - imported,
+        rows,
+    })
+}
+
+// Looks up an id by name with a cache, so each distinct project or user is queried once.
+async fn lookup(cache: &mut HashMap<String, Option<Uuid>>, name: &str, sql: &str, pool: &SqlitePool) -> Result<Option<Uuid>, AppError> {
+    if let Some(id) = cache.get(name) {
+        return Ok(*id);
+    }
+    let id = sqlx::query_scalar::<_, Uuid>(sql)
+        .bind(name)
+        .fetch_optional(pool)
+        .await
+        .map_err(AppError::Database)?;
+    cache.insert(name.to_string(), id);
+    Ok(id)
+}
+
+// Command line mode: `g21_practical import <file> --format csv [--project NAME] [--map field:column ...] [--as USER] [--dry-run]`.
+// Imports into the database selected by DATABASE_URL and prints the report as JSON.
+pub async fn run_cli(pool: &SqlitePool, args: &[String]) -> Result<ImportReport, String> {
+    let mut file = None;
+    let mut format = None;
+    let mut default_project = None;
+    let mut mapping = Vec::new();
+    let mut importer = "admin".to_string();
+    let mut dry_run = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().cloned().ok_or_else(|| format!("{} needs a value", name));
+        match arg.as_str() {
+            "--format" => format = Some(value("--format")?.parse::<ImportFormat>()?),
+            "--project" => default_project = Some(value("--project")?),
+            "--map" => mapping.push(value("--map")?),
+            "--as" => importer = value("--as")?,
+            "--dry-run" => dry_run = true,
+            other if other.starts_with("--") => return Err(format!("unknown option {}", other)),
+            other => file = Some(other.to_string()),
+        }
+    }
+
+    let file = file.ok_or("usage: import <file> --format csv|json|github|jira [--project NAME] [--map field:column] [--as USER] [--dry-run]")?;
+    // Default the format from the file extension
+    let format = match format {
+        Some(format) => format,
+        None if file.ends_with(".csv") => ImportFormat::Csv,
+        None => ImportFormat::Json,
+    };
+
+    let options = ImportOptions {
+        format,
+        dry_run,
+        default_project,
+        mapping: parse_mapping(Some(&mapping.join(","))).map_err(|e| e.to_string())?,
+    };
+
+    let importer_id = sqlx::query_scalar::<_, Uuid>("SELECT id FROM users WHERE username = ?")
+        .bind(&importer)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("unknown user '{}'", importer))?;
+
+    let data = fs::read(&file).map_err(|e| format!("could not read {}: {}", file, e))?;
+    let records = parse_source(&data, &options).map_err(|e| e.to_string())?;
+    import_records(pool, records, &options, importer_id).await.map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn options(format: ImportFormat, mapping: Option<&str>) -> ImportOptions {
+        ImportOptions { format, dry_run: true, default_project: None, mapping: parse_mapping(mapping).unwrap() }
+    }
+
+    fn parse(format: ImportFormat, mapping: Option<&str>, data: &str) -> Vec<ImportRecord> {
+        parse_source(data.as_bytes(), &options(format, mapping)).unwrap()
+    }
+
+    #[test]
+    fn mapping_renames_source_columns() {
+        let mapping = parse_mapping(Some("title:Summary, description : Details,")).unwrap();
+        assert_eq!(mapping.get("title").map(String::as_str), Some("Summary"));
+        assert_eq!(mapping.get("description").map(String::as_str), Some("Details"));
+        assert!(parse_mapping(None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn mapping_rejects_unknown_fields_and_missing_columns() {
+        assert!(matches!(parse_mapping(Some("summary:Title")), Err(AppError::BadRequest(_))));
+        assert!(matches!(parse_mapping(Some("title")), Err(AppError::BadRequest(_))));
+    }
+
+    #[test]
+    fn csv_columns_are_read_through_the_mapping() {
+        let records = parse(ImportFormat::Csv, Some("title:Summary"), "Summary,severity,ignored\nCrash on save,high,x\n");
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].row, 1);
+        assert_eq!(records[0].get("title"), Some("Crash on save"));
+        assert_eq!(records[0].get("severity"), Some("high"));
+        assert_eq!(records[0].fields.len(), 2);
+    }
+
+    #[test]
+    fn json_accepts_an_array_or_a_bugs_object() {
+        let records = parse(ImportFormat::Json, None, r#"[{"title": "A", "labels": ["ui", "login"], "is_fixed": true}, 3]"#);
+        assert_eq!(records[0].get("labels"), Some("ui,login"));
+        assert_eq!(records[0].get("is_fixed"), Some("true"));
+        assert_eq!(records[1].errors, vec!["Expected a JSON object"]);
+
+        let records = parse(ImportFormat::Json, None, r#"{"bugs": [{"title": "B"}]}"#);
+        assert_eq!(records[0].get("title"), Some("B"));
+
+        let error = parse_source(b"{\"title\": \"C\"}", &options(ImportFormat::Json, None)).unwrap_err();
+        assert!(matches!(error, AppError::BadRequest(_)));
+        let error = parse_source(b"not json", &options(ImportFormat::Json, None)).unwrap_err();
+        assert!(matches!(error, AppError::InvalidJson(_)));
+    }
+
+    #[test]
+    fn github_labels_set_severity_and_priority() {
+        let issues = json!([
+            {
+                "title": "Crash",
+                "body": "Steps",
+                "state": "closed",
+                "user": {"login": "admin"},
+                "assignee": null,
+                "labels": [{"name": "Severity:Critical"}, {"name": "priority/low"}, {"name": "ui"}]
+            },
+            {"title": "A pull request", "pull_request": {}}
+        ]);
+        let records = parse(ImportFormat::Github, None, &issues.to_string());
+        assert_eq!(records[0].get("severity"), Some("critical"));
+        assert_eq!(records[0].get("priority"), Some("low"));
+        assert_eq!(records[0].get("labels"), Some("ui"));
+        assert_eq!(records[0].get("is_fixed"), Some("true"));
+        assert_eq!(records[0].get("reported_by"), Some("admin"));
+        assert_eq!(records[0].get("fixed_by"), None);
+        assert_eq!(records[1].errors, vec!["Pull requests are not imported"]);
+    }
+
+    #[test]
+    fn jira_priorities_statuses_and_descriptions_are_mapped() {
+        let export = json!({"issues": [{
+            "key": "APP-1",
+            "fields": {
+                "summary": "Crash",
+                "description": {"type": "doc", "content": [
+                    {"type": "paragraph", "content": [{"type": "text", "text": "First"}]},
+                    {"type": "paragraph", "content": [{"type": "text", "text": "Second"}]}
+                ]},
+                "project": {"name": "Project A"},
+                "reporter": {"displayName": "admin"},
+                "priority": {"name": "Blocker"},
+                "status": {"name": "In Progress", "statusCategory": {"key": "indeterminate"}}
+            }
+        }, {"key": "APP-2"}]});
+        let records = parse(ImportFormat::Jira, None, &export.to_string());
+        assert_eq!(records[0].get("description"), Some("First\nSecond"));
+        assert_eq!(records[0].get("priority"), Some("urgent"));
+        assert_eq!(records[0].get("project"), Some("Project A"));
+        assert_eq!(records[0].get("reported_by"), Some("admin"));
+        assert_eq!(records[0].get("is_fixed"), Some("false"));
+        assert_eq!(records[1].errors, vec!["Jira issue has no \"fields\" object"]);
+    }
+
+    #[test]
+    fn booleans_accept_tracker_statuses() {
+        assert_eq!(parse_bool("Closed"), Some(true));
+        assert_eq!(parse_bool(" no "), Some(false));
+        assert_eq!(parse_bool(""), Some(false));
+        assert_eq!(parse_bool("maybe"), None);
+    }
+}
@@ -0,0 +1,207 @@
+// Typed links between bugs: duplicate_of, blocks, relates_to and caused_by. Chains of blocking or duplicate links
+// may not loop back to where they started, and a bug can be the duplicate of only one other bug.
+use sqlx::{SqliteConnection, SqlitePool};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::models::{BugLink, BugLinkKind, BugLinkView};
+
+const LINK_COLUMNS: &str = "id, source_bug_id, target_bug_id, kind, created_by, created_at";
+
+// Links `source` to `target` after checking the link against the existing ones. Both bugs must exist.
+pub async fn add(conn: &mut SqliteConnection, source: Uuid, target: Uuid, kind: BugLinkKind, created_by: Option<Uuid>) -> Result<BugLink, AppError> {
+    // "A blocked_by B" is stored as "B blocks A"
+    let (source, target, kind) = match kind {
+        BugLinkKind::BlockedBy => (target, source, BugLinkKind::Blocks),
+        _ => (source, target, kind),
+    };
+    if source == target {
+        return Err(AppError::BadRequest("A bug cannot be linked to itself".to_string()));
+    }
+
+    // relates_to works both ways, so the reverse link counts as the same one
+    let existing = sqlx::query_scalar::<_, Uuid>(
+        "SELECT id FROM bugLink WHERE kind = ? AND ((source_bug_id = ? AND target_bug_id = ?) \
+         OR (kind = 'relates_to' AND source_bug_id = ? AND target_bug_id = ?))"
+    )
+    .bind(kind)
+    .bind(source.as_bytes().as_slice())
+    .bind(target.as_bytes().as_slice())
+    .bind(target.as_bytes().as_slice())
+    .bind(source.as_bytes().as_slice())
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(AppError::Database)?;
+    if existing.is_some() {
+        return Err(AppError::Conflict(format!("The bugs are already linked with {}", kind.as_str())));
+    }
+
+    match kind {
+        BugLinkKind::DuplicateOf => {
+            let duplicate_of = sqlx::query_scalar::<_, Uuid>("SELECT id FROM bugLink WHERE source_bug_id = ? AND kind = 'duplicate_of'")
+                .bind(source.as_bytes().as_slice())
+                .fetch_optional(&mut *conn)
+                .await
+                .map_err(AppError::Database)?;
+            if duplicate_of.is_some() {
+                return Err(AppError::Conflict("Bug is already marked as a duplicate of another bug".to_string()));
+            }
+            if reaches(conn, target, source, kind).await.map_err(AppError::Database)? {
+                return Err(AppError::Conflict("Linking would create a cycle of duplicates".to_string()));
+            }
+        }
+        BugLinkKind::Blocks if reaches(conn, target, source, kind).await.map_err(AppError::Database)? => {
+            return Err(AppError::Conflict("Linking would create a blocking cycle".to_string()));
+        }
+        _ => {}
+    }
+
+    sqlx::query_as::<_, BugLink>(&format!(
+        "INSERT INTO bugLink (id, source_bug_id, target_bug_id, kind, created_by) VALUES (?, ?, ?, ?, ?) RETURNING {}",
+        LINK_COLUMNS
+    ))
+    .bind(Uuid::new_v4().as_bytes().as_slice())
+    .bind(source.as_bytes().as_slice())
+    .bind(target.as_bytes().as_slice())
+    .bind(kind)
+    .bind(created_by.map(|id| id.as_bytes().to_vec()))
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(AppError::from)
+}
+
+// Whether `to` can be reached from `from` by following links of one kind.
+async fn reaches(conn: &mut SqliteConnection, from: Uuid, to: Uuid, kind: BugLinkKind) -> Result<bool, sqlx::Error> {
+    // UNION (rather than UNION ALL) skips bugs already visited, so the walk ends even if the links loop
+    sqlx::query_scalar::<_, bool>(
+        "WITH RECURSIVE chain(bug_id) AS ( \
+             SELECT ? \
+             UNION \
+             SELECT l.target_bug_id FROM bugLink l JOIN chain c ON l.source_bug_id = c.bug_id WHERE l.kind = ? \
+         ) SELECT EXISTS (SELECT 1 FROM chain WHERE bug_id = ?)"
+    )
+    .bind(from.as_bytes().as_slice())
+    .bind(kind)
+    .bind(to.as_bytes().as_slice())
+    .fetch_one(conn)
+    .await
+}
+
+// Removes a link to or from the bug. Returns false if the bug has no such link.
+pub async fn remove(pool: &SqlitePool, bug_id: Uuid, link_id: Uuid) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM bugLink WHERE id = ? AND (source_bug_id = ? OR target_bug_id = ?)")
+        .bind(link_id.as_bytes().as_slice())
+        .bind(bug_id.as_bytes().as_slice())
+        .bind(bug_id.as_bytes().as_slice())
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+// Links to and from a bug, each described from this bug's side, oldest first. Links to trashed bugs are left out.
+pub async fn for_bug(pool: &SqlitePool, bug_id: Uuid) -> Result<Vec<BugLinkView>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (Uuid, BugLinkKind, bool, Uuid, String, bool)>(
+        "SELECT l.id, l.kind, l.source_bug_id = ?1, b.id, b.title, b.is_fixed FROM bugLink l \
+         JOIN bugReport b ON b.id = CASE WHEN l.source_bug_id = ?1 THEN l.target_bug_id ELSE l.source_bug_id END \
+         WHERE (l.source_bug_id = ?1 OR l.target_bug_id = ?1) AND b.deleted_at IS NULL ORDER BY l.created_at, l.rowid"
+    )
+    .bind(bug_id.as_bytes().as_slice())
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(link_id, kind, outgoing, bug_id, title, is_fixed)| BugLinkView {
+            link_id,
+            relation: if outgoing { kind.as_str() } else { kind.inverse() }.to_string(),
+            bug_id,
+            title,
+            is_fixed,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::pool::PoolConnection;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use sqlx::Sqlite;
+    use std::path::Path;
+
+    use crate::db;
+
+    // A private in-memory database with three bugs. A single connection, so every query sees the same database.
+    async fn setup() -> (PoolConnection<Sqlite>, [Uuid; 3]) {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        db::run_migrations(&pool, Path::new("migrations")).await.unwrap();
+        let mut conn = pool.acquire().await.unwrap();
+
+        let user_id = Uuid::new_v4();
+        let project_id = Uuid::new_v4();
+        sqlx::query("INSERT INTO users (id, username, hashed_password) VALUES (?, 'tester', '')")
+            .bind(user_id.as_bytes().as_slice())
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO projectRecord (id, user_id, project_name) VALUES (?, ?, 'Links')")
+            .bind(project_id.as_bytes().as_slice())
+            .bind(user_id.as_bytes().as_slice())
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+
+        let bugs = [Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()];
+        for (index, bug_id) in bugs.iter().enumerate() {
+            sqlx::query("INSERT INTO bugReport (id, title, description, project_id, reported_by, severity) VALUES (?, ?, '', ?, ?, 'low')")
+                .bind(bug_id.as_bytes().as_slice())
+                .bind(format!("Bug {}", index))
+                .bind(project_id.as_bytes().as_slice())
+                .bind(user_id.as_bytes().as_slice())
+                .execute(&mut *conn)
+                .await
+                .unwrap();
+        }
+        (conn, bugs)
+    }
+
+    #[actix_web::test]
+    async fn blocking_cycles_are_rejected() {
+        let (mut conn, [a, b, c]) = setup().await;
+        add(&mut conn, a, b, BugLinkKind::Blocks, None).await.unwrap();
+        add(&mut conn, b, c, BugLinkKind::Blocks, None).await.unwrap();
+
+        assert!(matches!(add(&mut conn, c, a, BugLinkKind::Blocks, None).await, Err(AppError::Conflict(_))));
+        // "A blocked_by C" is "C blocks A", closing the same loop
+        assert!(matches!(add(&mut conn, a, c, BugLinkKind::BlockedBy, None).await, Err(AppError::Conflict(_))));
+        // Other kinds of links do not count towards the chain
+        add(&mut conn, c, a, BugLinkKind::CausedBy, None).await.unwrap();
+    }
+
+    #[actix_web::test]
+    async fn duplicate_cycles_and_second_duplicates_are_rejected() {
+        let (mut conn, [a, b, c]) = setup().await;
+        add(&mut conn, a, b, BugLinkKind::DuplicateOf, None).await.unwrap();
+
+        assert!(matches!(add(&mut conn, b, a, BugLinkKind::DuplicateOf, None).await, Err(AppError::Conflict(_))));
+        assert!(matches!(add(&mut conn, a, c, BugLinkKind::DuplicateOf, None).await, Err(AppError::Conflict(_))));
+        add(&mut conn, c, a, BugLinkKind::DuplicateOf, None).await.unwrap();
+    }
+
+    #[actix_web::test]
+    async fn relates_to_is_stored_once_for_both_directions() {
+        let (mut conn, [a, b, c]) = setup().await;
+        add(&mut conn, a, b, BugLinkKind::RelatesTo, None).await.unwrap();
+
+        assert!(matches!(add(&mut conn, b, a, BugLinkKind::RelatesTo, None).await, Err(AppError::Conflict(_))));
+        // relates_to may go round in circles
+        add(&mut conn, b, c, BugLinkKind::RelatesTo, None).await.unwrap();
+        add(&mut conn, c, a, BugLinkKind::RelatesTo, None).await.unwrap();
+    }
+
+    #[actix_web::test]
+    async fn a_bug_cannot_be_linked_to_itself() {
+        let (mut conn, [a, _, _]) = setup().await;
+        assert!(matches!(add(&mut conn, a, a, BugLinkKind::Blocks, None).await, Err(AppError::BadRequest(_))));
+    }
+}
@@ -3,16 +3,37 @@
 // `dotenv` is used to load environment variables from a `.env` file.
 // `std::env` is used for accessing environment variables.
 // Internal module imports include `handlers` for routing, `models` for data structures, `auth` for authentication, and `db` for database operations.
-use actix_web::{web, App, HttpServer, HttpResponse, Responder};
+use actix_web::{middleware::Condition, web, App, HttpServer};
 use dotenv::dotenv;
-use std::env;
+use config::{Config, ConfigError};
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
 // Declare internal modules used in this application.
 mod handlers; // Handles HTTP request routing and response.
 mod models;   // Defines data structures used across the application.
 mod auth;     // Handles authentication logic and utilities.
 mod db;       // Contains database initialization and interaction functions.
 mod error;    // Handles error-handling.
+mod middleware; // Request-scoped middleware such as request ids.
+mod export;   // CSV/JSON formatting for bug exports.
+mod import;   // Bug imports from CSV, JSON, GitHub and Jira files.
+mod events;   // Domain events raised after changes are saved.
+mod webhooks; // Webhook delivery queue and worker.
+mod tls;      // HTTPS for the server: certificate reload, redirect and HSTS.
+mod email;    // Email notifications over SMTP.
+mod watchers; // Users following bugs.
+mod notifications; // In-app notification inbox.
+mod mentions; // @mentions in bug descriptions.
+mod markdown; // Markdown rendering of bug descriptions.
+mod links;    // Links between bugs.
+mod similarity; // Duplicate detection for new bugs.
+mod trash;    // Purging of deleted bugs.
+mod openapi;  // OpenAPI description of the API.
+mod metrics;  // Prometheus metrics.
+mod logging;  // Log output and redaction.
+mod health;   // Liveness, readiness and version endpoints.
+mod config;   // Settings from the config file, environment and flags.
+mod shutdown; // Graceful shutdown on SIGINT/SIGTERM.
 
 pub struct AppState {
     pub projects: Arc<RwLock<Vec<models::ProjectRecord>>>,
@@ -24,9 +45,74 @@ async fn main() -> std::io::Result<()> {
     // Load environment variables from a `.env` file. This is helpful for configuration management.
     dotenv().ok();
 
+    // Settings from config.toml, the environment and the flags before the subcommand; nothing is logged before this.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (config, args) = match Config::load(&args) {
+        Ok(loaded) => loaded,
+        Err(ConfigError::Help) => {
+            println!("{}", config::usage());
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    logging::init(&config.logging);
+    if config.auth.jwt_secret == config::DEFAULT_JWT_SECRET {
+        tracing::warn!("auth.jwt_secret is the built-in default; set JWT_SECRET so tokens cannot be forged");
+    }
+
     // Initialize the database connection pool asynchronously and store it in `db_pool`.
-    let db_pool = db::init_db().await;
-    
+    let db_pool = db::init_db(&config.database).await;
+
+    // `g21_practical import <file> ...` imports bugs into the database and exits instead of serving.
+    if args.first().map(String::as_str) == Some("import") {
+        return match import::run_cli(&db_pool, &args[1..]).await {
+            Ok(report) => {
+                println!("{}", serde_json::to_string_pretty(&report).unwrap_or_default());
+                Ok(())
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "Import failed");
+                std::process::exit(1);
+            }
+        };
+    }
+    // `g21_practical make-admin <username>` gives an existing user administrator rights and exits.
+    if args.first().map(String::as_str) == Some("make-admin") {
+        let [_, username] = args.as_slice() else {
+            eprintln!("usage: g21_practical make-admin <username>");
+            std::process::exit(2);
+        };
+        match auth::make_admin(&db_pool, username).await {
+            Ok(true) => {
+                tracing::info!(username = %username, "User is now an administrator");
+                return Ok(());
+            }
+            Ok(false) => tracing::error!(username = %username, "No user with this name"),
+            Err(e) => tracing::error!(error = ?e, "Failed to make the user an administrator"),
+        }
+        std::process::exit(1);
+    }
+    if let Some(command) = args.first() {
+        eprintln!("unknown command {}\n{}", command, config::usage());
+        std::process::exit(2);
+    }
+
+    // With TLS enabled, a certificate that cannot be used stops the server before it starts listening.
+    let certificate = if config.tls.is_enabled() {
+        match tls::ServerCertificate::load(&config.tls) {
+            Ok(certificate) => Some(certificate),
+            Err(e) => {
+                tracing::error!(error = %e, "Cannot load the TLS certificate");
+                std::process::exit(2);
+            }
+        }
+    } else {
+        None
+    };
+
     let initial_projects = sqlx::query_as::<_, models::ProjectRecord>(
         "SELECT id, project_name, project_description, created_at, user_id FROM projectRecord"
     )
@@ -34,20 +120,72 @@ async fn main() -> std::io::Result<()> {
     .await
     .expect("Failed to load initial projects");
 
+    // Deliver queued webhook events and emails in the background; shutdown waits for these tasks
+    let mut background_tasks = vec![
+        webhooks::start_worker(db_pool.clone(), config.webhooks.clone()),
+        notifications::start_cleanup(db_pool.clone(), config.retention.notification_days),
+        trash::start_purge(db_pool.clone(), config.retention.trash_days),
+    ];
+    background_tasks.extend(email::start_worker(db_pool.clone(), config.email.clone()));
+
      let app_state = web::Data::new(AppState {
         projects: Arc::new(RwLock::new(initial_projects)),
     });
+    let bind_address = (config.server.host.clone(), config.server.port);
+    let redirect_address = config.tls.redirect_port.map(|port| (config.server.host.clone(), port));
+    let shutdown_timeout = Duration::from_secs(config.server.shutdown_timeout_secs);
+    let config = web::Data::new(config);
+    let pool = db_pool.clone();
 
     // Configure and run the HTTP server.
-    HttpServer::new(move || {
+    let server = HttpServer::new(move || {
         App::new()
             // Share the database pool across different parts of the application using application data.
-            .app_data(web::Data::new(db_pool.clone()))
+            .app_data(web::Data::new(pool.clone()))
+            // Share the cached project list with handlers.
+            .app_data(app_state.clone())
+            // Share the settings, e.g. the JWT secret, with handlers and middleware.
+            .app_data(config.clone())
+            // Report malformed JSON bodies, query strings and path segments with the shared error body.
+            // JSON bodies are capped well above the largest valid request (a 10000 character description).
+            .app_data(web::JsonConfig::default().limit(256 * 1024).error_handler(error::json_error_handler))
+            .app_data(web::QueryConfig::default().error_handler(error::query_error_handler))
+            .app_data(web::PathConfig::default().error_handler(error::path_error_handler))
             // Configure application routes using the `config` function from the `handlers` module.
             .configure(handlers::config)
+            // With TLS: HSTS over HTTPS, and a redirect to HTTPS for requests to the redirect listener.
+            .wrap(Condition::new(config.tls.is_enabled(), tls::HttpsMiddleware::new(&config)))
+            // Count and time every request for /metrics.
+            .wrap(metrics::MetricsMiddleware)
+            // Log every request once it has been answered.
+            .wrap(middleware::AccessLogMiddleware)
+            // Give every request an id, echoed in `X-Request-Id`, in error bodies and in the logs.
+            .wrap(middleware::RequestIdMiddleware)
     })
-    // Bind the server to listen on the local machine at port 8080.
-    .bind(("127.0.0.1", 8080))?
-    .run() // Start the server.
-    .await // Await the completion of the server run (this runs indefinitely until shutdown).
+    // Signals are handled by the shutdown module, which also stops the background tasks.
+    .disable_signals()
+    .shutdown_timeout(shutdown_timeout.as_secs());
+
+    // Bind the server to the configured address, 127.0.0.1:8080 by default, over HTTPS when TLS is enabled.
+    let server = match certificate {
+        Some(certificate) => {
+            let server = server.bind_rustls_021(bind_address, certificate.server_config())?;
+            tls::reload_on_hangup(certificate);
+            match redirect_address {
+                Some(address) => server.bind(address)?,
+                None => server,
+            }
+        }
+        None => server.bind(bind_address)?,
+    }
+    .run(); // Start the server.
+
+    shutdown::listen(server.handle(), shutdown_timeout);
+    // Runs until the server is stopped, then waits for the background tasks and closes the pool.
+    server.await?;
+    if !shutdown::finish(&db_pool, background_tasks, shutdown_timeout).await {
+        // Report a shutdown that was cut short to whatever supervises the process
+        std::process::exit(1);
+    }
+    Ok(())
 }
\ No newline at end of file
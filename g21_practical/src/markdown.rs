@@ -0,0 +1,75 @@
+// Bug descriptions are CommonMark. `to_html` renders a description to HTML and sanitises the result with ammonia,
+// so raw HTML, `javascript:` links or event handler attributes in a description cannot run scripts in the pages
+// that show it. Templates use the same rendering through the `markdown` Tera filter.
+use ammonia::Builder;
+use pulldown_cmark::{html, Options, Parser};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use tera::{Tera, Value};
+
+fn sanitizer() -> &'static Builder<'static> {
+    static SANITIZER: OnceLock<Builder<'static>> = OnceLock::new();
+    SANITIZER.get_or_init(|| {
+        let mut builder = Builder::default();
+        // Keep the "language-rust" class of fenced code blocks for syntax highlighters
+        builder.add_tag_attributes("code", &["class"]);
+        builder
+    })
+}
+
+pub fn to_html(markdown: &str) -> String {
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let mut rendered = String::new();
+    html::push_html(&mut rendered, Parser::new_ext(markdown, options));
+    sanitizer().clean(&rendered).to_string()
+}
+
+// Adds `{{ text | markdown | safe }}` to the templates.
+pub fn register_filter(tera: &mut Tera) {
+    tera.register_filter("markdown", |value: &Value, _: &HashMap<String, Value>| {
+        let text = value.as_str().ok_or_else(|| tera::Error::msg("markdown filter expects a string"))?;
+        Ok(Value::String(to_html(text)))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markdown_is_rendered() {
+        assert_eq!(to_html("**Crash** on `save`"), "<p><strong>Crash</strong> on <code>save</code></p>\n");
+        assert!(to_html("```rust\nfn main() {}\n```").contains("<code class=\"language-rust\">"));
+    }
+
+    #[test]
+    fn script_tags_are_dropped() {
+        let html = to_html("Before <script>alert(1)</script> after");
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("alert(1)"));
+        assert!(html.contains("Before"));
+    }
+
+    #[test]
+    fn javascript_links_are_dropped() {
+        let html = to_html("[click](javascript:alert(1)) and <a href=\"javascript:alert(2)\">raw</a>");
+        assert!(!html.contains("javascript:"));
+        assert!(html.contains("click") && html.contains("raw"));
+    }
+
+    #[test]
+    fn event_handlers_are_dropped() {
+        let html = to_html("<img src=\"x.png\" onerror=\"alert(1)\">");
+        assert!(!html.contains("onerror"));
+    }
+
+    #[test]
+    fn filter_renders_template_values() {
+        let mut tera = Tera::default();
+        register_filter(&mut tera);
+        tera.add_raw_template("t", "{{ text | markdown | safe }}").unwrap();
+        let mut context = tera::Context::new();
+        context.insert("text", "*hi* <script>x</script>");
+        assert_eq!(tera.render("t", &context).unwrap(), "<p><em>hi</em> </p>\n");
+    }
+}
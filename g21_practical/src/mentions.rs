@@ -0,0 +1,108 @@
+// @mentions in bug descriptions: writing `@normal_user` pings that user. Mentions are parsed when a bug is
+// created, imported or its description changes, and matched against the users table; anything else, such as
+// `@Override` in a stack trace, stays plain text. The matches are stored in `bugMention`.
+use sqlx::{SqliteConnection, SqlitePool};
+use uuid::Uuid;
+
+use crate::models::SimpleUser;
+
+fn is_username_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')
+}
+
+// Usernames mentioned in the text, each once, in order of first appearance. An `@` only starts a mention at the
+// start of the text or after a character that cannot be part of a word, so email addresses are not mentions.
+pub fn parse(text: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut previous = None;
+    for (index, c) in text.char_indices() {
+        if c == '@' && !previous.is_some_and(|p: char| p.is_alphanumeric() || p == '_') {
+            let rest = &text[index + 1..];
+            let end = rest.find(|c: char| !is_username_char(c)).unwrap_or(rest.len());
+            // A mention at the end of a sentence should not include the full stop
+            let name = rest[..end].trim_end_matches(['.', '-']);
+            if !name.is_empty() && !names.iter().any(|n| n == name) {
+                names.push(name.to_string());
+            }
+        }
+        previous = Some(c);
+    }
+    names
+}
+
+// Replaces the stored mentions of a bug with the users mentioned in its description.
+// Returns the users that were not mentioned before, who are the ones to notify.
+pub async fn sync(conn: &mut SqliteConnection, bug_id: Uuid, description: &str) -> Result<Vec<Uuid>, sqlx::Error> {
+    let mut mentioned = Vec::new();
+    for name in parse(description) {
+        if let Some(user_id) = sqlx::query_scalar::<_, Uuid>("SELECT id FROM users WHERE username = ?")
+            .bind(&name)
+            .fetch_optional(&mut *conn)
+            .await?
+        {
+            mentioned.push(user_id);
+        }
+    }
+
+    let existing = sqlx::query_scalar::<_, Uuid>("SELECT user_id FROM bugMention WHERE bug_id = ?")
+        .bind(bug_id.as_bytes().as_slice())
+        .fetch_all(&mut *conn)
+        .await?;
+
+    for user_id in existing.iter().filter(|id| !mentioned.contains(id)) {
+        sqlx::query("DELETE FROM bugMention WHERE bug_id = ? AND user_id = ?")
+            .bind(bug_id.as_bytes().as_slice())
+            .bind(user_id.as_bytes().as_slice())
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    let added: Vec<Uuid> = mentioned.into_iter().filter(|id| !existing.contains(id)).collect();
+    for user_id in &added {
+        sqlx::query("INSERT INTO bugMention (bug_id, user_id) VALUES (?, ?)")
+            .bind(bug_id.as_bytes().as_slice())
+            .bind(user_id.as_bytes().as_slice())
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(added)
+}
+
+// Users mentioned in a bug, in alphabetical order.
+pub async fn mentioned_users(pool: &SqlitePool, bug_id: Uuid) -> Result<Vec<SimpleUser>, sqlx::Error> {
+    sqlx::query_as::<_, SimpleUser>(
+        "SELECT u.id, u.username FROM bugMention m JOIN users u ON u.id = m.user_id WHERE m.bug_id = ? ORDER BY u.username"
+    )
+    .bind(bug_id.as_bytes().as_slice())
+    .fetch_all(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mentions_are_listed_once_in_order() {
+        assert_eq!(parse("@admin please ask @normal_user, then @admin again"), vec!["admin", "normal_user"]);
+    }
+
+    #[test]
+    fn trailing_punctuation_is_not_part_of_the_name() {
+        assert_eq!(parse("Thanks @normal_user."), vec!["normal_user"]);
+        assert_eq!(parse("(cc @j.doe-)"), vec!["j.doe"]);
+        assert_eq!(parse("@first.last:"), vec!["first.last"]);
+    }
+
+    #[test]
+    fn email_addresses_and_bare_at_signs_are_not_mentions() {
+        assert!(parse("mail admin@example.com or write @ me").is_empty());
+        assert!(parse("foo_@bar").is_empty());
+    }
+
+    #[test]
+    fn mentions_may_follow_non_ascii_punctuation() {
+        assert_eq!(parse("«@admin»"), vec!["admin"]);
+        assert!(parse("é@admin").is_empty());
+    }
+}
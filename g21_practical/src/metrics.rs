@@ -0,0 +1,313 @@
+// Prometheus metrics, served in the text exposition format at GET /metrics. Request counts and latencies are
+// recorded by `MetricsMiddleware` per route pattern, method and status; error codes, logins and the database pool
+// are counted where they happen, and the bug gauges are read from the database when /metrics is scraped.
+use actix_web::{
+    body::MessageBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    web, Error, HttpResponse,
+};
+use futures_util::future::LocalBoxFuture;
+use prometheus::{Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder};
+use sqlx::SqlitePool;
+use std::future::{ready, Ready};
+use std::rc::Rc;
+use std::sync::LazyLock;
+use std::time::Instant;
+use tracing::error;
+
+use crate::error::AppError;
+
+// Route label of requests that matched no route, so that scanners probing random paths cannot create new series.
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+const SEVERITIES: [&str; 4] = ["low", "medium", "high", "critical"];
+
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    http_requests_in_flight: IntGauge,
+    app_errors: IntCounterVec,
+    logins: IntCounterVec,
+    db_pool_connections: IntGaugeVec,
+    db_pool_max_connections: IntGauge,
+    open_bugs: IntGaugeVec,
+    trashed_bugs: IntGauge,
+}
+
+// The process-wide registry. A global rather than `web::Data` so that the middleware and the login handler can count
+// without being handed the registry.
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests by route, method and status"),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "Time taken to answer HTTP requests"),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+        let http_requests_in_flight =
+            IntGauge::new("http_requests_in_flight", "HTTP requests being handled right now").unwrap();
+        let app_errors = IntCounterVec::new(
+            Opts::new("app_errors_total", "Error responses by AppError code, e.g. not_found or database_error"),
+            &["code"],
+        )
+        .unwrap();
+        let logins = IntCounterVec::new(Opts::new("logins_total", "Login attempts by result"), &["result"]).unwrap();
+        let db_pool_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Open database connections, idle or in use"),
+            &["state"],
+        )
+        .unwrap();
+        let db_pool_max_connections =
+            IntGauge::new("db_pool_max_connections", "Maximum number of connections in the database pool").unwrap();
+        let open_bugs =
+            IntGaugeVec::new(Opts::new("bugs_open", "Open bugs (not fixed, not in the trash) by severity"), &["severity"])
+                .unwrap();
+        let trashed_bugs = IntGauge::new("bugs_trashed", "Bugs in the trash waiting to be purged").unwrap();
+
+        registry.register(Box::new(http_requests.clone())).unwrap();
+        registry.register(Box::new(http_request_duration.clone())).unwrap();
+        registry.register(Box::new(http_requests_in_flight.clone())).unwrap();
+        registry.register(Box::new(app_errors.clone())).unwrap();
+        registry.register(Box::new(logins.clone())).unwrap();
+        registry.register(Box::new(db_pool_connections.clone())).unwrap();
+        registry.register(Box::new(db_pool_max_connections.clone())).unwrap();
+        registry.register(Box::new(open_bugs.clone())).unwrap();
+        registry.register(Box::new(trashed_bugs.clone())).unwrap();
+
+        // Both results are exported from the start, so rate() works before the first failed login
+        for result in ["success", "failure"] {
+            logins.with_label_values(&[result]);
+        }
+
+        Metrics {
+            registry,
+            http_requests,
+            http_request_duration,
+            http_requests_in_flight,
+            app_errors,
+            logins,
+            db_pool_connections,
+            db_pool_max_connections,
+            open_bugs,
+            trashed_bugs,
+        }
+    }
+}
+
+// Counts a login attempt.
+pub fn record_login(success: bool) {
+    METRICS.logins.with_label_values(&[if success { "success" } else { "failure" }]).inc();
+}
+
+// Requests being handled, also reported when the server shuts down.
+pub fn requests_in_flight() -> i64 {
+    METRICS.http_requests_in_flight.get()
+}
+
+// Counts a request as in flight until dropped, which also covers requests whose handling is cancelled.
+struct InFlight;
+
+impl InFlight {
+    fn start() -> Self {
+        METRICS.http_requests_in_flight.inc();
+        InFlight
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        METRICS.http_requests_in_flight.dec();
+    }
+}
+
+fn record_request(method: &str, route: &str, status: u16, started: Instant) {
+    let status = status.to_string();
+    let labels = [method, route, status.as_str()];
+    METRICS.http_requests.with_label_values(&labels).inc();
+    METRICS.http_request_duration.with_label_values(&labels).observe(started.elapsed().as_secs_f64());
+}
+
+fn record_error(error: &Error) {
+    if let Some(app_error) = error.as_error::<AppError>() {
+        METRICS.app_errors.with_label_values(&[app_error.code()]).inc();
+    }
+}
+
+// Reads the pool usage and the bug counts into their gauges.
+async fn refresh_gauges(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let size = i64::from(pool.size());
+    let idle = pool.num_idle() as i64;
+    METRICS.db_pool_connections.with_label_values(&["idle"]).set(idle);
+    METRICS.db_pool_connections.with_label_values(&["active"]).set(size - idle);
+    METRICS.db_pool_max_connections.set(i64::from(pool.options().get_max_connections()));
+
+    let open = sqlx::query_as::<_, (String, i64)>(
+        "SELECT severity, COUNT(*) FROM bugReport WHERE is_fixed = FALSE AND deleted_at IS NULL GROUP BY severity"
+    )
+    .fetch_all(pool)
+    .await?;
+    for severity in SEVERITIES {
+        let count = open.iter().find(|(s, _)| s == severity).map_or(0, |(_, count)| *count);
+        METRICS.open_bugs.with_label_values(&[severity]).set(count);
+    }
+
+    let trashed: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM bugReport WHERE deleted_at IS NOT NULL")
+        .fetch_one(pool)
+        .await?;
+    METRICS.trashed_bugs.set(trashed);
+    Ok(())
+}
+
+// Asynchronous function returning every metric in the Prometheus text format.
+pub async fn metrics(pool: web::Data<SqlitePool>) -> Result<HttpResponse, AppError> {
+    refresh_gauges(pool.get_ref()).await.map_err(|e| {
+        error!(error = ?e, "Error reading metrics from the database");
+        AppError::Database(e)
+    })?;
+
+    let encoder = TextEncoder::new();
+    let mut buffer = Vec::new();
+    encoder
+        .encode(&METRICS.registry.gather(), &mut buffer)
+        .map_err(|e| AppError::Internal(format!("Failed to encode metrics: {}", e)))?;
+    Ok(HttpResponse::Ok().content_type(encoder.format_type()).body(buffer))
+}
+
+// Middleware timing every request and counting it under the route pattern it matched (e.g. /api/v1/bugs/{id}),
+// together with the AppError code of error responses.
+pub struct MetricsMiddleware;
+
+impl<S, B> Transform<S, ServiceRequest> for MetricsMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = MetricsService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(MetricsService {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct MetricsService<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for MetricsService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let started = Instant::now();
+        let method = req.method().to_string();
+        let route = req.match_pattern().unwrap_or_else(|| UNMATCHED_ROUTE.to_string());
+        let in_flight = InFlight::start();
+
+        Box::pin(async move {
+            let _in_flight = in_flight;
+            // Errors from middleware (e.g. a missing token) come back as Err and are only turned into a response
+            // further out, so their status is taken from the error
+            match service.call(req).await {
+                Ok(res) => {
+                    if let Some(error) = res.response().error() {
+                        record_error(error);
+                    }
+                    record_request(&method, &route, res.status().as_u16(), started);
+                    Ok(res)
+                }
+                Err(error) => {
+                    record_error(&error);
+                    record_request(&method, &route, error.as_response_error().status_code().as_u16(), started);
+                    Err(error)
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::{call_service, init_service, TestRequest};
+    use actix_web::App;
+
+    fn requests(method: &str, route: &str, status: &str) -> u64 {
+        METRICS.http_requests.with_label_values(&[method, route, status]).get()
+    }
+
+    fn app_errors(code: &str) -> u64 {
+        METRICS.app_errors.with_label_values(&[code]).get()
+    }
+
+    #[actix_web::test]
+    async fn requests_are_counted_by_route_pattern_and_errors_by_code() {
+        let app = init_service(
+            App::new()
+                .service(
+                    web::scope("/api/v1")
+                        .route("/bugs/{id}", web::put().to(|| async { Err::<HttpResponse, _>(AppError::Conflict("taken".into())) }))
+                        .route("/bugs/{id}", web::patch().to(HttpResponse::Ok)),
+                )
+                .service(
+                    web::resource("/me")
+                        .wrap_fn(|_, _| async { Err::<ServiceResponse, Error>(AppError::Unauthorized("no token".into()).into()) })
+                        .route(web::put().to(HttpResponse::Ok)),
+                )
+                .wrap(MetricsMiddleware),
+        )
+        .await;
+        let before = (
+            requests("PATCH", "/api/v1/bugs/{id}", "200"),
+            requests("PUT", "/api/v1/bugs/{id}", "409"),
+            requests("PATCH", UNMATCHED_ROUTE, "404"),
+            requests("PUT", "/me", "401"),
+            app_errors("conflict"),
+            app_errors("unauthorized"),
+        );
+
+        for uri in ["/api/v1/bugs/1", "/api/v1/bugs/2", "/wp-login.php"] {
+            call_service(&app, TestRequest::patch().uri(uri).to_request()).await;
+        }
+        assert_eq!(call_service(&app, TestRequest::put().uri("/api/v1/bugs/1").to_request()).await.status(), 409);
+        // Errors returned by middleware are counted too
+        let res = app.call(TestRequest::put().uri("/me").to_request()).await;
+        assert_eq!(res.err().unwrap().as_response_error().status_code(), 401);
+
+        let after = (
+            requests("PATCH", "/api/v1/bugs/{id}", "200"),
+            requests("PUT", "/api/v1/bugs/{id}", "409"),
+            requests("PATCH", UNMATCHED_ROUTE, "404"),
+            requests("PUT", "/me", "401"),
+            app_errors("conflict"),
+            app_errors("unauthorized"),
+        );
+        assert_eq!(after, (before.0 + 2, before.1 + 1, before.2 + 1, before.3 + 1, before.4 + 1, before.5 + 1));
+        assert_eq!(requests("PATCH", "/api/v1/bugs/1", "200"), 0);
+        assert_eq!(requests_in_flight(), 0);
+    }
+}
@@ -0,0 +1,164 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{self, HeaderName, HeaderValue},
+    Error,
+};
+use futures_util::future::LocalBoxFuture;
+use std::future::{ready, Ready};
+use std::rc::Rc;
+use std::time::Instant;
+use tracing::{info, info_span, Instrument};
+use uuid::Uuid;
+
+use crate::logging;
+
+// Header used to pass a request id in from a proxy and to return it to the client.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+// Request id of the request currently being handled, kept as a task-local so that
+// `AppError::error_response` (which has no access to the request) can include it.
+tokio::task_local! {
+    static CURRENT_REQUEST_ID: String;
+}
+
+// Returns the id of the request being handled on this task, if any.
+pub fn current_request_id() -> Option<String> {
+    CURRENT_REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+// Middleware assigning every request an id (reusing a sane incoming `X-Request-Id`)
+// and echoing it in the response headers. The request is handled inside a `request` span
+// carrying the id, so every event logged on its behalf can be traced back to it.
+pub struct RequestIdMiddleware;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestIdMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequestIdService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestIdService {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct RequestIdService<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestIdService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+
+        let request_id = req
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| !value.is_empty() && value.len() <= 64 && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+            .map(str::to_string)
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+        let span = info_span!("request", request_id = %request_id, method = %req.method(), path = %req.path());
+
+        Box::pin(CURRENT_REQUEST_ID.scope(request_id.clone(), async move {
+            let mut res = service.call(req).await?;
+
+            if let Ok(value) = HeaderValue::from_str(&request_id) {
+                res.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+            }
+            Ok(res)
+        }.instrument(span)))
+    }
+}
+
+// Middleware logging one `access` event per request once it has been answered: status, time taken,
+// client address and user agent. Query strings are redacted, as `/events` takes the JWT in one.
+// Wrapped inside `RequestIdMiddleware` so the event carries the request id.
+pub struct AccessLogMiddleware;
+
+impl<S, B> Transform<S, ServiceRequest> for AccessLogMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = AccessLogService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AccessLogService {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct AccessLogService<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for AccessLogService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let started = Instant::now();
+        let uri = logging::redact(&req.uri().to_string());
+        let peer = req.connection_info().realip_remote_addr().unwrap_or("-").to_string();
+        let user_agent = req
+            .headers()
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("-")
+            .to_string();
+
+        Box::pin(async move {
+            let result = service.call(req).await;
+            let status = match &result {
+                Ok(res) => res.status(),
+                Err(error) => error.as_response_error().status_code(),
+            };
+            info!(
+                target: "access",
+                status = status.as_u16(),
+                elapsed_ms = started.elapsed().as_secs_f64() * 1000.0,
+                uri = %uri,
+                peer = %peer,
+                user_agent = %user_agent,
+                "Request answered"
+            );
+            result
+        })
+    }
+}
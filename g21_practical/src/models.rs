@@ -1,9 +1,23 @@
 // Import necessary traits from the `serde` crate to enable serialization and deserialization of data structures.
 // `serde` is commonly used for converting data structures to and from formats like JSON.
 // Import `uuid` for generating and handling universally unique identifiers (UUIDs).
-use serde::{Deserialize, Serialize};
+// Import `validator` to declare length and format rules on the request structs.
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use uuid::Uuid;
 use sqlx::FromRow;
+use std::fmt;
+use std::str::FromStr;
+use utoipa::{IntoParams, ToSchema};
+use validator::{Validate, ValidationError};
+
+use crate::events::EventKind;
+use crate::markdown;
+
+// Upper bounds on free-text fields accepted from clients.
+pub const MAX_TITLE_LEN: u64 = 200;
+pub const MAX_DESCRIPTION_LEN: u64 = 10_000;
+pub const MAX_NAME_LEN: u64 = 100;
+pub const MAX_BULK_ITEMS: u64 = 500;
 
 // Define the `User` struct to represent a user in the application.
 // This struct derives `Serialize` and `Deserialize` so it can be easily converted to and from JSON and other data formats.
@@ -16,13 +30,13 @@ pub struct User {
 }
 
 // Struct for incoming login requests
-#[derive(Serialize, Deserialize, Debug, FromRow)]
+#[derive(Serialize, Deserialize, Debug, FromRow, ToSchema)]
 pub struct LoginRequest {
     pub username: String, // Username of the user
     pub password: String, // Pasword of the user that key it in
 }
 
-#[derive(Serialize, Deserialize, Debug, FromRow)]
+#[derive(Serialize, Deserialize, Debug, FromRow, ToSchema)]
 pub struct LoginResponse {
     pub status: String,
     pub message: String,
@@ -30,7 +44,7 @@ pub struct LoginResponse {
 }
 
 // Define the `BugReport` struct to represent a bug report.
-#[derive(Serialize, Deserialize, Debug, FromRow)]
+#[derive(Serialize, Deserialize, Debug, FromRow, ToSchema)]
 pub struct BugReport {
     pub id: Uuid,                 // Unique identifier of the bug.
     pub project_id: Uuid, // The ID of the project that the bug belongs to.
@@ -38,22 +52,179 @@ pub struct BugReport {
     pub description: String,   // The description of the bug
     pub reported_by: Uuid,       // The ID of the user that reported the bug.
     pub fixed_by: Option<Uuid>,       // The ID of the user that fixed the bug.
-    pub severity: String,    // The severity of the bug
+    pub severity: Severity,    // The severity of the bug
+    pub priority: Priority,    // How urgently the bug should be worked on
     pub is_fixed: bool, // Indicates whether the bug has been fixed or not.
     pub created_at: String, // Timestamp of when the bug was created
+    pub version: i64, // Incremented on every update, used as the ETag for optimistic concurrency
+    // The description rendered from Markdown to sanitised HTML; only set in responses of the /bugs endpoints
+    #[sqlx(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description_html: Option<String>,
+}
+
+impl BugReport {
+    pub fn with_description_html(mut self) -> Self {
+        self.description_html = Some(markdown::to_html(&self.description));
+        self
+    }
+}
+
+// A bug report together with its related data, as returned by `GET /bugs/{id}`.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct BugDetails {
+    #[serde(flatten)]
+    pub bug: BugReport,
+    pub labels: Vec<String>, // Labels attached to the bug, in alphabetical order
+    pub watchers: Vec<SimpleUser>, // Users following the bug, in alphabetical order
+    pub mentions: Vec<SimpleUser>, // Users @mentioned in the description, in alphabetical order
+    pub links: Vec<BugLinkView>, // Links to and from other bugs
+}
+
+// A bug in the trash, as listed by `GET /bugs/trash`.
+#[derive(Serialize, Debug, FromRow, ToSchema)]
+pub struct TrashedBug {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub bug: BugReport,
+    pub deleted_at: String,
+    pub deleted_by: Option<String>, // Username of the user who deleted the bug
+    pub purge_at: String,           // When the purge job will delete the bug for good
+}
+
+// A bug the authenticated user watches, as listed by `GET /me/watching`.
+#[derive(Serialize, Debug, FromRow, ToSchema)]
+pub struct WatchedBug {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub bug: BugReport,
+    pub watching_since: String,
+}
+
+// Define the `Severity` enum to represent how much damage a bug does.
+// Variants are declared from least to most severe so the derived `Ord` ranks critical > high > medium > low.
+// Values are stored in SQLite as lowercase TEXT and accepted from clients case-insensitively ("High" == "high").
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase", try_from = "String")]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "low" => Ok(Severity::Low),
+            "medium" => Ok(Severity::Medium),
+            "high" => Ok(Severity::High),
+            "critical" => Ok(Severity::Critical),
+            _ => Err(format!("invalid severity '{}', expected one of: low, medium, high, critical", s)),
+        }
+    }
+}
+
+impl TryFrom<String> for Severity {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// Define the `Priority` enum to represent how soon a bug should be picked up.
+// Like `Severity`, variants are ordered so that urgent > high > medium > low.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase", try_from = "String")]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Medium,
+    High,
+    Urgent,
+}
+
+impl Priority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+            Priority::Urgent => "urgent",
+        }
+    }
+}
+
+impl FromStr for Priority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "low" => Ok(Priority::Low),
+            "medium" => Ok(Priority::Medium),
+            "high" => Ok(Priority::High),
+            "urgent" => Ok(Priority::Urgent),
+            _ => Err(format!("invalid priority '{}', expected one of: low, medium, high, urgent", s)),
+        }
+    }
+}
+
+impl TryFrom<String> for Priority {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 // Define the 'CreateBug' struct to represent the creation of a bug
-#[derive(Serialize, Deserialize, Debug, FromRow)]
+// Text fields are trimmed while deserializing, so the length rules apply to the trimmed value.
+#[derive(Serialize, Deserialize, Debug, Validate, ToSchema)]
 pub struct CreateBug {
+    #[serde(deserialize_with = "trimmed")]
+    #[validate(length(min = 1, max = "MAX_TITLE_LEN", message = "title must be between 1 and 200 characters"))]
     pub title: String,    // The title of the bug
+    #[serde(deserialize_with = "trimmed")]
+    #[validate(length(min = 1, max = "MAX_DESCRIPTION_LEN", message = "description must be between 1 and 10000 characters"))]
     pub description: String, // The description of the bug
-    pub severity: String, // The severity of the bug
+    pub severity: Severity, // The severity of the bug
+    #[serde(default)]
+    pub priority: Priority, // The priority of the bug, defaults to medium
+    #[serde(deserialize_with = "trimmed")]
+    #[validate(length(min = 1, max = "MAX_NAME_LEN", message = "project_name must be between 1 and 100 characters"))]
     pub project_name: String, // The name of the project that the bug belongs to
 }
 
 
-#[derive(Serialize, Deserialize, Debug, FromRow)]
+#[derive(Serialize, Deserialize, Debug, FromRow, ToSchema)]
 pub struct ProjectRecord {
     pub id: Uuid,
     pub project_name: String,
@@ -62,44 +233,675 @@ pub struct ProjectRecord {
     pub user_id: Uuid, // The ID of the user that created the project
 }
 
-#[derive(Serialize, Deserialize, Debug, FromRow)]
+#[derive(Serialize, Deserialize, Debug, Validate, ToSchema)]
 pub struct CreateProject {
+    #[serde(deserialize_with = "trimmed")]
+    #[validate(length(min = 1, max = "MAX_NAME_LEN", message = "username must be between 1 and 100 characters"))]
     pub username: String,
+    #[serde(deserialize_with = "trimmed")]
+    #[validate(length(min = 1, max = "MAX_NAME_LEN", message = "project_title must be between 1 and 100 characters"))]
     pub project_title: String,
+    #[serde(deserialize_with = "trimmed")]
+    #[validate(length(max = "MAX_DESCRIPTION_LEN", message = "project_description must be at most 10000 characters"))]
     pub project_description: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, FromRow)]
+#[derive(Serialize, Deserialize, Debug, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct BugFilter {
     pub is_fixed: Option<bool>,     
-    pub severity: Option<String>,
+    pub severity: Option<Severity>,
+    pub priority: Option<Priority>,
     pub project_name: Option<String>,  
+    pub label: Option<String>, // Only bugs carrying this label
+    pub mentioned: Option<String>, // Only bugs whose description @mentions this username, or "me" for the authenticated user
+    pub sort: Option<BugSort>, // Optional ordering of the results
 }
 
-#[derive(Serialize, Deserialize, Debug, FromRow)]
+impl BugFilter {
+    // Whether the filter narrows the bugs down at all; `sort` only orders them.
+    pub fn has_conditions(&self) -> bool {
+        self.is_fixed.is_some()
+            || self.severity.is_some()
+            || self.priority.is_some()
+            || self.project_name.is_some()
+            || self.label.is_some()
+            || self.mentioned.is_some()
+    }
+}
+
+// Orderings supported by `GET /bugs?sort=...`.
+// `severity` and `priority` list the most severe/urgent bugs first, `created_at` lists the newest first.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BugSort {
+    Severity,
+    Priority,
+    CreatedAt,
+}
+
+#[derive(Serialize, Deserialize, Debug, Validate, ToSchema)]
 pub struct UpdateBugReport {
     pub is_fixed: Option<bool>,     
-    pub severity: Option<String>,
+    pub severity: Option<Severity>,
+    pub priority: Option<Priority>,
+    #[serde(default, deserialize_with = "trimmed_opt")]
+    #[validate(length(min = 1, max = "MAX_DESCRIPTION_LEN", message = "description must be between 1 and 10000 characters"))]
     pub description: Option<String>,
+    #[serde(default, deserialize_with = "trimmed_opt")]
+    #[validate(length(min = 1, max = "MAX_NAME_LEN", message = "fixed_by must be between 1 and 100 characters"))]
     pub fixed_by: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct BugAssignment { 
-    pub bug_id: Uuid, // The ID of the bug being assigned
-    pub user_id: Uuid, // The ID of the user to whom the bug is assigned
+// Output formats supported by `GET /bugs/export`.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+    Ndjson,
+}
+
+// Export-specific query parameters of `GET /bugs/export`; the filter fields are read into a `BugFilter`.
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportQuery {
+    #[serde(default)]
+    pub format: ExportFormat,
+    pub columns: Option<String>, // Comma-separated column list, defaults to every column
+}
+
+// A bug report with ids resolved to names, as written by `GET /bugs/export`.
+#[derive(Debug, FromRow)]
+pub struct BugExportRow {
+    pub id: Uuid,
+    pub title: String,
+    pub description: String,
+    pub project_name: Option<String>,
+    pub severity: Severity,
+    pub priority: Priority,
+    pub is_fixed: bool,
+    pub reported_by: Option<String>, // Username of the reporter
+    pub fixed_by: Option<String>,    // Username of the assignee
+    pub labels: Option<String>,      // Comma-separated labels
+    pub created_at: String,
+    pub version: i64,
+}
+
+// Source formats accepted by `POST /bugs/import` and the `import` CLI command.
+// `github` and `jira` read the JSON export files of those trackers.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    Csv,
+    Json,
+    Github,
+    Jira,
+}
+
+impl FromStr for ImportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "csv" => Ok(ImportFormat::Csv),
+            "json" => Ok(ImportFormat::Json),
+            "github" => Ok(ImportFormat::Github),
+            "jira" => Ok(ImportFormat::Jira),
+            _ => Err(format!("invalid import format '{}', expected one of: csv, json, github, jira", s)),
+        }
+    }
+}
+
+// Query parameters of `POST /bugs/import`; the file itself is the request body.
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ImportQuery {
+    pub format: ImportFormat,
+    #[serde(default)]
+    pub dry_run: bool, // Validate and report without saving anything
+    pub project: Option<String>, // Project for rows that do not name one
+    pub mapping: Option<String>, // Column mapping, e.g. "title:Summary,description:Details"
+}
+
+// Whether a row of an import was imported.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportOutcome {
+    Imported,
+    Error,
+}
+
+// Outcome of importing a single row.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct ImportRowResult {
+    pub row: usize, // 1-based row (or array element) number in the source file
+    pub title: Option<String>,
+    pub outcome: ImportOutcome,
+    pub bug_id: Option<Uuid>,
+    pub errors: Vec<String>,
+}
+
+// Report returned by an import, listing every row.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub total: usize,
+    pub imported: usize,
+    pub failed: usize,
+    pub rows: Vec<ImportRowResult>,
+}
+
+// Request body for `POST /bugs/bulk`: apply one set of changes to many bugs in a single transaction.
+// Bugs are selected either by `bug_ids` or by `filter` (the same fields as `GET /bugs`), never both.
+#[derive(Deserialize, Debug, Validate, ToSchema)]
+#[validate(schema(function = "validate_bulk_target"))]
+pub struct BulkBugRequest {
+    #[validate(length(min = 1, max = "MAX_BULK_ITEMS", message = "bug_ids must contain between 1 and 500 ids"))]
+    pub bug_ids: Option<Vec<String>>,
+    pub filter: Option<BugFilter>,
+    #[validate(nested)]
+    pub changes: BulkBugChanges,
+    #[serde(default)]
+    pub dry_run: bool, // Report what would happen and roll everything back
+}
+
+// The changes applied to every selected bug. `delete` cannot be combined with other changes.
+#[derive(Deserialize, Debug, Validate, ToSchema)]
+#[validate(schema(function = "validate_bulk_changes"))]
+pub struct BulkBugChanges {
+    pub is_fixed: Option<bool>,
+    pub severity: Option<Severity>,
+    pub priority: Option<Priority>,
+    #[serde(default, deserialize_with = "trimmed_opt")]
+    #[validate(length(min = 1, max = "MAX_NAME_LEN", message = "fixed_by must be between 1 and 100 characters"))]
+    pub fixed_by: Option<String>, // Username of the assignee
+    #[serde(default)]
+    #[validate(custom(function = "validate_labels"))]
+    pub add_labels: Vec<String>,
+    #[serde(default)]
+    #[validate(custom(function = "validate_labels"))]
+    pub remove_labels: Vec<String>,
+    #[serde(default)]
+    pub delete: bool,
+}
+
+// What a bulk operation did to a single bug.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkOutcome {
+    Updated,
+    Deleted,
+    NotFound,
+    InvalidId,
+}
+
+// Outcome for a single bug of a bulk operation.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct BulkItemResult {
+    pub bug_id: String,
+    pub outcome: BulkOutcome,
+    pub error: Option<String>,
+}
+
+// Response body of `POST /bugs/bulk`.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct BulkBugResponse {
+    pub dry_run: bool,
+    pub matched: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<BulkItemResult>,
+}
+
+// Request body for `POST /webhooks`: subscribe a URL to events of one project.
+// An empty `events` list (or "*") subscribes to every event; a secret is generated when none is given.
+#[derive(Deserialize, Debug, Validate, ToSchema)]
+pub struct CreateWebhook {
+    #[serde(deserialize_with = "trimmed")]
+    #[validate(length(min = 1, max = "MAX_NAME_LEN", message = "project_name must be between 1 and 100 characters"))]
+    pub project_name: String,
+    #[serde(deserialize_with = "trimmed")]
+    #[validate(custom(function = "validate_webhook_url"))]
+    pub url: String,
+    #[serde(default)]
+    #[validate(custom(function = "validate_event_names"))]
+    pub events: Vec<String>,
+    #[serde(default, deserialize_with = "trimmed_opt")]
+    #[validate(length(min = 16, max = 200, message = "secret must be between 16 and 200 characters"))]
+    pub secret: Option<String>,
+}
+
+// A webhook subscription as stored in the database, without its secret.
+#[derive(Debug, FromRow)]
+pub struct WebhookRecord {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub url: String,
+    pub events: String, // Comma-separated event names, "*" for every event
+    pub created_by: Uuid,
+    pub created_at: String,
+}
+
+// A webhook subscription as returned to clients. The secret is only included when the webhook is created.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct Webhook {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub url: String,
+    pub events: Vec<String>,
+    pub created_by: Uuid,
+    pub created_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
 }
 
-// Alternative BugAssignment struct that accepts string UUIDs from frontend
-#[derive(Serialize, Deserialize, Debug)]
+impl From<WebhookRecord> for Webhook {
+    fn from(record: WebhookRecord) -> Self {
+        Webhook {
+            id: record.id,
+            project_id: record.project_id,
+            url: record.url,
+            events: record.events.split(',').map(str::to_string).collect(),
+            created_by: record.created_by,
+            created_at: record.created_at,
+            secret: None,
+        }
+    }
+}
+
+// Query parameters of `GET /webhooks`.
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct WebhookFilter {
+    pub project_name: Option<String>,
+}
+
+// One attempt-tracked delivery of an event to a webhook, as listed by `GET /webhooks/{id}/deliveries`.
+// `status` is "pending" (queued or waiting for a retry), "delivered" or "failed" (out of retries).
+#[derive(Serialize, Debug, FromRow, ToSchema)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub event: String,
+    #[serde(serialize_with = "raw_json")]
+    #[schema(value_type = Object)]
+    pub payload: String, // The exact JSON body that is signed and sent
+    pub status: String,
+    pub attempts: i64,
+    pub next_attempt_at: String,
+    pub last_status_code: Option<i64>,
+    pub last_error: Option<String>,
+    pub created_at: String,
+    pub delivered_at: Option<String>,
+}
+
+// How a user's emails are delivered: one by one, or collected into an hourly or daily digest.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+pub enum DigestMode {
+    #[default]
+    Off,
+    Hourly,
+    Daily,
+}
+
+// A user's email address and notification preferences, as returned by `GET /me/preferences`.
+#[derive(Serialize, Debug, FromRow, ToSchema)]
+pub struct NotificationPreferences {
+    pub email: Option<String>,
+    pub email_enabled: bool, // Master switch for every email
+    pub notify_assigned: bool, // A bug was assigned to the user
+    pub notify_status_changed: bool, // A bug the user reported, is assigned to or watches was fixed or reopened
+    pub notify_mentioned: bool, // The user was @mentioned in a bug description
+    pub digest: DigestMode,
+}
+
+// Request body for `PATCH /me/preferences`; fields that are left out keep their current value.
+#[derive(Deserialize, Debug, Validate, ToSchema)]
+pub struct UpdatePreferences {
+    #[serde(default, deserialize_with = "trimmed_opt")]
+    #[validate(email(message = "email must be a valid email address"))]
+    pub email: Option<String>,
+    pub email_enabled: Option<bool>,
+    pub notify_assigned: Option<bool>,
+    pub notify_status_changed: Option<bool>,
+    pub notify_mentioned: Option<bool>,
+    pub digest: Option<DigestMode>,
+}
+
+// Kinds of links between bugs, read as "<bug> <kind> <other bug>". `blocked_by` is accepted when
+// creating a link and stored as the other bug `blocks` this one.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum BugLinkKind {
+    DuplicateOf,
+    Blocks,
+    BlockedBy,
+    RelatesTo,
+    CausedBy,
+}
+
+impl BugLinkKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BugLinkKind::DuplicateOf => "duplicate_of",
+            BugLinkKind::Blocks => "blocks",
+            BugLinkKind::BlockedBy => "blocked_by",
+            BugLinkKind::RelatesTo => "relates_to",
+            BugLinkKind::CausedBy => "caused_by",
+        }
+    }
+
+    // How the link reads from the target bug's side
+    pub fn inverse(&self) -> &'static str {
+        match self {
+            BugLinkKind::DuplicateOf => "duplicated_by",
+            BugLinkKind::Blocks => "blocked_by",
+            BugLinkKind::BlockedBy => "blocks",
+            BugLinkKind::RelatesTo => "relates_to",
+            BugLinkKind::CausedBy => "causes",
+        }
+    }
+}
+
+// Request body for `POST /bugs/{id}/links`.
+#[derive(Deserialize, Debug, Validate, ToSchema)]
+pub struct CreateBugLink {
+    #[serde(deserialize_with = "trimmed")]
+    #[validate(custom(function = "validate_uuid"))]
+    pub target_id: String,
+    pub kind: BugLinkKind,
+}
+
+// A stored link between two bugs.
+#[derive(Serialize, Debug, FromRow, ToSchema)]
+pub struct BugLink {
+    pub id: Uuid,
+    pub source_bug_id: Uuid,
+    pub target_bug_id: Uuid,
+    pub kind: BugLinkKind,
+    pub created_by: Option<Uuid>,
+    pub created_at: String,
+}
+
+// A link as seen from one of its bugs, e.g. {"relation": "blocked_by", "bug_id": ..., "title": ...}
+// for the target of a `blocks` link. Listed in `GET /bugs/{id}`.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct BugLinkView {
+    pub link_id: Uuid,
+    pub relation: String,
+    pub bug_id: Uuid,
+    pub title: String,
+    pub is_fixed: bool,
+}
+
+// Request body for `POST /bugs/similar`: the bug as far as the reporter has written it.
+#[derive(Deserialize, Debug, Validate, ToSchema)]
+pub struct SimilarBugsRequest {
+    #[serde(deserialize_with = "trimmed")]
+    #[validate(length(min = 1, max = "MAX_TITLE_LEN", message = "title must be between 1 and 200 characters"))]
+    pub title: String,
+    #[serde(default, deserialize_with = "trimmed")]
+    #[validate(length(max = "MAX_DESCRIPTION_LEN", message = "description must be at most 10000 characters"))]
+    pub description: String,
+    #[serde(deserialize_with = "trimmed")]
+    #[validate(length(min = 1, max = "MAX_NAME_LEN", message = "project_name must be between 1 and 100 characters"))]
+    pub project_name: String,
+    #[validate(range(min = 1, max = 20, message = "limit must be between 1 and 20"))]
+    pub limit: Option<usize>, // Defaults to 5
+}
+
+// An open bug that may be the same as the one being reported. `score` runs from 0.0 to 1.0.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct SimilarBug {
+    pub bug_id: Uuid,
+    pub title: String,
+    pub severity: Severity,
+    pub created_at: String,
+    pub score: f64,
+}
+
+// Response of `POST /bugs`: the created bug with the open bugs it may duplicate.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct CreatedBug {
+    #[serde(flatten)]
+    pub bug: BugReport,
+    pub possible_duplicates: Vec<SimilarBug>,
+}
+
+// What an in-app notification is about.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum NotificationKind {
+    Assigned,       // A bug was assigned to the user
+    StatusChanged,  // A watched bug was fixed or reopened
+    SeverityRaised, // The severity of a watched bug went up
+    Mentioned,      // The user was @mentioned in a bug description
+}
+
+// An entry of the notification inbox, with the bug title and actor's username resolved.
+#[derive(Serialize, Debug, FromRow, ToSchema)]
+pub struct Notification {
+    pub id: Uuid,
+    pub kind: NotificationKind,
+    pub bug_id: Option<Uuid>,
+    pub bug_title: Option<String>,
+    pub actor: Option<String>, // Username of the user whose change caused the notification
+    pub message: String,
+    pub read_at: Option<String>, // None while unread
+    pub created_at: String,
+}
+
+// Response of `GET /me/notifications`. The counts cover the whole inbox, not just the returned page.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct NotificationInbox {
+    pub unread: i64,
+    pub total: i64,
+    pub notifications: Vec<Notification>,
+}
+
+// Query parameters of `GET /me/notifications`.
+#[derive(Deserialize, Debug, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct NotificationQuery {
+    #[serde(default)]
+    pub unread_only: bool,
+    #[validate(range(min = 1, max = 200, message = "limit must be between 1 and 200"))]
+    pub limit: Option<i64>, // Defaults to 50, newest first
+}
+
+// Query parameters of `GET /events`. Browsers' EventSource cannot send an Authorization header,
+// so the JWT may be passed as `access_token` instead.
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EventStreamQuery {
+    pub access_token: Option<String>,
+    pub project_name: Option<String>, // Only stream events of this project
+}
+
+// BugAssignment request that accepts string UUIDs from frontend
+#[derive(Serialize, Deserialize, Debug, Validate, ToSchema)]
 pub struct BugAssignmentRequest { 
+    #[serde(deserialize_with = "trimmed")]
+    #[validate(custom(function = "validate_uuid"))]
     pub bug_id: String, // String representation of UUID from frontend
+    #[serde(deserialize_with = "trimmed")]
+    #[validate(custom(function = "validate_uuid"))]
     pub user_id: String, // String representation of UUID from frontend
 }
 
+// Request body of PUT /api/v1/bugs/{id}/assignee; the bug comes from the path.
+#[derive(Deserialize, Debug, Validate, ToSchema)]
+pub struct AssignBug {
+    #[serde(deserialize_with = "trimmed")]
+    #[validate(custom(function = "validate_uuid"))]
+    pub user_id: String,
+}
+
 // Simple user struct for dropdowns (no password needed)
-#[derive(Serialize, Deserialize, Debug, FromRow)]
+#[derive(Serialize, Deserialize, Debug, FromRow, ToSchema)]
 pub struct SimpleUser {
     pub id: Uuid,
     pub username: String,
 }
+
+// Labels are stored trimmed and lowercase so "UI" and "ui " are the same label.
+pub fn normalize_label(label: &str) -> String {
+    label.trim().to_lowercase()
+}
+
+// Exactly one of `bug_ids` and `filter` selects the bugs of a bulk request. An empty filter would select every bug.
+fn validate_bulk_target(request: &BulkBugRequest) -> Result<(), ValidationError> {
+    match (&request.bug_ids, &request.filter) {
+        (None, Some(filter)) if !filter.has_conditions() => {
+            Err(ValidationError::new("bulk_target").with_message("filter must contain at least one condition".into()))
+        }
+        (Some(_), None) | (None, Some(_)) => Ok(()),
+        _ => Err(ValidationError::new("bulk_target").with_message("provide exactly one of bug_ids or filter".into())),
+    }
+}
+
+// A bulk request must change something, and deleting excludes every other change.
+fn validate_bulk_changes(changes: &BulkBugChanges) -> Result<(), ValidationError> {
+    let has_updates = changes.is_fixed.is_some()
+        || changes.severity.is_some()
+        || changes.priority.is_some()
+        || changes.fixed_by.is_some()
+        || !changes.add_labels.is_empty()
+        || !changes.remove_labels.is_empty();
+
+    if changes.delete && has_updates {
+        return Err(ValidationError::new("bulk_changes").with_message("delete cannot be combined with other changes".into()));
+    }
+    if !changes.delete && !has_updates {
+        return Err(ValidationError::new("bulk_changes").with_message("no changes provided".into()));
+    }
+    Ok(())
+}
+
+// Labels must be non-empty after trimming and at most 50 characters.
+fn validate_labels(labels: &[String]) -> Result<(), ValidationError> {
+    if labels.iter().any(|label| label.trim().is_empty() || label.trim().chars().count() > 50) {
+        return Err(ValidationError::new("labels").with_message("labels must be between 1 and 50 characters".into()));
+    }
+    Ok(())
+}
+
+// Deserializes a string with surrounding whitespace removed.
+fn trimmed<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    Ok(value.trim().to_string())
+}
+
+// Same as `trimmed`, for optional fields.
+fn trimmed_opt<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    let value = Option::<String>::deserialize(deserializer)?;
+    Ok(value.map(|v| v.trim().to_string()))
+}
+
+// Webhook URLs must be absolute http(s) URLs.
+fn validate_webhook_url(value: &str) -> Result<(), ValidationError> {
+    match url::Url::parse(value) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") && url.host_str().is_some() => Ok(()),
+        _ => Err(ValidationError::new("url").with_message("url must be an absolute http or https URL".into())),
+    }
+}
+
+// Every subscribed event must be "*" or a known event name such as "bug.created".
+fn validate_event_names(events: &[String]) -> Result<(), ValidationError> {
+    match events.iter().find(|event| event.trim() != "*" && event.trim().parse::<EventKind>().is_err()) {
+        Some(event) => Err(ValidationError::new("events").with_message(format!("unknown event '{}'", event).into())),
+        None => Ok(()),
+    }
+}
+
+// Serializes a column holding JSON text as JSON rather than as a string.
+fn raw_json<S: Serializer>(value: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    match serde_json::from_str::<serde_json::Value>(value) {
+        Ok(json) => json.serialize(serializer),
+        Err(_) => serializer.serialize_str(value),
+    }
+}
+
+// Validates that a string field holds a UUID.
+fn validate_uuid(value: &str) -> Result<(), ValidationError> {
+    Uuid::parse_str(value)
+        .map(|_| ())
+        .map_err(|_| ValidationError::new("uuid").with_message("must be a valid UUID".into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn severity_and_priority_parse_case_insensitively() {
+        assert_eq!("critical".parse::<Severity>(), Ok(Severity::Critical));
+        assert_eq!(" High ".parse::<Severity>(), Ok(Severity::High));
+        assert_eq!("URGENT".parse::<Priority>(), Ok(Priority::Urgent));
+        assert_eq!("low".parse::<Priority>(), Ok(Priority::Low));
+    }
+
+    #[test]
+    fn unknown_severity_and_priority_are_rejected() {
+        assert!("crit".parse::<Severity>().unwrap_err().contains("low, medium, high, critical"));
+        assert!("urgent".parse::<Severity>().is_err());
+        assert!("critical".parse::<Priority>().is_err());
+        assert!("".parse::<Priority>().is_err());
+        assert!(serde_json::from_str::<Severity>("\"blocker\"").is_err());
+    }
+
+    #[test]
+    fn severity_and_priority_rank_and_round_trip() {
+        assert!(Severity::Critical > Severity::High && Severity::Medium > Severity::Low);
+        assert!(Priority::Urgent > Priority::High);
+        assert_eq!(Priority::default(), Priority::Medium);
+        for severity in [Severity::Low, Severity::Medium, Severity::High, Severity::Critical] {
+            assert_eq!(severity.as_str().parse::<Severity>(), Ok(severity));
+        }
+        assert_eq!(serde_json::to_string(&Severity::High).unwrap(), "\"high\"");
+    }
+
+    fn bulk_request(json: serde_json::Value) -> BulkBugRequest {
+        serde_json::from_value(json).expect("valid bulk request")
+    }
+
+    #[test]
+    fn bulk_request_takes_either_ids_or_a_filter() {
+        let by_ids = bulk_request(serde_json::json!({"bug_ids": ["a"], "changes": {"is_fixed": true}}));
+        assert!(validate_bulk_target(&by_ids).is_ok());
+
+        let by_filter = bulk_request(serde_json::json!({"filter": {"severity": "low"}, "changes": {"delete": true}}));
+        assert!(validate_bulk_target(&by_filter).is_ok());
+    }
+
+    #[test]
+    fn bulk_request_rejects_both_or_neither_target() {
+        let both = bulk_request(serde_json::json!({"bug_ids": ["a"], "filter": {"severity": "low"}, "changes": {"delete": true}}));
+        assert_eq!(validate_bulk_target(&both).unwrap_err().code, "bulk_target");
+
+        let neither = bulk_request(serde_json::json!({"changes": {"delete": true}}));
+        assert!(validate_bulk_target(&neither).is_err());
+    }
+
+    #[test]
+    fn bulk_request_rejects_a_filter_without_conditions() {
+        let empty = bulk_request(serde_json::json!({"filter": {}, "changes": {"delete": true}}));
+        assert!(validate_bulk_target(&empty).is_err());
+
+        // Sorting alone does not narrow anything down
+        let sort_only = bulk_request(serde_json::json!({"filter": {"sort": "severity"}, "changes": {"delete": true}}));
+        assert!(validate_bulk_target(&sort_only).is_err());
+    }
+
+    #[test]
+    fn bulk_outcome_serializes_in_snake_case() {
+        assert_eq!(serde_json::to_string(&BulkOutcome::InvalidId).unwrap(), "\"invalid_id\"");
+        assert_eq!(serde_json::to_string(&BulkOutcome::NotFound).unwrap(), "\"not_found\"");
+    }
+}
@@ -0,0 +1,358 @@
+// In-app notifications: the per-user inbox behind `/me/notifications`. Entries are created from bug events,
+// like emails, but regardless of email preferences. A cleanup task deletes entries older than
+// retention.notification_days (90 by default).
+use actix_web::rt::task::JoinHandle;
+use sqlx::SqlitePool;
+use std::time::Duration;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::events::{DomainEvent, EventKind};
+use crate::models::{Notification, NotificationInbox, NotificationKind, Severity};
+use crate::{shutdown, watchers};
+
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(3600);
+const DEFAULT_PAGE_SIZE: i64 = 50;
+
+// Selects `Notification`s; append a WHERE clause on `n` that includes NOT_TRASHED.
+const NOTIFICATION_SELECT: &str = "SELECT n.id, n.kind, n.bug_id, b.title AS bug_title, u.username AS actor, n.message, \
+     n.read_at, n.created_at FROM notification n \
+     LEFT JOIN bugReport b ON b.id = n.bug_id LEFT JOIN users u ON u.id = n.actor_id";
+
+// Notifications about a bug in the trash are hidden, and counted nowhere, until it is restored.
+const NOT_TRASHED: &str = "NOT EXISTS (SELECT 1 FROM bugReport WHERE id = n.bug_id AND deleted_at IS NOT NULL)";
+
+// Adds a notification for everyone the event concerns, except the user who caused it: the assignee of an
+// assigned bug, mentioned users, and the watchers of a bug that was fixed, reopened or made more severe.
+pub async fn enqueue(pool: &SqlitePool, event: &DomainEvent) -> Result<usize, sqlx::Error> {
+    let id_field = |field: &str| event.data[field].as_str().and_then(|id| Uuid::parse_str(id).ok());
+    let Some(bug_id) = id_field("id").filter(|_| event.event != EventKind::ProjectCreated) else {
+        return Ok(0);
+    };
+
+    let actor = match event.actor {
+        Some(actor) => sqlx::query_scalar::<_, String>("SELECT username FROM users WHERE id = ?")
+            .bind(actor.as_bytes().as_slice())
+            .fetch_optional(pool)
+            .await?,
+        None => None,
+    }
+    .unwrap_or_else(|| "Someone".to_string());
+    let title = event.data["title"].as_str().unwrap_or_default();
+
+    let (kind, message) = match event.event {
+        EventKind::BugAssigned => (NotificationKind::Assigned, format!("{} assigned you \"{}\"", actor, title)),
+        EventKind::BugMentioned => (NotificationKind::Mentioned, format!("{} mentioned you in \"{}\"", actor, title)),
+        EventKind::BugFixed => (NotificationKind::StatusChanged, format!("{} marked \"{}\" as fixed", actor, title)),
+        EventKind::BugReopened => (NotificationKind::StatusChanged, format!("{} reopened \"{}\"", actor, title)),
+        EventKind::BugUpdated => match severity_raise(event) {
+            Some((from, to)) => (
+                NotificationKind::SeverityRaised,
+                format!("{} raised the severity of \"{}\" from {} to {}", actor, title, from, to),
+            ),
+            None => return Ok(0),
+        },
+        _ => return Ok(0),
+    };
+
+    let mut recipients = match kind {
+        NotificationKind::Assigned => id_field("fixed_by").into_iter().collect(),
+        NotificationKind::Mentioned => event.mentioned.clone(),
+        _ => watchers::watcher_ids(pool, bug_id).await?,
+    };
+    recipients.retain(|id| Some(*id) != event.actor);
+    recipients.sort();
+    recipients.dedup();
+
+    for recipient in &recipients {
+        sqlx::query("INSERT INTO notification (id, user_id, kind, bug_id, actor_id, message) VALUES (?, ?, ?, ?, ?, ?)")
+            .bind(Uuid::new_v4().as_bytes().as_slice())
+            .bind(recipient.as_bytes().as_slice())
+            .bind(kind)
+            .bind(bug_id.as_bytes().as_slice())
+            .bind(event.actor.map(|id| id.as_bytes().to_vec()))
+            .bind(&message)
+            .execute(pool)
+            .await?;
+    }
+    Ok(recipients.len())
+}
+
+// The old and new severity if a bug.updated event raised it.
+fn severity_raise(event: &DomainEvent) -> Option<(Severity, Severity)> {
+    let change = event.changes.as_ref()?.get("severity")?;
+    let from: Severity = change["from"].as_str()?.parse().ok()?;
+    let to: Severity = change["to"].as_str()?.parse().ok()?;
+    (to > from).then_some((from, to))
+}
+
+// A page of the user's notifications, newest first, with the unread and total counts of the whole inbox.
+pub async fn inbox(pool: &SqlitePool, user_id: Uuid, unread_only: bool, limit: Option<i64>) -> Result<NotificationInbox, sqlx::Error> {
+    let (unread, total) = sqlx::query_as::<_, (i64, i64)>(&format!(
+        "SELECT COUNT(*) FILTER (WHERE n.read_at IS NULL), COUNT(*) FROM notification n WHERE n.user_id = ? AND {}",
+        NOT_TRASHED
+    ))
+    .bind(user_id.as_bytes().as_slice())
+    .fetch_one(pool)
+    .await?;
+
+    let notifications = sqlx::query_as::<_, Notification>(&format!(
+        "{} WHERE n.user_id = ? AND (? = FALSE OR n.read_at IS NULL) AND {} ORDER BY n.created_at DESC, n.rowid DESC LIMIT ?",
+        NOTIFICATION_SELECT, NOT_TRASHED
+    ))
+    .bind(user_id.as_bytes().as_slice())
+    .bind(unread_only)
+    .bind(limit.unwrap_or(DEFAULT_PAGE_SIZE))
+    .fetch_all(pool)
+    .await?;
+
+    Ok(NotificationInbox { unread, total, notifications })
+}
+
+// Marks one of the user's notifications as read. Returns None if the user has no such notification, or it is hidden.
+pub async fn mark_read(pool: &SqlitePool, user_id: Uuid, notification_id: Uuid) -> Result<Option<Notification>, sqlx::Error> {
+    sqlx::query(&format!(
+        "UPDATE notification AS n SET read_at = COALESCE(read_at, CURRENT_TIMESTAMP) WHERE n.id = ? AND n.user_id = ? AND {}",
+        NOT_TRASHED
+    ))
+    .bind(notification_id.as_bytes().as_slice())
+    .bind(user_id.as_bytes().as_slice())
+    .execute(pool)
+    .await?;
+
+    sqlx::query_as::<_, Notification>(&format!("{} WHERE n.id = ? AND n.user_id = ? AND {}", NOTIFICATION_SELECT, NOT_TRASHED))
+        .bind(notification_id.as_bytes().as_slice())
+        .bind(user_id.as_bytes().as_slice())
+        .fetch_optional(pool)
+        .await
+}
+
+// Marks every unread notification of the user as read and returns how many there were.
+pub async fn mark_all_read(pool: &SqlitePool, user_id: Uuid) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(&format!(
+        "UPDATE notification AS n SET read_at = CURRENT_TIMESTAMP WHERE n.user_id = ? AND n.read_at IS NULL AND {}",
+        NOT_TRASHED
+    ))
+    .bind(user_id.as_bytes().as_slice())
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+// Starts the hourly task deleting notifications older than `retention_days`, read or not. It stops on shutdown.
+pub fn start_cleanup(pool: SqlitePool, retention_days: i64) -> JoinHandle<()> {
+    actix_web::rt::spawn(async move {
+        loop {
+            let deleted = sqlx::query("DELETE FROM notification WHERE created_at < datetime('now', ?)")
+                .bind(format!("-{} days", retention_days))
+                .execute(&pool)
+                .await;
+            match deleted {
+                Ok(result) if result.rows_affected() > 0 => {
+                    info!(deleted = result.rows_affected(), retention_days, "Deleted old notifications")
+                }
+                Ok(_) => {}
+                Err(e) => error!(error = ?e, "Notification cleanup error"),
+            }
+            if !shutdown::pause(CLEANUP_INTERVAL).await {
+                break;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use std::path::Path;
+
+    use crate::db;
+
+    // Ids of the rows set up by `setup`.
+    struct Fixture {
+        pool: SqlitePool,
+        users: [Uuid; 3],
+        project_id: Uuid,
+        bug_id: Uuid,
+    }
+
+    // A private in-memory database with three users and one bug reported by the first, who watches it.
+    async fn setup() -> Fixture {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        db::run_migrations(&pool, Path::new("migrations")).await.unwrap();
+
+        let users = [Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()];
+        for (index, user_id) in users.iter().enumerate() {
+            sqlx::query("INSERT INTO users (id, username, hashed_password) VALUES (?, ?, '')")
+                .bind(user_id.as_bytes().as_slice())
+                .bind(format!("user{}", index))
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        let project_id = Uuid::new_v4();
+        sqlx::query("INSERT INTO projectRecord (id, user_id, project_name) VALUES (?, ?, 'Notifications')")
+            .bind(project_id.as_bytes().as_slice())
+            .bind(users[0].as_bytes().as_slice())
+            .execute(&pool)
+            .await
+            .unwrap();
+        let bug_id = Uuid::new_v4();
+        sqlx::query("INSERT INTO bugReport (id, title, description, project_id, reported_by, severity) VALUES (?, 'Crash', '', ?, ?, 'low')")
+            .bind(bug_id.as_bytes().as_slice())
+            .bind(project_id.as_bytes().as_slice())
+            .bind(users[0].as_bytes().as_slice())
+            .execute(&pool)
+            .await
+            .unwrap();
+        watchers::watch(&pool, bug_id, users[0]).await.unwrap();
+
+        Fixture { pool, users, project_id, bug_id }
+    }
+
+    // An event about the fixture's bug, as `DomainEvent::bug` would build it.
+    fn event(fixture: &Fixture, kind: EventKind, actor: Uuid, data: serde_json::Value) -> DomainEvent {
+        let mut bug = json!({ "id": fixture.bug_id.to_string(), "title": "Crash" });
+        bug.as_object_mut().unwrap().extend(data.as_object().unwrap().clone());
+        DomainEvent {
+            event: kind,
+            project_id: fixture.project_id,
+            actor: Some(actor),
+            occurred_at: String::new(),
+            data: bug,
+            changes: None,
+            mentioned: Vec::new(),
+        }
+    }
+
+    async fn set_trashed(fixture: &Fixture, trashed: bool) {
+        sqlx::query("UPDATE bugReport SET deleted_at = CASE WHEN ? THEN CURRENT_TIMESTAMP END WHERE id = ?")
+            .bind(trashed)
+            .bind(fixture.bug_id.as_bytes().as_slice())
+            .execute(&fixture.pool)
+            .await
+            .unwrap();
+    }
+
+    #[actix_web::test]
+    async fn notifications_about_trashed_bugs_are_hidden_until_restored() {
+        let fixture = setup().await;
+        let [reporter, assignee, _] = fixture.users;
+        let assigned = event(&fixture, EventKind::BugAssigned, reporter, json!({ "fixed_by": assignee.to_string() }));
+        assert_eq!(enqueue(&fixture.pool, &assigned).await.unwrap(), 1);
+        let notification_id = inbox(&fixture.pool, assignee, false, None).await.unwrap().notifications[0].id;
+
+        set_trashed(&fixture, true).await;
+        let hidden = inbox(&fixture.pool, assignee, false, None).await.unwrap();
+        assert_eq!((hidden.unread, hidden.total, hidden.notifications.len()), (0, 0, 0));
+        assert!(mark_read(&fixture.pool, assignee, notification_id).await.unwrap().is_none());
+        assert_eq!(mark_all_read(&fixture.pool, assignee).await.unwrap(), 0);
+
+        set_trashed(&fixture, false).await;
+        let restored = inbox(&fixture.pool, assignee, false, None).await.unwrap();
+        assert_eq!((restored.unread, restored.total), (1, 1));
+        assert_eq!(restored.notifications[0].bug_title.as_deref(), Some("Crash"));
+    }
+
+    // Recipients of the notifications in the database, with their kinds, sorted.
+    async fn sent(fixture: &Fixture) -> Vec<(Uuid, NotificationKind)> {
+        let mut sent = sqlx::query_as::<_, (Uuid, NotificationKind)>("SELECT user_id, kind FROM notification")
+            .fetch_all(&fixture.pool)
+            .await
+            .unwrap();
+        sent.sort_by_key(|(user_id, _)| *user_id);
+        sent
+    }
+
+    #[actix_web::test]
+    async fn assignments_notify_the_assignee_only() {
+        let fixture = setup().await;
+        let [reporter, assignee, _] = fixture.users;
+        let assigned = event(&fixture, EventKind::BugAssigned, reporter, json!({ "fixed_by": assignee.to_string() }));
+        assert_eq!(enqueue(&fixture.pool, &assigned).await.unwrap(), 1);
+        assert_eq!(sent(&fixture).await, vec![(assignee, NotificationKind::Assigned)]);
+
+        let message = &inbox(&fixture.pool, assignee, false, None).await.unwrap().notifications[0].message;
+        assert_eq!(message, "user0 assigned you \"Crash\"");
+    }
+
+    #[actix_web::test]
+    async fn mentions_notify_the_mentioned_users_except_the_actor() {
+        let fixture = setup().await;
+        let [reporter, first, second] = fixture.users;
+        let mut mentioned = event(&fixture, EventKind::BugMentioned, reporter, json!({}));
+        mentioned.mentioned = vec![first, second, reporter, first];
+        assert_eq!(enqueue(&fixture.pool, &mentioned).await.unwrap(), 2);
+
+        let mut expected = vec![(first, NotificationKind::Mentioned), (second, NotificationKind::Mentioned)];
+        expected.sort_by_key(|(user_id, _)| *user_id);
+        assert_eq!(sent(&fixture).await, expected);
+    }
+
+    #[actix_web::test]
+    async fn status_changes_notify_the_watchers_except_the_actor() {
+        let fixture = setup().await;
+        let [reporter, watcher, _] = fixture.users;
+        watchers::watch(&fixture.pool, fixture.bug_id, watcher).await.unwrap();
+
+        let fixed = event(&fixture, EventKind::BugFixed, watcher, json!({}));
+        assert_eq!(enqueue(&fixture.pool, &fixed).await.unwrap(), 1);
+        assert_eq!(sent(&fixture).await, vec![(reporter, NotificationKind::StatusChanged)]);
+
+        // Without an actor, every watcher is told
+        let mut reopened = event(&fixture, EventKind::BugReopened, watcher, json!({}));
+        reopened.actor = None;
+        assert_eq!(enqueue(&fixture.pool, &reopened).await.unwrap(), 2);
+    }
+
+    #[actix_web::test]
+    async fn only_raised_severities_notify_the_watchers() {
+        let fixture = setup().await;
+        let [reporter, actor, _] = fixture.users;
+        let updated = |from: &str, to: &str| DomainEvent {
+            changes: Some(json!({ "severity": { "from": from, "to": to } })),
+            ..event(&fixture, EventKind::BugUpdated, actor, json!({}))
+        };
+
+        assert_eq!(severity_raise(&updated("low", "critical")), Some((Severity::Low, Severity::Critical)));
+        assert_eq!(severity_raise(&updated("high", "medium")), None);
+        assert_eq!(severity_raise(&updated("high", "high")), None);
+        assert_eq!(severity_raise(&event(&fixture, EventKind::BugUpdated, actor, json!({}))), None);
+
+        assert_eq!(enqueue(&fixture.pool, &updated("high", "low")).await.unwrap(), 0);
+        assert_eq!(enqueue(&fixture.pool, &updated("low", "high")).await.unwrap(), 1);
+        assert_eq!(sent(&fixture).await, vec![(reporter, NotificationKind::SeverityRaised)]);
+        let message = &inbox(&fixture.pool, reporter, false, None).await.unwrap().notifications[0].message;
+        assert_eq!(message, "user1 raised the severity of \"Crash\" from low to high");
+    }
+
+    #[actix_web::test]
+    async fn other_events_notify_nobody() {
+        let fixture = setup().await;
+        let [reporter, actor, _] = fixture.users;
+        for kind in [EventKind::BugCreated, EventKind::BugDeleted, EventKind::BugRestored] {
+            assert_eq!(enqueue(&fixture.pool, &event(&fixture, kind, actor, json!({}))).await.unwrap(), 0);
+        }
+        assert!(sent(&fixture).await.is_empty());
+        assert_eq!(inbox(&fixture.pool, reporter, false, None).await.unwrap().total, 0);
+    }
+
+    #[actix_web::test]
+    async fn users_can_only_mark_their_own_notifications_read() {
+        let fixture = setup().await;
+        let [reporter, assignee, other] = fixture.users;
+        let assigned = event(&fixture, EventKind::BugAssigned, reporter, json!({ "fixed_by": assignee.to_string() }));
+        enqueue(&fixture.pool, &assigned).await.unwrap();
+        let notification_id = inbox(&fixture.pool, assignee, false, None).await.unwrap().notifications[0].id;
+
+        assert!(mark_read(&fixture.pool, other, notification_id).await.unwrap().is_none());
+        assert_eq!(inbox(&fixture.pool, assignee, false, None).await.unwrap().unread, 1);
+
+        let read = mark_read(&fixture.pool, assignee, notification_id).await.unwrap().unwrap();
+        assert!(read.read_at.is_some());
+        let inbox = inbox(&fixture.pool, assignee, true, None).await.unwrap();
+        assert_eq!((inbox.unread, inbox.total, inbox.notifications.len()), (0, 1, 0));
+    }
+}
@@ -0,0 +1,193 @@
+// OpenAPI 3 description of version 1 of the JSON API, generated by utoipa from the `#[utoipa::path]` attributes on
+// the handlers and the `ToSchema`/`IntoParams` derives in models.rs. Served as JSON at `/openapi.json` and browsable
+// at `/docs`. Paths are relative to the `/api/v1` server. A route added to `handlers::v1_routes` needs a
+// `#[utoipa::path]` and an entry in `paths(...)` below, which the test at the bottom of this file checks.
+use actix_web::{HttpResponse, Responder};
+use std::sync::OnceLock;
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+use crate::handlers;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Bug Tracker API", description = "Projects, bug reports and their notifications.", version = "1"),
+    servers((url = "/api/v1", description = "Version 1")),
+    paths(
+        handlers::login_function,
+        handlers::get_projects,
+        handlers::add_project,
+        handlers::get_bugs,
+        handlers::export_bugs,
+        handlers::get_trash,
+        handlers::get_bug_by_id,
+        handlers::create_bug,
+        handlers::similar_bugs,
+        handlers::bulk_update_bugs,
+        handlers::import_bugs,
+        handlers::set_bug_assignee,
+        handlers::watch_bug,
+        handlers::unwatch_bug,
+        handlers::add_bug_link,
+        handlers::remove_bug_link,
+        handlers::restore_bug,
+        handlers::update_bug_details,
+        handlers::delete_bug,
+        handlers::create_webhook,
+        handlers::get_webhooks,
+        handlers::redeliver_webhook,
+        handlers::delete_webhook,
+        handlers::get_webhook_deliveries,
+        handlers::get_preferences,
+        handlers::update_preferences,
+        handlers::get_watching,
+        handlers::get_notifications,
+        handlers::mark_all_notifications_read,
+        handlers::mark_notification_read,
+        handlers::stream_events,
+    ),
+    modifiers(&SecuritySchemes),
+    tags(
+        (name = "auth", description = "Logging in"),
+        (name = "projects", description = "Projects that bugs are filed under"),
+        (name = "bugs", description = "Bug reports, their watchers and links"),
+        (name = "trash", description = "Deleting and restoring bugs"),
+        (name = "webhooks", description = "Webhook subscriptions and deliveries"),
+        (name = "me", description = "Preferences, watched bugs and notifications of the logged in user"),
+        (name = "events", description = "Live stream of bug and project events"),
+    )
+)]
+pub struct ApiDoc;
+
+// The JWT from `POST /login`, sent as `Authorization: Bearer <token>` or, for `/events` only, as `?access_token=`.
+// Also tidies up the generated info section.
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        // utoipa fills the license in from Cargo.toml, which has none
+        openapi.info.license = None;
+
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).bearer_format("JWT").build()),
+        );
+        components.add_security_scheme("access_token", SecurityScheme::ApiKey(ApiKey::Query(ApiKeyValue::new("access_token"))));
+    }
+}
+
+// The spec only changes with the code, so it is serialized once.
+fn spec_json() -> &'static str {
+    static SPEC: OnceLock<String> = OnceLock::new();
+    SPEC.get_or_init(|| ApiDoc::openapi().to_pretty_json().expect("OpenAPI document serializes to JSON"))
+}
+
+// Asynchronous function returning the OpenAPI document.
+pub async fn openapi_json() -> impl Responder {
+    HttpResponse::Ok().content_type("application/json").body(spec_json())
+}
+
+// Asynchronous function returning a page that renders `/openapi.json` as browsable documentation.
+pub async fn api_docs() -> impl Responder {
+    HttpResponse::Ok().content_type("text/html; charset=utf-8").body(include_str!("../static/api-docs.html"))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::{Method, StatusCode};
+    use actix_web::{test, web, App};
+    use std::collections::BTreeSet;
+    use utoipa::openapi::path::{Operation, PathItem};
+    use utoipa::OpenApi;
+
+    use super::ApiDoc;
+    use crate::config::Config;
+    use crate::{db, handlers};
+
+    // Handler names passed to `.to(...)` in `handlers::v1_routes`, read from its source.
+    fn registered_handlers() -> BTreeSet<String> {
+        let source = include_str!("handlers.rs");
+        let config = &source[source.find("fn v1_routes(").expect("handlers::v1_routes exists")..];
+        let config = &config[..config.find("\n}\n").expect("handlers::v1_routes ends")];
+        config
+            .split(".to(")
+            .skip(1)
+            .map(|rest| rest[..rest.find(')').unwrap()].rsplit("::").next().unwrap().to_string())
+            .collect()
+    }
+
+    fn operations(item: &PathItem) -> Vec<(Method, &Operation)> {
+        [
+            (Method::GET, &item.get),
+            (Method::POST, &item.post),
+            (Method::PUT, &item.put),
+            (Method::PATCH, &item.patch),
+            (Method::DELETE, &item.delete),
+        ]
+        .into_iter()
+        .filter_map(|(method, operation)| operation.as_ref().map(|operation| (method, operation)))
+        .collect()
+    }
+
+    // Fails when a route is added, removed or moved without updating the spec, or when the spec says a route
+    // needs a token but it answers without one (or the other way round).
+    #[actix_web::test]
+    async fn spec_matches_registered_routes() {
+        let spec = ApiDoc::openapi();
+
+        let documented: BTreeSet<String> = spec
+            .paths
+            .paths
+            .values()
+            .flat_map(operations)
+            .filter_map(|(_, operation)| operation.operation_id.clone())
+            .collect();
+        assert_eq!(documented, registered_handlers(), "handlers registered in handlers::v1_routes and documented in ApiDoc differ");
+
+        let config = Config::default();
+        let pool = db::init_db(&config.database).await;
+        let app = test::init_service(
+            App::new().app_data(web::Data::new(pool)).app_data(web::Data::new(config)).configure(handlers::config),
+        )
+        .await;
+
+        for (path, item) in &spec.paths.paths {
+            for (method, operation) in operations(item) {
+                // Placeholders such as {id} are filled with an id nothing has
+                let path = format!("{}{}", handlers::API_V1, path);
+                let uri = path
+                    .split('/')
+                    .map(|segment| if segment.starts_with('{') { "00000000-0000-0000-0000-000000000000" } else { segment })
+                    .collect::<Vec<_>>()
+                    .join("/");
+                let response = test::call_service(&app, test::TestRequest::default().method(method.clone()).uri(&uri).to_request()).await;
+                let status = response.status();
+                let body = test::read_body(response).await;
+
+                // Unmatched routes get actix's empty 404, unmatched methods a 405
+                assert!(
+                    !(status == StatusCode::NOT_FOUND && body.is_empty()) && status != StatusCode::METHOD_NOT_ALLOWED,
+                    "{} {} is documented but not routed ({})",
+                    method,
+                    path,
+                    status
+                );
+
+                // Optional authentication is documented as an empty requirement next to the scheme
+                let security = serde_json::to_value(&operation.security).unwrap();
+                let requires_token = security
+                    .as_array()
+                    .is_some_and(|requirements| !requirements.is_empty() && requirements.iter().all(|r| r.as_object().is_some_and(|r| !r.is_empty())));
+                assert_eq!(
+                    status == StatusCode::UNAUTHORIZED,
+                    requires_token,
+                    "{} {} answered {} without a token, which does not match its documented security",
+                    method,
+                    path,
+                    status
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,157 @@
+// Graceful shutdown. The first SIGINT (Ctrl-C) or SIGTERM:
+// 1. stops the listeners accepting connections and tells everything waiting on `requested()` to wind down:
+//    `/events` streams end, the webhook and email workers send what is already due, the periodic cleanups stop;
+// 2. waits up to server.shutdown_timeout_secs for in-flight requests to finish, then for the background tasks;
+// 3. closes the database pool.
+// Each step is logged with what it drained. A second signal stops at once, dropping whatever is still running.
+use actix_web::dev::ServerHandle;
+use actix_web::rt::task::JoinHandle;
+use futures_util::future::join_all;
+use sqlx::SqlitePool;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+use crate::metrics;
+
+// When shutdown started, None while the server is running. A global so that the workers and the event streams can
+// wait on it without being handed a receiver.
+static SHUTDOWN: LazyLock<watch::Sender<Option<Instant>>> = LazyLock::new(|| watch::Sender::new(None));
+
+// Set by a second signal: nothing more is waited for.
+static FORCED: LazyLock<watch::Sender<bool>> = LazyLock::new(|| watch::Sender::new(false));
+
+// Whether shutdown has started.
+pub fn is_requested() -> bool {
+    SHUTDOWN.borrow().is_some()
+}
+
+// Resolves once shutdown starts (at once if it already has).
+pub async fn requested() {
+    let mut receiver = SHUTDOWN.subscribe();
+    // Fails only if the sender is dropped, which as a static it never is
+    let _ = receiver.wait_for(Option::is_some).await;
+}
+
+async fn forced() {
+    let _ = FORCED.subscribe().wait_for(|forced| *forced).await;
+}
+
+// Sleeps for `duration` between two rounds of a background task. Returns false, early, when shutdown starts.
+pub async fn pause(duration: Duration) -> bool {
+    tokio::select! {
+        _ = tokio::time::sleep(duration) => !is_requested(),
+        _ = requested() => false,
+    }
+}
+
+// Waits for SIGINT or SIGTERM.
+async fn signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut terminate = signal(SignalKind::terminate()).expect("Failed to listen for SIGTERM");
+        let mut interrupt = signal(SignalKind::interrupt()).expect("Failed to listen for SIGINT");
+        tokio::select! {
+            _ = terminate.recv() => "SIGTERM",
+            _ = interrupt.recv() => "SIGINT",
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await.expect("Failed to listen for Ctrl-C");
+        "Ctrl-C"
+    }
+}
+
+// Starts the task stopping `server` on the first signal. The server itself must be built with `disable_signals()`
+// and a `shutdown_timeout` of `timeout`, which bounds how long in-flight requests are waited for.
+pub fn listen(server: ServerHandle, timeout: Duration) {
+    actix_web::rt::spawn(async move {
+        let name = signal().await;
+        info!(
+            signal = name,
+            in_flight = metrics::requests_in_flight(),
+            timeout_secs = timeout.as_secs(),
+            "Shutting down: no longer accepting connections, draining in-flight requests"
+        );
+        SHUTDOWN.send_replace(Some(Instant::now()));
+
+        // The graceful stop returns once the requests are drained. A second signal, before or after that, cuts
+        // short the wait for the requests and then for the background tasks.
+        let mut draining = true;
+        let stop = server.stop(true);
+        let second = signal();
+        tokio::pin!(stop, second);
+        let name = loop {
+            tokio::select! {
+                _ = &mut stop, if draining => draining = false,
+                name = &mut second => break name,
+            }
+        };
+        warn!(signal = name, in_flight = metrics::requests_in_flight(), "Second signal, stopping immediately");
+        FORCED.send_replace(true);
+        if draining {
+            server.stop(false).await;
+        }
+    });
+}
+
+// Called once the server has stopped: logs how the drain went, gives the background tasks what is left of
+// `timeout` to finish, and closes the pool. Returns false if the timeout cut anything off.
+pub async fn finish(pool: &SqlitePool, tasks: Vec<JoinHandle<()>>, timeout: Duration) -> bool {
+    // Also reached when the server stops without a signal, e.g. because a worker thread died
+    let started = *SHUTDOWN.borrow();
+    let started = started.unwrap_or_else(|| {
+        SHUTDOWN.send_replace(Some(Instant::now()));
+        Instant::now()
+    });
+
+    // Requests cut off by the timeout are dropped with their worker threads, so the gauge may already be back to 0
+    let in_flight = metrics::requests_in_flight();
+    let mut clean = in_flight == 0 && started.elapsed() < timeout && !*FORCED.borrow();
+    if !clean {
+        warn!(in_flight, "Shutdown timeout reached, requests still running were dropped");
+    } else {
+        info!(elapsed_ms = started.elapsed().as_millis() as u64, "In-flight requests drained");
+    }
+
+    let count = tasks.len();
+    let aborts: Vec<_> = tasks.iter().map(JoinHandle::abort_handle).collect();
+    let remaining = timeout.saturating_sub(started.elapsed());
+    let stopped = tokio::select! {
+        result = tokio::time::timeout(remaining, join_all(tasks)) => result.is_ok(),
+        _ = forced() => false,
+    };
+    if stopped {
+        info!(tasks = count, "Background tasks stopped");
+    } else {
+        // Dropping them releases their database connections, so that closing the pool below does not wait
+        let unfinished = aborts.iter().filter(|task| !task.is_finished()).count();
+        aborts.iter().for_each(|task| task.abort());
+        warn!(unfinished, "Background tasks did not stop in time and were cancelled");
+        clean = false;
+    }
+
+    // Deliveries not yet due (retries, digests) stay queued; they survive a restart when the database is a file
+    match queued_work(pool).await {
+        Ok((0, 0)) => {}
+        Ok((webhooks, emails)) => info!(webhooks, emails, "Deliveries still queued"),
+        Err(e) => error!(error = ?e, "Failed to count queued deliveries"),
+    }
+
+    pool.close().await;
+    info!(elapsed_ms = started.elapsed().as_millis() as u64, "Database pool closed, shutdown complete");
+    clean
+}
+
+async fn queued_work(pool: &SqlitePool) -> Result<(i64, i64), sqlx::Error> {
+    let webhooks = sqlx::query_scalar("SELECT COUNT(*) FROM webhookDelivery WHERE status = 'pending'")
+        .fetch_one(pool)
+        .await?;
+    let emails = sqlx::query_scalar("SELECT COUNT(*) FROM emailOutbox WHERE status = 'pending'")
+        .fetch_one(pool)
+        .await?;
+    Ok((webhooks, emails))
+}
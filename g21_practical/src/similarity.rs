@@ -0,0 +1,158 @@
+// Likely duplicates of a bug that is about to be reported. Titles and descriptions are compared with the open bugs
+// of the same project by the words they share and by their character trigrams, which also catch typos and
+// different word endings ("crash" vs "crashes"). The title counts for more than the description.
+use sqlx::SqlitePool;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::models::{Severity, SimilarBug};
+
+// Bugs scoring below this are not reported as likely duplicates.
+pub const MIN_SCORE: f64 = 0.3;
+pub const DEFAULT_LIMIT: usize = 5;
+
+const TITLE_WEIGHT: f64 = 0.7;
+
+// Words too common in bug reports to say anything about whether two of them are the same.
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "from", "has", "have", "in", "is", "it", "its", "not",
+    "of", "on", "or", "that", "the", "this", "to", "was", "when", "with",
+];
+
+// The words of a text and the trigrams of those words, lowercased.
+struct Fingerprint {
+    words: HashSet<String>,
+    trigrams: HashSet<String>,
+}
+
+impl Fingerprint {
+    fn new(text: &str) -> Self {
+        let words: HashSet<String> = text
+            .split(|c: char| !c.is_alphanumeric())
+            .map(str::to_lowercase)
+            .filter(|word| !word.is_empty() && !STOP_WORDS.contains(&word.as_str()))
+            .collect();
+        // Padded with spaces so that short words still have trigrams and word starts and ends weigh more
+        let trigrams = words
+            .iter()
+            .flat_map(|word| {
+                let chars: Vec<char> = format!("  {} ", word).chars().collect();
+                chars.windows(3).map(|w| w.iter().collect::<String>()).collect::<Vec<_>>()
+            })
+            .collect();
+        Fingerprint { words, trigrams }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    // 0.0 (nothing in common) to 1.0 (same words)
+    fn similarity(&self, other: &Fingerprint) -> f64 {
+        (jaccard(&self.words, &other.words) + jaccard(&self.trigrams, &other.trigrams)) / 2.0
+    }
+}
+
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+// How much an existing bug resembles the new one, weighting the title by TITLE_WEIGHT.
+fn score(title: &Fingerprint, description: &Fingerprint, bug_title: &str, bug_description: &str) -> f64 {
+    let title_score = title.similarity(&Fingerprint::new(bug_title));
+    if description.is_empty() {
+        return title_score;
+    }
+    let description_score = description.similarity(&Fingerprint::new(bug_description));
+    TITLE_WEIGHT * title_score + (1.0 - TITLE_WEIGHT) * description_score
+}
+
+// Open bugs of the project resembling the given title and description, most similar first.
+// The description may be empty, in which case only the titles are compared.
+pub async fn find_similar(
+    pool: &SqlitePool,
+    project_id: Uuid,
+    title: &str,
+    description: &str,
+    limit: usize,
+) -> Result<Vec<SimilarBug>, sqlx::Error> {
+    let title = Fingerprint::new(title);
+    let description = Fingerprint::new(description);
+    if title.is_empty() && description.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Scored here rather than in SQL; a project has at most a few thousand open bugs
+    let candidates = sqlx::query_as::<_, (Uuid, String, String, Severity, String)>(
+        "SELECT id, title, description, severity, created_at FROM bugReport WHERE project_id = ? AND is_fixed = FALSE AND deleted_at IS NULL"
+    )
+    .bind(project_id.as_bytes().as_slice())
+    .fetch_all(pool)
+    .await?;
+
+    let mut similar: Vec<SimilarBug> = candidates
+        .into_iter()
+        .filter_map(|(bug_id, bug_title, bug_description, severity, created_at)| {
+            let score = score(&title, &description, &bug_title, &bug_description);
+            (score >= MIN_SCORE).then(|| SimilarBug {
+                bug_id,
+                title: bug_title,
+                severity,
+                created_at,
+                score: (score * 100.0).round() / 100.0,
+            })
+        })
+        .collect();
+
+    similar.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| b.created_at.cmp(&a.created_at)));
+    similar.truncate(limit);
+    Ok(similar)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn similarity(a: &str, b: &str) -> f64 {
+        Fingerprint::new(a).similarity(&Fingerprint::new(b))
+    }
+
+    #[test]
+    fn identical_texts_score_one_and_unrelated_texts_zero() {
+        assert_eq!(similarity("Login page crashes", "login PAGE crashes!"), 1.0);
+        assert_eq!(similarity("Login fails", "Export hangs"), 0.0);
+    }
+
+    #[test]
+    fn stop_words_and_punctuation_are_ignored() {
+        assert_eq!(similarity("The login is broken", "login broken"), 1.0);
+        assert!(Fingerprint::new("the, and: of").is_empty());
+    }
+
+    #[test]
+    fn trigrams_catch_different_word_endings() {
+        let close = similarity("App crash on save", "App crashes on save");
+        assert!(close > MIN_SCORE && close < 1.0, "{}", close);
+        assert!(close > similarity("App crash on save", "App hangs on load"));
+    }
+
+    #[test]
+    fn title_outweighs_description() {
+        let title = Fingerprint::new("Crash when saving");
+        let description = Fingerprint::new("Open a file and press save");
+        let same_title = score(&title, &description, "Crash when saving", "Qux zyx");
+        let same_description = score(&title, &description, "Qux zyx", "Open a file and press save");
+        assert!((same_title - TITLE_WEIGHT).abs() < 1e-9);
+        assert!((same_description - (1.0 - TITLE_WEIGHT)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn an_empty_description_compares_titles_only() {
+        let title = Fingerprint::new("Crash when saving");
+        assert_eq!(score(&title, &Fingerprint::new(""), "Crash when saving", "Something else entirely"), 1.0);
+    }
+}
@@ -0,0 +1,298 @@
+// TLS for the server.
+// When tls.cert_path and tls.key_path are set the server speaks HTTPS only. The certificate and key are read again
+// on SIGHUP, so a renewed certificate is picked up by new connections without a restart; if the new files cannot be
+// used the old certificate stays in place. `HttpsMiddleware` sends the Strict-Transport-Security header over HTTPS
+// and redirects requests arriving on the optional plain HTTP listener (tls.redirect_port) to HTTPS.
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{self, HeaderValue},
+    Error, HttpResponse,
+};
+use futures_util::future::LocalBoxFuture;
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::{self, CertifiedKey};
+use rustls::{Certificate, PrivateKey, ServerConfig};
+use std::fs::File;
+use std::future::{ready, Ready};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::{Arc, RwLock};
+use tracing::{error, info};
+
+use crate::config::{Config, TlsConfig};
+use crate::shutdown;
+
+// The server's certificate and key, handed to every new TLS connection and replaced by `reload`.
+pub struct ServerCertificate {
+    cert_path: PathBuf,
+    key_path: PathBuf,
+    current: RwLock<Arc<CertifiedKey>>,
+}
+
+impl ServerCertificate {
+    // Reads the certificate chain and key configured in `config`, which must have TLS enabled.
+    pub fn load(config: &TlsConfig) -> Result<Arc<Self>, String> {
+        let (Some(cert_path), Some(key_path)) = (&config.cert_path, &config.key_path) else {
+            return Err("tls.cert_path and tls.key_path are not set".to_string());
+        };
+        let key = read_certified_key(cert_path, key_path)?;
+        Ok(Arc::new(ServerCertificate {
+            cert_path: cert_path.clone(),
+            key_path: key_path.clone(),
+            current: RwLock::new(Arc::new(key)),
+        }))
+    }
+
+    // Reads the files again; connections already open keep the certificate they started with.
+    pub fn reload(&self) -> Result<(), String> {
+        let key = read_certified_key(&self.cert_path, &self.key_path)?;
+        *self.current.write().unwrap() = Arc::new(key);
+        Ok(())
+    }
+
+    // Server configuration presenting this certificate.
+    pub fn server_config(self: &Arc<Self>) -> ServerConfig {
+        ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_cert_resolver(self.clone())
+    }
+}
+
+impl ResolvesServerCert for ServerCertificate {
+    fn resolve(&self, _client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        Some(self.current.read().unwrap().clone())
+    }
+}
+
+fn read_certified_key(cert_path: &Path, key_path: &Path) -> Result<CertifiedKey, String> {
+    let open = |path: &Path| {
+        File::open(path).map(BufReader::new).map_err(|e| format!("Cannot read {}: {}", path.display(), e))
+    };
+
+    let chain: Vec<Certificate> = rustls_pemfile::certs(&mut open(cert_path)?)
+        .map_err(|e| format!("Invalid certificate file {}: {}", cert_path.display(), e))?
+        .into_iter()
+        .map(Certificate)
+        .collect();
+    if chain.is_empty() {
+        return Err(format!("No certificate found in {}", cert_path.display()));
+    }
+
+    let key = rustls_pemfile::read_all(&mut open(key_path)?)
+        .map_err(|e| format!("Invalid key file {}: {}", key_path.display(), e))?
+        .into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::PKCS8Key(der) | rustls_pemfile::Item::RSAKey(der) | rustls_pemfile::Item::ECKey(der) => {
+                Some(PrivateKey(der))
+            }
+            _ => None,
+        })
+        .ok_or_else(|| format!("No private key found in {}", key_path.display()))?;
+    let key = sign::any_supported_type(&key)
+        .map_err(|e| format!("Unsupported private key in {}: {}", key_path.display(), e))?;
+
+    Ok(CertifiedKey::new(chain, key))
+}
+
+// Starts the task reloading the certificate whenever the process receives SIGHUP, until shutdown.
+#[cfg(unix)]
+pub fn reload_on_hangup(certificate: Arc<ServerCertificate>) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(e) => {
+            error!(error = ?e, "Failed to listen for SIGHUP, certificates will not be reloaded");
+            return;
+        }
+    };
+    actix_web::rt::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = hangup.recv() => match certificate.reload() {
+                    Ok(()) => info!(cert = %certificate.cert_path.display(), "Reloaded the TLS certificate"),
+                    Err(e) => error!(error = %e, "Failed to reload the TLS certificate, keeping the current one"),
+                },
+                _ = shutdown::requested() => break,
+            }
+        }
+    });
+}
+
+#[cfg(not(unix))]
+pub fn reload_on_hangup(_certificate: Arc<ServerCertificate>) {}
+
+// Middleware for servers with TLS enabled: adds Strict-Transport-Security to responses sent over HTTPS and answers
+// requests that arrived over plain HTTP (the redirect listener) with a 308 redirect to the same URL over HTTPS.
+pub struct HttpsMiddleware {
+    https_port: u16,
+    hsts: Option<HeaderValue>,
+}
+
+impl HttpsMiddleware {
+    pub fn new(config: &Config) -> Self {
+        let hsts = (config.tls.hsts_max_age_secs > 0)
+            .then(|| HeaderValue::from_str(&format!("max-age={}", config.tls.hsts_max_age_secs)).unwrap());
+        HttpsMiddleware { https_port: config.server.port, hsts }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for HttpsMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = HttpsService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(HttpsService {
+            service: Rc::new(service),
+            https_port: self.https_port,
+            hsts: self.hsts.clone(),
+        }))
+    }
+}
+
+pub struct HttpsService<S> {
+    service: Rc<S>,
+    https_port: u16,
+    hsts: Option<HeaderValue>,
+}
+
+impl<S, B> Service<ServiceRequest> for HttpsService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        // Only connections accepted by the TLS listener are secure
+        if !req.app_config().secure() {
+            let location = https_url(&req, self.https_port);
+            let response = HttpResponse::PermanentRedirect().insert_header((header::LOCATION, location)).finish();
+            return Box::pin(async move { Ok(req.into_response(response).map_into_right_body()) });
+        }
+
+        let service = self.service.clone();
+        let hsts = self.hsts.clone();
+        Box::pin(async move {
+            let mut res = service.call(req).await?;
+            if let Some(hsts) = hsts {
+                res.headers_mut().insert(header::STRICT_TRANSPORT_SECURITY, hsts);
+            }
+            Ok(res.map_into_left_body())
+        })
+    }
+}
+
+// The URL of the request with the scheme changed to https and the port to the HTTPS port.
+fn https_url(req: &ServiceRequest, https_port: u16) -> String {
+    let connection = req.connection_info();
+    let host = connection.host();
+    // Drop the port of the redirect listener, leaving IPv6 literals such as [::1] intact
+    let host = match host.rsplit_once(':') {
+        Some((name, port)) if !port.ends_with(']') && port.bytes().all(|b| b.is_ascii_digit()) => name,
+        _ => host,
+    };
+    let path = req.uri().path_and_query().map_or("/", |path| path.as_str());
+    match https_port {
+        443 => format!("https://{}{}", host, path),
+        port => format!("https://{}:{}{}", host, port, path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::{call_service, init_service, TestRequest};
+    use actix_web::{web, App, HttpServer};
+
+    // A self-signed certificate for localhost and 127.0.0.1, and its key.
+    const TEST_CERT: &str = "src/testdata/cert.pem";
+    const TEST_KEY: &str = "src/testdata/key.pem";
+
+    fn tls_config() -> Config {
+        let mut config = Config::default();
+        config.server.port = 8443;
+        config.tls.cert_path = Some(PathBuf::from(TEST_CERT));
+        config.tls.key_path = Some(PathBuf::from(TEST_KEY));
+        config.tls.hsts_max_age_secs = 600;
+        config
+    }
+
+    #[test]
+    fn https_urls_keep_host_and_path_and_use_the_https_port() {
+        let url = |host: &str, uri: &str, https_port: u16| {
+            https_url(&TestRequest::with_uri(uri).insert_header((header::HOST, host)).to_srv_request(), https_port)
+        };
+        assert_eq!(url("example.com:8080", "/api/v1/bugs?page=2", 8443), "https://example.com:8443/api/v1/bugs?page=2");
+        assert_eq!(url("example.com:8080", "/api/v1/bugs?page=2", 443), "https://example.com/api/v1/bugs?page=2");
+        assert_eq!(url("example.com", "/", 443), "https://example.com/");
+        assert_eq!(url("[::1]:8080", "/healthz", 8443), "https://[::1]:8443/healthz");
+        assert_eq!(url("[::1]", "/healthz", 443), "https://[::1]/healthz");
+    }
+
+    #[actix_web::test]
+    async fn plain_http_requests_are_redirected_without_hsts() {
+        let app = init_service(
+            App::new()
+                .route("/bugs", web::get().to(HttpResponse::Ok))
+                .wrap(HttpsMiddleware::new(&tls_config())),
+        )
+        .await;
+        let req = TestRequest::get().uri("/bugs?page=2").insert_header((header::HOST, "example.com:8080")).to_request();
+        let res = call_service(&app, req).await;
+
+        assert_eq!(res.status(), 308);
+        assert_eq!(res.headers().get(header::LOCATION).unwrap(), "https://example.com:8443/bugs?page=2");
+        assert!(!res.headers().contains_key(header::STRICT_TRANSPORT_SECURITY));
+    }
+
+    #[actix_web::test]
+    async fn only_responses_over_tls_carry_hsts() {
+        let config = tls_config();
+        let certificate = ServerCertificate::load(&config.tls).unwrap();
+        let server = HttpServer::new(move || {
+            App::new()
+                .route("/bugs", web::get().to(HttpResponse::Ok))
+                .wrap(HttpsMiddleware::new(&config))
+        })
+        .workers(1)
+        .disable_signals()
+        .bind_rustls_021(("127.0.0.1", 0), certificate.server_config())
+        .unwrap()
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let [https_address, http_address] = server.addrs()[..] else { panic!("expected two listeners") };
+        actix_web::rt::spawn(server.run());
+
+        let client = reqwest::Client::builder()
+            .add_root_certificate(reqwest::Certificate::from_pem(&std::fs::read(TEST_CERT).unwrap()).unwrap())
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap();
+
+        let res = client.get(format!("https://localhost:{}/bugs", https_address.port())).send().await.unwrap();
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers()[header::STRICT_TRANSPORT_SECURITY.as_str()], "max-age=600");
+
+        let res = client.get(format!("http://localhost:{}/bugs", http_address.port())).send().await.unwrap();
+        assert_eq!(res.status(), 308);
+        assert_eq!(res.headers()[header::LOCATION.as_str()], "https://localhost:8443/bugs");
+        assert!(!res.headers().contains_key(header::STRICT_TRANSPORT_SECURITY.as_str()));
+    }
+}
@@ -0,0 +1,36 @@
+// The trash: deleting a bug only sets its `deleted_at`, which hides it from every query. Trashed bugs can be
+// restored until an hourly task purges them for good, retention.trash_days (30 by default) after their deletion.
+use actix_web::rt::task::JoinHandle;
+use sqlx::SqlitePool;
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::shutdown;
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(3600);
+
+// Permanently deletes the bugs trashed more than `retention_days` ago, together with their labels,
+// watchers, mentions, links and notifications. Returns how many bugs were purged.
+pub async fn purge(pool: &SqlitePool, retention_days: i64) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM bugReport WHERE deleted_at IS NOT NULL AND deleted_at < datetime('now', ?)")
+        .bind(format!("-{} days", retention_days))
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+// Starts the hourly task purging the bugs trashed more than `retention_days` ago. It stops on shutdown.
+pub fn start_purge(pool: SqlitePool, retention_days: i64) -> JoinHandle<()> {
+    actix_web::rt::spawn(async move {
+        loop {
+            match purge(&pool, retention_days).await {
+                Ok(purged) if purged > 0 => info!(purged, retention_days, "Purged bugs from the trash"),
+                Ok(_) => {}
+                Err(e) => error!(error = ?e, "Trash purge error"),
+            }
+            if !shutdown::pause(PURGE_INTERVAL).await {
+                break;
+            }
+        }
+    })
+}
@@ -0,0 +1,74 @@
+// Bug watchers: users who follow a bug and want to hear about its changes. Reporters and assignees are
+// added automatically from the bug events (see `auto_watch`); anyone else watches through `/bugs/{id}/watch`.
+// Notification channels use `watcher_ids` to find out who to tell about a change.
+use sqlx::{Executor, Sqlite, SqlitePool};
+use uuid::Uuid;
+
+use crate::events::{DomainEvent, EventKind};
+use crate::models::{SimpleUser, WatchedBug};
+
+// Adds a watcher. Watching a bug that is already watched does nothing.
+pub async fn watch<'e, E: Executor<'e, Database = Sqlite>>(executor: E, bug_id: Uuid, user_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT OR IGNORE INTO bugWatcher (bug_id, user_id) VALUES (?, ?)")
+        .bind(bug_id.as_bytes().to_vec())
+        .bind(user_id.as_bytes().to_vec())
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+pub async fn unwatch(pool: &SqlitePool, bug_id: Uuid, user_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM bugWatcher WHERE bug_id = ? AND user_id = ?")
+        .bind(bug_id.as_bytes().as_slice())
+        .bind(user_id.as_bytes().as_slice())
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+// Watchers of a bug, in alphabetical order.
+pub async fn watchers(pool: &SqlitePool, bug_id: Uuid) -> Result<Vec<SimpleUser>, sqlx::Error> {
+    sqlx::query_as::<_, SimpleUser>(
+        "SELECT u.id, u.username FROM bugWatcher w JOIN users u ON u.id = w.user_id WHERE w.bug_id = ? ORDER BY u.username"
+    )
+    .bind(bug_id.as_bytes().as_slice())
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn watcher_ids(pool: &SqlitePool, bug_id: Uuid) -> Result<Vec<Uuid>, sqlx::Error> {
+    sqlx::query_scalar::<_, Uuid>("SELECT user_id FROM bugWatcher WHERE bug_id = ?")
+        .bind(bug_id.as_bytes().as_slice())
+        .fetch_all(pool)
+        .await
+}
+
+// Bugs a user watches, most recently watched first.
+pub async fn watched_bugs(pool: &SqlitePool, user_id: Uuid) -> Result<Vec<WatchedBug>, sqlx::Error> {
+    sqlx::query_as::<_, WatchedBug>(
+        "SELECT b.id, b.project_id, b.title, b.description, b.reported_by, b.fixed_by, b.severity, b.priority, \
+         b.is_fixed, b.created_at, b.version, w.created_at AS watching_since \
+         FROM bugWatcher w JOIN bugReport b ON b.id = w.bug_id WHERE w.user_id = ? AND b.deleted_at IS NULL ORDER BY w.created_at DESC, b.title"
+    )
+    .bind(user_id.as_bytes().as_slice())
+    .fetch_all(pool)
+    .await
+}
+
+// Makes the reporter of a new bug and the assignee of an assigned bug watch it.
+pub async fn auto_watch(pool: &SqlitePool, event: &DomainEvent) -> Result<(), sqlx::Error> {
+    let id_field = |field: &str| event.data[field].as_str().and_then(|id| Uuid::parse_str(id).ok());
+    let users = match event.event {
+        EventKind::BugCreated => vec![id_field("reported_by"), id_field("fixed_by")],
+        EventKind::BugAssigned => vec![id_field("fixed_by")],
+        _ => return Ok(()),
+    };
+    let Some(bug_id) = id_field("id") else {
+        return Ok(());
+    };
+
+    for user_id in users.into_iter().flatten() {
+        watch(pool, bug_id, user_id).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,359 @@
+// Outgoing webhooks: a persistent delivery queue in the `webhookDelivery` table and a background worker
+// that POSTs each queued event to its subscriber, retrying failures with exponential backoff.
+// Bodies are signed with HMAC-SHA256 using the webhook's secret; receivers should recompute
+// `sha256=<hex>` over the raw body and compare it with the `X-Webhook-Signature` header.
+// Webhooks are only sent to public addresses, unless `webhooks.allow_private_hosts` is set: a URL pointing into
+// the server's own network (loopback, private ranges, the cloud metadata address) is refused when the webhook is
+// created and again when each delivery is sent.
+use actix_web::rt::task::JoinHandle;
+use futures_util::future::join_all;
+use hmac::{Hmac, Mac};
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use reqwest::header::CONTENT_TYPE;
+use sha2::Sha256;
+use sqlx::{FromRow, SqlitePool};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info};
+use url::{Host, Url};
+use uuid::Uuid;
+
+use crate::config::WebhooksConfig;
+use crate::events::{DomainEvent, EventKind};
+use crate::models::WebhookDelivery;
+use crate::shutdown;
+
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+pub const DELIVERY_HEADER: &str = "X-Webhook-Delivery";
+
+// Columns of `webhookDelivery` in the order of the `WebhookDelivery` model.
+pub const DELIVERY_COLUMNS: &str =
+    "id, webhook_id, event, payload, status, attempts, next_attempt_at, last_status_code, last_error, created_at, delivered_at";
+
+// A delivery is retried after 10s, 20s, 40s, ... (capped at an hour) and given up after MAX_ATTEMPTS.
+const MAX_ATTEMPTS: i64 = 8;
+const BASE_RETRY_DELAY_SECS: i64 = 10;
+const MAX_RETRY_DELAY_SECS: i64 = 3600;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const BATCH_SIZE: i64 = 20;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Shared by every delivery so connections to the same receiver are reused. Redirects are not followed.
+fn client(config: &WebhooksConfig) -> reqwest::Client {
+    let builder = reqwest::Client::builder()
+        .user_agent("g21-bugtracker-webhooks")
+        .timeout(REQUEST_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none());
+    let builder = if config.allow_private_hosts { builder } else { builder.dns_resolver(Arc::new(PublicResolver)) };
+    builder.build().expect("Failed to build the webhook HTTP client")
+}
+
+// Resolves host names leaving out the addresses webhooks may not be sent to, so that a name checked when the
+// webhook was created cannot be pointed at an internal address afterwards.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| is_public(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} does not resolve to a public address", name.as_str()).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+// Whether webhooks may be sent to the address: not loopback, private, link-local (which includes the cloud
+// metadata address 169.254.169.254), shared, unspecified, broadcast or multicast.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || first == 0
+                // 100.64.0.0/10, carrier-grade NAT and some cloud internal networks
+                || (first == 100 && second & 0xc0 == 64))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                || ip.is_unique_local()
+                || ip.is_unicast_link_local()),
+        },
+    }
+}
+
+// Checks that webhooks may be sent to `url`: unless private hosts are allowed, every address its host resolves
+// to must be public. The error says why not.
+pub async fn check_url(url: &str, config: &WebhooksConfig) -> Result<(), String> {
+    if config.allow_private_hosts {
+        return Ok(());
+    }
+    let url = Url::parse(url).map_err(|e| format!("invalid URL: {}", e))?;
+    let port = url.port_or_known_default().unwrap_or(80);
+    let addrs: Vec<IpAddr> = match url.host() {
+        Some(Host::Ipv4(ip)) => vec![ip.into()],
+        Some(Host::Ipv6(ip)) => vec![ip.into()],
+        Some(Host::Domain(domain)) => tokio::net::lookup_host((domain, port))
+            .await
+            .map_err(|e| format!("cannot resolve {}: {}", domain, e))?
+            .map(|addr| addr.ip())
+            .collect(),
+        None => return Err("URL has no host".to_string()),
+    };
+    match addrs.iter().find(|ip| !is_public(**ip)) {
+        Some(ip) => Err(format!("{} is not a public address", ip)),
+        None if addrs.is_empty() => Err("host does not resolve to any address".to_string()),
+        None => Ok(()),
+    }
+}
+
+// A queued delivery joined with the webhook it goes to.
+#[derive(Debug, FromRow)]
+struct DueDelivery {
+    id: Uuid,
+    url: String,
+    secret: String,
+    event: String,
+    payload: String,
+    attempts: i64,
+}
+
+// Whether a comma-separated subscription list includes the event.
+fn is_subscribed(events: &str, kind: EventKind) -> bool {
+    events.split(',').any(|event| event == "*" || event == kind.as_str())
+}
+
+// Queues a delivery of the event for every webhook of its project subscribed to it.
+pub async fn enqueue(pool: &SqlitePool, event: &DomainEvent) -> Result<usize, sqlx::Error> {
+    let webhooks = sqlx::query_as::<_, (Uuid, String)>("SELECT id, events FROM webhook WHERE project_id = ?")
+        .bind(event.project_id.as_bytes().as_slice())
+        .fetch_all(pool)
+        .await?;
+
+    let payload = serde_json::to_string(event).unwrap_or_default();
+    let mut queued = 0;
+    for (webhook_id, events) in webhooks {
+        if !is_subscribed(&events, event.event) {
+            continue;
+        }
+        sqlx::query("INSERT INTO webhookDelivery (id, webhook_id, event, payload) VALUES (?, ?, ?, ?)")
+            .bind(Uuid::new_v4().as_bytes().as_slice())
+            .bind(webhook_id.as_bytes().as_slice())
+            .bind(event.event.as_str())
+            .bind(&payload)
+            .execute(pool)
+            .await?;
+        queued += 1;
+    }
+    Ok(queued)
+}
+
+// Queues a fresh copy of an earlier delivery, keeping the original in the log. Returns None if it does not exist.
+pub async fn redeliver(pool: &SqlitePool, delivery_id: Uuid) -> Result<Option<WebhookDelivery>, sqlx::Error> {
+    sqlx::query_as::<_, WebhookDelivery>(&format!(
+        "INSERT INTO webhookDelivery (id, webhook_id, event, payload) \
+         SELECT ?, webhook_id, event, payload FROM webhookDelivery WHERE id = ? RETURNING {}",
+        DELIVERY_COLUMNS
+    ))
+    .bind(Uuid::new_v4().as_bytes().as_slice())
+    .bind(delivery_id.as_bytes().as_slice())
+    .fetch_optional(pool)
+    .await
+}
+
+// Starts the background task delivering queued events. Deliveries left over from a previous run are picked up too.
+// On shutdown it sends whatever is already due and stops; retries scheduled for later stay queued.
+pub fn start_worker(pool: SqlitePool, config: WebhooksConfig) -> JoinHandle<()> {
+    let client = client(&config);
+    actix_web::rt::spawn(async move {
+        loop {
+            if let Err(e) = deliver_due(&pool, &client, &config).await {
+                error!(error = ?e, "Webhook delivery error");
+            }
+            if !shutdown::pause(POLL_INTERVAL).await {
+                break;
+            }
+        }
+
+        let mut flushed = 0;
+        loop {
+            match deliver_due(&pool, &client, &config).await {
+                Ok(0) => break,
+                Ok(sent) => flushed += sent,
+                Err(e) => {
+                    error!(error = ?e, "Webhook delivery error");
+                    break;
+                }
+            }
+        }
+        info!(flushed, "Webhook worker stopped");
+    })
+}
+
+// Sends every delivery whose next attempt is due, concurrently, and records the outcome of each.
+// Returns how many were attempted.
+async fn deliver_due(pool: &SqlitePool, client: &reqwest::Client, config: &WebhooksConfig) -> Result<usize, sqlx::Error> {
+    let due = sqlx::query_as::<_, DueDelivery>(
+        "SELECT d.id, w.url, w.secret, d.event, d.payload, d.attempts \
+         FROM webhookDelivery d JOIN webhook w ON w.id = d.webhook_id \
+         WHERE d.status = 'pending' AND d.next_attempt_at <= CURRENT_TIMESTAMP \
+         ORDER BY d.next_attempt_at LIMIT ?"
+    )
+    .bind(BATCH_SIZE)
+    .fetch_all(pool)
+    .await?;
+
+    let sent = join_all(due.into_iter().map(|delivery| async move {
+        let outcome = send(client, config, &delivery).await;
+        (delivery, outcome)
+    }))
+    .await;
+
+    let attempted = sent.len();
+    for (delivery, outcome) in sent {
+        record_attempt(pool, &delivery, outcome).await?;
+    }
+    Ok(attempted)
+}
+
+// POSTs the payload and returns the response status code. A URL that is no longer allowed is not contacted.
+async fn send(client: &reqwest::Client, config: &WebhooksConfig, delivery: &DueDelivery) -> Result<u16, String> {
+    check_url(&delivery.url, config).await.map_err(|e| format!("refused to send: {}", e))?;
+    let response = client
+        .post(&delivery.url)
+        .header(CONTENT_TYPE, "application/json")
+        .header(EVENT_HEADER, &delivery.event)
+        .header(DELIVERY_HEADER, delivery.id.to_string())
+        .header(SIGNATURE_HEADER, format!("sha256={}", sign(&delivery.secret, delivery.payload.as_bytes())))
+        .body(delivery.payload.clone())
+        .send()
+        .await
+        .map_err(|e| format!("could not send request: {}", e))?;
+    Ok(response.status().as_u16())
+}
+
+// Marks the delivery as delivered on a 2xx response, otherwise schedules a retry or gives up.
+async fn record_attempt(pool: &SqlitePool, delivery: &DueDelivery, outcome: Result<u16, String>) -> Result<(), sqlx::Error> {
+    let attempts = delivery.attempts + 1;
+    let (status_code, error) = match outcome {
+        Ok(code) if (200..300).contains(&code) => {
+            sqlx::query(
+                "UPDATE webhookDelivery SET status = 'delivered', attempts = ?, last_status_code = ?, last_error = NULL, \
+                 delivered_at = CURRENT_TIMESTAMP WHERE id = ?"
+            )
+            .bind(attempts)
+            .bind(code)
+            .bind(delivery.id.as_bytes().as_slice())
+            .execute(pool)
+            .await?;
+            return Ok(());
+        }
+        Ok(code) => (Some(code), format!("HTTP {}", code)),
+        Err(e) => (None, e),
+    };
+
+    let delay = (BASE_RETRY_DELAY_SECS << (attempts - 1).min(20)).min(MAX_RETRY_DELAY_SECS);
+    let status = if attempts >= MAX_ATTEMPTS { "failed" } else { "pending" };
+    sqlx::query(
+        "UPDATE webhookDelivery SET status = ?, attempts = ?, last_status_code = ?, last_error = ?, \
+         next_attempt_at = datetime('now', ?) WHERE id = ?"
+    )
+    .bind(status)
+    .bind(attempts)
+    .bind(status_code)
+    .bind(&error)
+    .bind(format!("+{} seconds", delay))
+    .bind(delivery.id.as_bytes().as_slice())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+// Hex-encoded HMAC-SHA256 of the body.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+// Random secret for webhooks created without one.
+pub fn generate_secret() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_is_hex_hmac_sha256_of_the_body() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn signature_depends_on_secret_and_body() {
+        let signature = sign("secret", br#"{"event":"bug.created"}"#);
+        assert_eq!(signature.len(), 64);
+        assert_ne!(signature, sign("other secret", br#"{"event":"bug.created"}"#));
+        assert_ne!(signature, sign("secret", br#"{"event":"bug.updated"}"#));
+    }
+
+    #[test]
+    fn subscriptions_match_listed_events_or_the_wildcard() {
+        assert!(is_subscribed("*", EventKind::BugDeleted));
+        assert!(is_subscribed("bug.created,bug.fixed", EventKind::BugFixed));
+        assert!(!is_subscribed("bug.created", EventKind::BugUpdated));
+    }
+
+    #[test]
+    fn internal_addresses_are_not_public() {
+        for ip in [
+            "127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254", "100.64.0.1", "0.0.0.0",
+            "255.255.255.255", "224.0.0.1", "::1", "::", "fd00::1", "fe80::1", "::ffff:127.0.0.1", "::ffff:169.254.169.254",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{} counted as public", ip);
+        }
+        for ip in ["93.184.216.34", "1.1.1.1", "172.32.0.1", "100.128.0.1", "2606:4700:4700::1111", "::ffff:8.8.8.8"] {
+            assert!(is_public(ip.parse().unwrap()), "{} counted as internal", ip);
+        }
+    }
+
+    #[actix_web::test]
+    async fn urls_of_internal_hosts_are_refused_unless_allowed() {
+        let strict = WebhooksConfig::default();
+        for url in [
+            "http://127.0.0.1:9000/hook",
+            "http://[::1]/hook",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://localhost:9000/hook",
+            "https://10.0.0.5/",
+        ] {
+            assert!(check_url(url, &strict).await.is_err(), "{} was allowed", url);
+        }
+        assert_eq!(check_url("http://169.254.169.254/", &strict).await.unwrap_err(), "169.254.169.254 is not a public address");
+        assert!(check_url("https://93.184.216.34/hook", &strict).await.is_ok());
+
+        let open = WebhooksConfig { allow_private_hosts: true };
+        assert!(check_url("http://127.0.0.1:9000/hook", &open).await.is_ok());
+    }
+}